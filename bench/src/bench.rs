@@ -1,8 +1,32 @@
 use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
-use fast_surface_nets::{surface_nets, SignedDistance, SurfaceNetsBuffer};
+use fast_surface_nets::{
+    surface_nets, surface_nets_small, surface_nets_with_config, SignedDistance, SurfaceNetsBuffer,
+    SurfaceNetsConfig,
+};
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
 use std::f32::consts::PI;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Counts heap allocations, to compare the allocation counts of the output buffers.
+struct CountingAllocator;
+
+static NUM_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        NUM_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
 type SampleShape = ConstShape3u32<18, 18, 18>;
 
@@ -71,7 +95,151 @@ fn bench_sphere(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_sine_sdf, bench_sphere, bench_empty_space);
+fn bench_sparse_sphere(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_sparse_sphere");
+    let mut samples = [Sd8(i8::MAX); SampleShape::USIZE];
+    for i in 0u32..(SampleShape::SIZE) {
+        let p = into_domain(16, SampleShape::delinearize(i));
+        // Most of the chunk is empty space.
+        samples[i as usize] = small_sphere_sdf(p);
+    }
+
+    // Do a single run first to allocate the buffer to the right size.
+    let mut buffer = SurfaceNetsBuffer::default();
+    surface_nets(&samples, &SampleShape {}, [0; 3], [17; 3], &mut buffer);
+    let num_triangles = buffer.indices.len() / 3;
+
+    group.bench_with_input(
+        BenchmarkId::from_parameter(format!("tris={}", num_triangles)),
+        &(),
+        |b, _| {
+            b.iter(|| surface_nets(&samples, &SampleShape {}, [0; 3], [17; 3], &mut buffer));
+        },
+    );
+    group.finish();
+}
+
+fn bench_watertight_sphere(c: &mut Criterion) {
+    type LargeShape = ConstShape3u32<66, 66, 66>;
+
+    let mut group = c.benchmark_group("bench_watertight_sphere");
+    let mut samples = vec![Sd8(i8::MAX); LargeShape::USIZE];
+    for i in 0u32..(LargeShape::SIZE) {
+        let p = into_domain(64, LargeShape::delinearize(i));
+        // Big enough to be clipped by every boundary plane.
+        samples[i as usize] = clipped_sphere_sdf(p);
+    }
+
+    for parallel_boundary_faces in [false, true] {
+        let config = SurfaceNetsConfig {
+            generate_boundary_faces: true,
+            parallel_boundary_faces,
+            ..Default::default()
+        };
+
+        // Do a single run first to allocate the buffer to the right size.
+        let mut buffer = SurfaceNetsBuffer::default();
+        surface_nets_with_config(
+            &samples,
+            &LargeShape {},
+            [0; 3],
+            [65; 3],
+            config,
+            &mut buffer,
+        );
+        let num_triangles = buffer.indices.len() / 3;
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!(
+                "parallel={} tris={}",
+                parallel_boundary_faces, num_triangles
+            )),
+            &(),
+            |b, _| {
+                b.iter(|| {
+                    surface_nets_with_config(
+                        &samples,
+                        &LargeShape {},
+                        [0; 3],
+                        [65; 3],
+                        config,
+                        &mut buffer,
+                    )
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_tiny_chunks(c: &mut Criterion) {
+    type TinyShape = ConstShape3u32<8, 8, 8>;
+    const NUM_CHUNKS: usize = 512;
+
+    let mut group = c.benchmark_group("bench_tiny_chunks");
+    let mut samples = [Sd8(i8::MAX); TinyShape::USIZE];
+    for i in 0u32..(TinyShape::SIZE) {
+        let p = into_domain(7, TinyShape::delinearize(i));
+        samples[i as usize] = medium_sphere_sdf(p);
+    }
+
+    // Keep every chunk's mesh, like a world made of tiny chunks would.
+    let mesh_vec = |meshes: &mut Vec<SurfaceNetsBuffer>| {
+        meshes.clear();
+        for _ in 0..NUM_CHUNKS {
+            let mut buffer = SurfaceNetsBuffer::default();
+            surface_nets(&samples, &TinyShape {}, [0; 3], [7; 3], &mut buffer);
+            meshes.push(buffer);
+        }
+    };
+    let mut scratch = SurfaceNetsBuffer::default();
+    let mut mesh_small = |meshes: &mut Vec<_>| {
+        meshes.clear();
+        for _ in 0..NUM_CHUNKS {
+            let config = SurfaceNetsConfig::default();
+            meshes.push(surface_nets_small(
+                &samples,
+                &TinyShape {},
+                [0; 3],
+                [7; 3],
+                config,
+                &mut scratch,
+            ));
+        }
+    };
+
+    let mut vec_meshes = Vec::with_capacity(NUM_CHUNKS);
+    let mut small_meshes = Vec::with_capacity(NUM_CHUNKS);
+    for (name, num_allocations) in [
+        ("vec", count_allocations(|| mesh_vec(&mut vec_meshes))),
+        ("small", count_allocations(|| mesh_small(&mut small_meshes))),
+    ] {
+        println!(
+            "bench_tiny_chunks/{name}: {:.1} allocations per chunk",
+            num_allocations as f32 / NUM_CHUNKS as f32
+        );
+    }
+
+    group.bench_function("vec", |b| b.iter(|| mesh_vec(&mut vec_meshes)));
+    group.bench_function("small", |b| b.iter(|| mesh_small(&mut small_meshes)));
+    group.finish();
+}
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = NUM_ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    NUM_ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+criterion_group!(
+    benches,
+    bench_sine_sdf,
+    bench_sphere,
+    bench_empty_space,
+    bench_sparse_sphere,
+    bench_watertight_sphere,
+    bench_tiny_chunks
+);
 criterion_main!(benches);
 
 // The higher the frequency (n) the more surface area to mesh.
@@ -87,6 +255,24 @@ fn sphere_sdf([x, y, z]: [f32; 3]) -> Sd8 {
     val.into()
 }
 
+fn medium_sphere_sdf([x, y, z]: [f32; 3]) -> Sd8 {
+    let val = (x * x + y * y + z * z) - 0.5;
+
+    val.into()
+}
+
+fn small_sphere_sdf([x, y, z]: [f32; 3]) -> Sd8 {
+    let val = (x * x + y * y + z * z) - 0.05;
+
+    val.into()
+}
+
+fn clipped_sphere_sdf([x, y, z]: [f32; 3]) -> Sd8 {
+    let val = (x * x + y * y + z * z) - 1.2;
+
+    val.into()
+}
+
 fn into_domain(array_dim: u32, [x, y, z]: [u32; 3]) -> [f32; 3] {
     [
         (2.0 * x as f32 / array_dim as f32) - 1.0,
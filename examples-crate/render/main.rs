@@ -1,7 +1,7 @@
 use fast_surface_nets::glam::{Vec2, Vec3A};
 use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
 use fast_surface_nets::{
-    surface_nets, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig,
+    surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig, UvProjection,
 };
 
 use bevy::{
@@ -118,24 +118,19 @@ fn sdf_to_mesh(
     }
 
     let mut buffer = SurfaceNetsBuffer::default();
-
-    if watertight {
-        let config = SurfaceNetsConfig {
-            generate_boundary_faces: true,
-        };
-        surface_nets_with_config(
-            &samples,
-            &SampleShape {},
-            [0; 3],
-            [33; 3],
-            config,
-            &mut buffer,
-        );
-    } else {
-        surface_nets(&samples, &SampleShape {}, [0; 3], [33; 3], &mut buffer);
-    }
-
-    let num_vertices = buffer.positions.len();
+    let config = SurfaceNetsConfig {
+        generate_boundary_faces: watertight,
+        generate_uvs: Some(UvProjection::Box),
+        ..Default::default()
+    };
+    surface_nets_with_config(
+        &samples,
+        &SampleShape {},
+        [0; 3],
+        [33; 3],
+        config,
+        &mut buffer,
+    );
 
     let mut render_mesh = Mesh::new(
         PrimitiveTopology::TriangleList,
@@ -151,7 +146,7 @@ fn sdf_to_mesh(
     );
     render_mesh.insert_attribute(
         Mesh::ATTRIBUTE_UV_0,
-        VertexAttributeValues::Float32x2(vec![[0.0; 2]; num_vertices]),
+        VertexAttributeValues::Float32x2(buffer.uvs.clone()),
     );
     render_mesh.insert_indices(Indices::U32(buffer.indices.clone()));
 
@@ -0,0 +1,206 @@
+use crate::topology::{triangle_neighbors, NO_NEIGHBOR};
+use crate::SurfaceNetsBuffer;
+
+use glam::{Vec2, Vec3A};
+use std::collections::HashMap;
+
+/// The gap left around every chart in the atlas, in mesh units (voxels), to avoid bleeding between charts.
+const CHART_PADDING: f32 = 0.5;
+
+/// Charts are never allowed to bend this far, since projecting them onto a plane could then flip triangles.
+const MAX_CHART_ANGLE: f32 = 0.49 * std::f32::consts::PI;
+
+/// Computes a non-overlapping UV parameterization of `mesh` (a texture atlas), suitable for baking lightmaps.
+///
+/// The mesh is segmented into charts by flood-filling across triangle edges, adding a triangle to a chart only if its face
+/// normal is within `chart_angle_threshold` radians of the normal of the triangle that started the chart. The threshold is
+/// clamped just below 90 degrees. Each chart is projected onto the plane perpendicular to its starting normal, then all charts
+/// are packed into rows inside the unit square with a single uniform scale, so texel density is the same everywhere.
+///
+/// Vertices on the seams between charts are split, so this appends vertices to `mesh` (all other per-vertex attributes are
+/// copied) and rewrites `indices`. Existing vertices keep their index, so `cube_to_index` stays valid. The result is written
+/// to `mesh.uvs`.
+///
+/// A chart is a height field over its projection plane as long as the surface doesn't fold back over itself within the
+/// threshold, which holds for the smooth surfaces produced by Surface Nets.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{compute_atlas_uvs, surface_nets, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<14, 14, 14>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i);
+///     let [x, y, z] = [x as f32 - 6.5, y as f32 - 6.5, z as f32 - 6.5];
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 4.5;
+/// }
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [13; 3], &mut mesh);
+///
+/// compute_atlas_uvs(&mut mesh, 45f32.to_radians());
+/// assert_eq!(mesh.uvs.len(), mesh.positions.len());
+/// assert!(mesh.uvs.iter().flatten().all(|&c| (0.0..=1.0).contains(&c)));
+///
+/// // Check that no two UV triangles overlap with the separating axis test.
+/// let uv_triangles: Vec<[[f32; 2]; 3]> = mesh
+///     .indices
+///     .chunks(3)
+///     .map(|t| [mesh.uvs[t[0] as usize], mesh.uvs[t[1] as usize], mesh.uvs[t[2] as usize]])
+///     .collect();
+/// let separated_along = |a: &[[f32; 2]; 3], b: &[[f32; 2]; 3], [ex, ey]: [f32; 2]| {
+///     let project = |t: &[[f32; 2]; 3]| t.map(|[x, y]| x * -ey + y * ex);
+///     let (pa, pb) = (project(a), project(b));
+///     let max = |p: [f32; 3]| p[0].max(p[1]).max(p[2]);
+///     let min = |p: [f32; 3]| p[0].min(p[1]).min(p[2]);
+///     max(pa) <= min(pb) + 1e-5 || max(pb) <= min(pa) + 1e-5
+/// };
+/// let separated = |a: &[[f32; 2]; 3], b: &[[f32; 2]; 3]| {
+///     [a, b].iter().any(|t| {
+///         (0..3).any(|i| {
+///             let [p, q] = [t[i], t[(i + 1) % 3]];
+///             separated_along(a, b, [q[0] - p[0], q[1] - p[1]])
+///         })
+///     })
+/// };
+/// for (i, a) in uv_triangles.iter().enumerate() {
+///     for b in uv_triangles[i + 1..].iter() {
+///         assert!(separated(a, b));
+///     }
+/// }
+/// ```
+pub fn compute_atlas_uvs(mesh: &mut SurfaceNetsBuffer, chart_angle_threshold: f32) {
+    let num_triangles = mesh.indices.len() / 3;
+    let face_normals: Vec<Vec3A> = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|tri| {
+            let [p0, p1, p2] = [0, 1, 2].map(|i| Vec3A::from(mesh.positions[tri[i] as usize]));
+            (p1 - p0).cross(p2 - p0).normalize_or_zero()
+        })
+        .collect();
+
+    // Grow the charts.
+    let neighbors = triangle_neighbors(&mesh.indices);
+    let min_cos = chart_angle_threshold.min(MAX_CHART_ANGLE).cos();
+    let mut triangle_charts = vec![NO_CHART; num_triangles];
+    let mut chart_axes = Vec::new();
+    let mut stack = Vec::new();
+    for seed in 0..num_triangles {
+        if triangle_charts[seed] != NO_CHART {
+            continue;
+        }
+        let chart = chart_axes.len() as u32;
+        let axis = face_normals[seed];
+        chart_axes.push(axis);
+        triangle_charts[seed] = chart;
+        stack.push(seed as u32);
+        while let Some(t) = stack.pop() {
+            for &n in neighbors[t as usize].iter() {
+                if n != NO_NEIGHBOR
+                    && triangle_charts[n as usize] == NO_CHART
+                    && face_normals[n as usize].dot(axis) >= min_cos
+                {
+                    triangle_charts[n as usize] = chart;
+                    stack.push(n);
+                }
+            }
+        }
+    }
+
+    // Split vertices that are shared by multiple charts. The first chart to claim a vertex keeps the original.
+    let mut vertex_charts = vec![NO_CHART; mesh.positions.len()];
+    let mut split_vertices = HashMap::<(u32, u32), u32>::new();
+    for (t, &chart) in triangle_charts.iter().enumerate() {
+        for corner in 0..3 {
+            let v = mesh.indices[3 * t + corner];
+            let v_chart = vertex_charts[v as usize];
+            if v_chart == NO_CHART {
+                vertex_charts[v as usize] = chart;
+            } else if v_chart != chart {
+                let copy = *split_vertices
+                    .entry((v, chart))
+                    .or_insert_with(|| mesh.duplicate_vertex(v));
+                if copy as usize == vertex_charts.len() {
+                    vertex_charts.push(chart);
+                }
+                mesh.indices[3 * t + corner] = copy;
+            }
+        }
+    }
+
+    // Project every chart onto its plane.
+    let chart_bases: Vec<(Vec3A, Vec3A)> = chart_axes
+        .iter()
+        .map(|&axis| {
+            let axis = if axis == Vec3A::ZERO { Vec3A::Z } else { axis };
+            let (u, _) = axis.any_orthonormal_pair();
+            (u, axis.cross(u))
+        })
+        .collect();
+    let mut chart_mins = vec![Vec2::splat(f32::INFINITY); chart_axes.len()];
+    let mut chart_maxs = vec![Vec2::splat(f32::NEG_INFINITY); chart_axes.len()];
+    let local_uvs: Vec<Vec2> = mesh
+        .positions
+        .iter()
+        .zip(vertex_charts.iter())
+        .map(|(&p, &chart)| {
+            if chart == NO_CHART {
+                return Vec2::ZERO;
+            }
+            let (u, v) = chart_bases[chart as usize];
+            let p = Vec3A::from(p);
+            let uv = Vec2::new(p.dot(u), p.dot(v));
+            chart_mins[chart as usize] = chart_mins[chart as usize].min(uv);
+            chart_maxs[chart as usize] = chart_maxs[chart as usize].max(uv);
+            uv
+        })
+        .collect();
+
+    // Pack the padded chart rectangles into rows, tallest first.
+    let sizes: Vec<Vec2> = chart_mins
+        .iter()
+        .zip(chart_maxs.iter())
+        .map(|(&min, &max)| (max - min).max(Vec2::ZERO) + CHART_PADDING)
+        .collect();
+    let total_area: f32 = sizes.iter().map(|s| s.x * s.y).sum();
+    let row_width = sizes
+        .iter()
+        .fold(total_area.sqrt(), |width, s| width.max(s.x));
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| sizes[b].y.total_cmp(&sizes[a].y));
+    let mut offsets = vec![Vec2::ZERO; sizes.len()];
+    let mut cursor = Vec2::ZERO;
+    let mut row_height = 0.0f32;
+    let mut used = Vec2::ZERO;
+    for chart in order {
+        let size = sizes[chart];
+        if cursor.x > 0.0 && cursor.x + size.x > row_width {
+            cursor = Vec2::new(0.0, cursor.y + row_height);
+            row_height = 0.0;
+        }
+        offsets[chart] = cursor;
+        cursor.x += size.x;
+        row_height = row_height.max(size.y);
+        used = used.max(cursor + Vec2::new(0.0, size.y));
+    }
+    let scale = 1.0 / used.max_element().max(f32::EPSILON);
+
+    mesh.uvs.clear();
+    mesh.uvs.extend(
+        local_uvs
+            .iter()
+            .zip(vertex_charts.iter())
+            .map(|(&uv, &chart)| {
+                if chart == NO_CHART {
+                    return [0.0; 2];
+                }
+                let chart = chart as usize;
+                let packed = uv - chart_mins[chart] + offsets[chart] + 0.5 * CHART_PADDING;
+                (packed * scale).into()
+            }),
+    );
+}
+
+const NO_CHART: u32 = u32::MAX;
@@ -0,0 +1,309 @@
+use crate::{surface_nets_with_config, SignedDistance, SurfaceNetsBuffer, SurfaceNetsConfig};
+
+use ndshape::Shape;
+
+/// One chunk to mesh with [`mesh_chunks`]: the arguments of [`surface_nets_with_config`], plus where the chunk is in the world.
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkInput<'a, T, S> {
+    /// The samples of the chunk.
+    pub sdf: &'a [T],
+    /// The shape of `sdf`.
+    pub shape: &'a S,
+    /// The minimal sample of the extent to mesh.
+    pub min: [u32; 3],
+    /// The maximal sample of the extent to mesh.
+    pub max: [u32; 3],
+    /// If set, this is added to every position, moving the mesh from chunk-local coordinates into world space.
+    pub world_offset: Option<[f32; 3]>,
+}
+
+impl<T, S> ChunkInput<'_, T, S> {
+    fn move_to_world(&self, mesh: &mut SurfaceNetsBuffer) {
+        if let Some(offset) = self.world_offset {
+            let offset_point = |p: [f32; 3]| [0, 1, 2].map(|i| p[i] + offset[i]);
+            for p in mesh.positions.iter_mut() {
+                *p = offset_point(*p);
+            }
+            mesh.aabb = mesh
+                .aabb
+                .map(|(min, max)| (offset_point(min), offset_point(max)));
+        }
+    }
+}
+
+/// Meshes every chunk in `chunks` with the same `config`, and returns their meshes in the same order.
+///
+/// With the `rayon` feature, the chunks are meshed in parallel. Each thread meshes into its own scratch buffer, which is reused
+/// for all of its chunks, and the returned meshes are copies of its mesh data that are no bigger than they need to be. The
+/// per-cube maps ([`cube_to_index`](SurfaceNetsBuffer::cube_to_index) and [`surface_mask`](SurfaceNetsBuffer::surface_mask))
+/// stay in the scratch buffer, so they're empty in the returned meshes. Otherwise the chunks are meshed one after another, into
+/// a single scratch buffer.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{mesh_chunks, surface_nets_with_config, ChunkInput, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A big sphere, split into 4x4x4 chunks of 16 cubes each, which overlap by one sample on each side.
+/// let chunk_origins: Vec<[u32; 3]> = (0..64).map(|i| [i % 4, (i / 4) % 4, i / 16].map(|c| 16 * c)).collect();
+/// let sdfs: Vec<Vec<f32>> = chunk_origins
+///     .iter()
+///     .map(|origin| {
+///         (0..ChunkShape::SIZE)
+///             .map(|i| {
+///                 let [x, y, z] = ChunkShape::delinearize(i);
+///                 let [x, y, z] = [x + origin[0], y + origin[1], z + origin[2]].map(|c| c as f32 - 32.5);
+///                 (x * x + y * y + z * z).sqrt() - 25.0
+///             })
+///             .collect()
+///     })
+///     .collect();
+/// let chunks: Vec<ChunkInput<f32, ChunkShape>> = sdfs
+///     .iter()
+///     .zip(chunk_origins.iter())
+///     .map(|(sdf, origin)| ChunkInput {
+///         sdf,
+///         shape: &ChunkShape {},
+///         min: [0; 3],
+///         max: [17; 3],
+///         world_offset: Some(origin.map(|c| c as f32)),
+///     })
+///     .collect();
+///
+/// let config = SurfaceNetsConfig::default();
+/// let meshes = mesh_chunks(&chunks, config);
+///
+/// // Each mesh is the same as meshing its chunk alone, in the same order, and moved into world space.
+/// assert_eq!(meshes.len(), chunks.len());
+/// for ((mesh, sdf), origin) in meshes.iter().zip(sdfs.iter()).zip(chunk_origins.iter()) {
+///     let mut expected = SurfaceNetsBuffer::default();
+///     surface_nets_with_config(sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut expected);
+///     let to_world = |p: [f32; 3]| [0, 1, 2].map(|i| p[i] + origin[i] as f32);
+///     for p in expected.positions.iter_mut() {
+///         *p = to_world(*p);
+///     }
+///     assert_eq!(mesh.positions, expected.positions);
+///     assert_eq!(mesh.aabb, expected.aabb.map(|(min, max)| (to_world(min), to_world(max))));
+///     assert_eq!(mesh.normals, expected.normals);
+///     assert_eq!(mesh.indices, expected.indices);
+///     assert_eq!(mesh.surface_points, expected.surface_points);
+/// }
+/// assert!(meshes.iter().any(|mesh| mesh.indices.is_empty()));
+/// assert!(meshes.iter().any(|mesh| !mesh.indices.is_empty()));
+/// assert!(meshes.iter().all(|mesh| mesh.cube_to_index.is_empty()));
+/// ```
+pub fn mesh_chunks<T, S>(
+    chunks: &[ChunkInput<T, S>],
+    config: SurfaceNetsConfig,
+) -> Vec<SurfaceNetsBuffer>
+where
+    T: SignedDistance + Sync,
+    S: Shape<3, Coord = u32> + Sync,
+{
+    let mesh_chunk = |scratch: &mut SurfaceNetsBuffer, chunk: &ChunkInput<T, S>| {
+        surface_nets_with_config(
+            chunk.sdf,
+            chunk.shape,
+            chunk.min,
+            chunk.max,
+            config,
+            scratch,
+        );
+        let mut mesh = copy_mesh(scratch);
+        chunk.move_to_world(&mut mesh);
+        mesh
+    };
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        chunks
+            .par_iter()
+            .map_init(SurfaceNetsBuffer::default, mesh_chunk)
+            .collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        let mut scratch = SurfaceNetsBuffer::default();
+        chunks
+            .iter()
+            .map(|chunk| mesh_chunk(&mut scratch, chunk))
+            .collect()
+    }
+}
+
+// Copies the mesh in `scratch`, leaving out the maps over every cube of the extent, which are only needed while meshing.
+fn copy_mesh(scratch: &SurfaceNetsBuffer) -> SurfaceNetsBuffer {
+    SurfaceNetsBuffer {
+        positions: scratch.positions.clone(),
+        normals: scratch.normals.clone(),
+        face_normals: scratch.face_normals.clone(),
+        indices: scratch.indices.clone(),
+        quad_indices: scratch.quad_indices.clone(),
+        line_indices: scratch.line_indices.clone(),
+        boundary_indices: scratch.boundary_indices.clone(),
+        boundary_indices_start: scratch.boundary_indices_start,
+        uvs: scratch.uvs.clone(),
+        barycentrics: scratch.barycentrics.clone(),
+        vertex_materials: scratch.vertex_materials.clone(),
+        triangle_materials: scratch.triangle_materials.clone(),
+        aabb: scratch.aabb,
+        num_surface_cubes: scratch.num_surface_cubes,
+        num_boundary_vertices: scratch.num_boundary_vertices,
+        surface_points: scratch.surface_points.clone(),
+        surface_strides: scratch.surface_strides.clone(),
+        cube_to_index: Vec::new(),
+        surface_mask: Vec::new(),
+    }
+}
+
+/// Meshes every chunk in `chunks` into the buffer at the same position in `outputs`, in parallel, like [`mesh_chunks`] but
+/// reusing the caller's buffers, so their allocations carry over from one batch to the next (e.g. across frames).
+///
+/// Each buffer is filled by exactly one task, with the same result as meshing its chunk alone with
+/// [`surface_nets_with_config`] (plus the world offset), independent of the number of threads and how the work is scheduled.
+/// The chunks are only read, so `T` and `S` need to be `Sync` to be shared between the threads, and nothing needs to be
+/// `Send`. This runs on the current rayon thread pool.
+///
+/// Panics if `chunks` and `outputs` have different lengths.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_batch, surface_nets_with_config, ChunkInput, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // 100 chunks, each with a sphere of a different size.
+/// let sdfs: Vec<Vec<f32>> = (0..100)
+///     .map(|c| {
+///         (0..ChunkShape::SIZE)
+///             .map(|i| {
+///                 let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///                 (x * x + y * y + z * z).sqrt() - c as f32 / 10.0
+///             })
+///             .collect()
+///     })
+///     .collect();
+/// let chunks: Vec<ChunkInput<f32, ChunkShape>> = sdfs
+///     .iter()
+///     .map(|sdf| ChunkInput {
+///         sdf,
+///         shape: &ChunkShape {},
+///         min: [0; 3],
+///         max: [17; 3],
+///         world_offset: None,
+///     })
+///     .collect();
+/// let config = SurfaceNetsConfig::default();
+///
+/// let mut sequential = vec![SurfaceNetsBuffer::default(); chunks.len()];
+/// for (sdf, output) in sdfs.iter().zip(sequential.iter_mut()) {
+///     surface_nets_with_config(sdf, &ChunkShape {}, [0; 3], [17; 3], config, output);
+/// }
+/// let mut parallel = vec![SurfaceNetsBuffer::default(); chunks.len()];
+/// surface_nets_batch(&chunks, config, &mut parallel);
+///
+/// let bytes = |mesh: &SurfaceNetsBuffer| {
+///     let floats = mesh.positions.iter().chain(mesh.normals.iter()).flatten().flat_map(|c| c.to_le_bytes());
+///     let ints = mesh.indices.iter().chain(mesh.surface_strides.iter()).chain(mesh.cube_to_index.iter());
+///     floats.chain(ints.flat_map(|i| i.to_le_bytes())).collect::<Vec<u8>>()
+/// };
+/// for (s, p) in sequential.iter().zip(parallel.iter()) {
+///     assert_eq!(bytes(s), bytes(p));
+///     assert_eq!(s.surface_points, p.surface_points);
+/// }
+///
+/// // Meshing the same batch again reuses the allocations.
+/// let allocations: Vec<*const [f32; 3]> = parallel.iter().map(|mesh| mesh.positions.as_ptr()).collect();
+/// surface_nets_batch(&chunks, config, &mut parallel);
+/// assert!(parallel.iter().zip(allocations).all(|(mesh, allocation)| mesh.positions.as_ptr() == allocation));
+/// ```
+#[cfg(feature = "rayon")]
+pub fn surface_nets_batch<T, S>(
+    chunks: &[ChunkInput<T, S>],
+    config: SurfaceNetsConfig,
+    outputs: &mut [SurfaceNetsBuffer],
+) where
+    T: SignedDistance + Sync,
+    S: Shape<3, Coord = u32> + Sync,
+{
+    use rayon::prelude::*;
+
+    assert_eq!(
+        chunks.len(),
+        outputs.len(),
+        "every chunk needs its own output buffer"
+    );
+    outputs
+        .par_iter_mut()
+        .zip(chunks.par_iter())
+        .for_each(|(output, chunk)| {
+            surface_nets_with_config(chunk.sdf, chunk.shape, chunk.min, chunk.max, config, output);
+            chunk.move_to_world(output);
+        });
+}
+
+/// Meshes every extent `(min, max)` in `extents` of one shared `sdf`, in parallel, and returns their meshes in the same order.
+/// This is [`mesh_chunks`] for chunks that are extents of a single large array, e.g. generated terrain: the positions are in the
+/// coordinates of the whole array, so neighboring chunks (overlapping by one sample) fit together without an offset.
+///
+/// Each thread meshes into its own scratch buffer, so `cube_to_index` is only allocated once per thread, for the largest
+/// extent it meshes, rather than once per chunk. Like with [`mesh_chunks`], it isn't copied into the returned meshes.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_par, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type TerrainShape = ConstShape3u32<34, 34, 34>;
+///
+/// let mut sdf = [1.0; TerrainShape::USIZE];
+/// for i in 0u32..TerrainShape::SIZE {
+///     let [x, y, z] = TerrainShape::delinearize(i).map(|c| c as f32 - 16.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 12.0;
+/// }
+///
+/// // 2x2x2 chunks of 16 cubes each.
+/// let extents: Vec<([u32; 3], [u32; 3])> = (0..8)
+///     .map(|i| {
+///         let min = [i % 2, (i / 2) % 2, i / 4].map(|c| 16 * c);
+///         (min, min.map(|c| c + 17))
+///     })
+///     .collect();
+/// let config = SurfaceNetsConfig::default();
+/// let meshes = surface_nets_par(&sdf, &TerrainShape {}, &extents, config);
+///
+/// assert_eq!(meshes.len(), extents.len());
+/// for (mesh, &(min, max)) in meshes.iter().zip(extents.iter()) {
+///     let mut expected = SurfaceNetsBuffer::default();
+///     surface_nets_with_config(&sdf, &TerrainShape {}, min, max, config, &mut expected);
+///     assert_eq!(mesh.positions, expected.positions);
+///     assert_eq!(mesh.indices, expected.indices);
+///     assert!(!mesh.indices.is_empty());
+/// }
+/// ```
+#[cfg(feature = "rayon")]
+pub fn surface_nets_par<T, S>(
+    sdf: &[T],
+    shape: &S,
+    extents: &[([u32; 3], [u32; 3])],
+    config: SurfaceNetsConfig,
+) -> Vec<SurfaceNetsBuffer>
+where
+    T: SignedDistance + Sync,
+    S: Shape<3, Coord = u32> + Sync,
+{
+    let chunks: Vec<ChunkInput<T, S>> = extents
+        .iter()
+        .map(|&(min, max)| ChunkInput {
+            sdf,
+            shape,
+            min,
+            max,
+            world_offset: None,
+        })
+        .collect();
+    mesh_chunks(&chunks, config)
+}
@@ -0,0 +1,315 @@
+use crate::{SignedDistance, NULL_VERTEX};
+
+use glam::Vec2;
+use ndshape::Shape;
+
+/// The output buffers used by [`surface_nets_2d`]. These buffers can be reused to avoid reallocating memory.
+#[derive(Default, Clone)]
+pub struct SurfaceNets2dBuffer {
+    /// The contour positions.
+    pub positions: Vec<[f32; 2]>,
+    /// The contour normals.
+    ///
+    /// The normals are **not** normalized, since that is done most efficiently on the GPU.
+    pub normals: Vec<[f32; 2]>,
+    /// Line segment indices, two per segment. Each segment runs counterclockwise around the interior, so the interior is on its
+    /// left and the normals point to its right.
+    pub indices: Vec<u32>,
+
+    /// Local 2D array coordinates of every square that intersects the contour.
+    pub surface_points: Vec<[u32; 2]>,
+    /// Stride of every square that intersects the contour.
+    pub surface_strides: Vec<u32>,
+    /// Used to map back from square to vertex index, or [`NULL_VERTEX`] if the square has no vertex. Squares are numbered like
+    /// the samples of an array of the extent's size, with X varying fastest, i.e. the square with minimal corner `[x, y]` is at
+    /// `(y - min_y) * size_x + (x - min_x)`.
+    pub square_to_index: Vec<u32>,
+}
+
+impl SurfaceNets2dBuffer {
+    /// Clears all of the buffers, but keeps the memory allocated for reuse.
+    fn reset(&mut self, num_squares: usize) {
+        self.positions.clear();
+        self.normals.clear();
+        self.indices.clear();
+        self.surface_points.clear();
+        self.surface_strides.clear();
+
+        // Just make sure this buffer is big enough, whether or not we've used it before.
+        self.square_to_index.resize(num_squares, NULL_VERTEX);
+    }
+
+    /// Chains the segments in `indices` into polylines of vertex indices, e.g. for outlines. Each polyline runs the same way as
+    /// its segments. A closed loop ends with its first vertex again, and an open polyline runs between two points on the
+    /// border of the extent.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape2u32};
+    /// use fast_surface_nets::{surface_nets_2d, SurfaceNets2dBuffer};
+    ///
+    /// type ChunkShape = ConstShape2u32<34, 34>;
+    ///
+    /// // Two circles, one of which is cut by the max X border of the extent.
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y] = ChunkShape::delinearize(i).map(|c| c as f32);
+    ///     let first = ((x - 8.5).powi(2) + (y - 16.5).powi(2)).sqrt() - 6.0;
+    ///     let second = ((x - 28.5).powi(2) + (y - 16.5).powi(2)).sqrt() - 6.0;
+    ///     sdf[i as usize] = first.min(second);
+    /// }
+    /// let mut buffer = SurfaceNets2dBuffer::default();
+    /// surface_nets_2d(&sdf, &ChunkShape {}, [0; 2], [30; 2], &mut buffer);
+    ///
+    /// let polylines = buffer.polylines();
+    /// assert_eq!(polylines.len(), 2);
+    /// let (closed, open): (Vec<_>, Vec<_>) = polylines.iter().partition(|p| p.first() == p.last());
+    /// assert_eq!((closed.len(), open.len()), (1, 1));
+    ///
+    /// // Every segment is in exactly one polyline.
+    /// let num_segments: usize = polylines.iter().map(|p| p.len() - 1).sum();
+    /// assert_eq!(num_segments, buffer.indices.len() / 2);
+    /// for pair in open[0].windows(2) {
+    ///     assert!(buffer.indices.chunks(2).any(|s| s == pair));
+    /// }
+    ///
+    /// // The open polyline starts and ends at the last column of squares.
+    /// for v in [open[0][0], *open[0].last().unwrap()] {
+    ///     assert_eq!(buffer.surface_points[v as usize][0], 29);
+    /// }
+    /// ```
+    pub fn polylines(&self) -> Vec<Vec<u32>> {
+        let num_vertices = self.positions.len();
+        let mut outgoing = vec![Vec::new(); num_vertices];
+        let mut num_incoming = vec![0; num_vertices];
+        for segment in self.indices.chunks_exact(2).rev() {
+            outgoing[segment[0] as usize].push(segment[1]);
+            num_incoming[segment[1] as usize] += 1;
+        }
+
+        // Follow the open polylines from their first vertices, then the closed loops from any vertex.
+        let open_starts = (0..num_vertices).filter(|&v| outgoing[v].len() > num_incoming[v]);
+        let starts: Vec<usize> = open_starts.chain(0..num_vertices).collect();
+        let mut polylines = Vec::new();
+        for start in starts {
+            while !outgoing[start].is_empty() {
+                let mut polyline = vec![start as u32];
+                let mut v = start;
+                while let Some(next) = outgoing[v].pop() {
+                    polyline.push(next);
+                    v = next as usize;
+                }
+                polylines.push(polyline);
+            }
+        }
+        polylines
+    }
+}
+
+/// The Naive Surface Nets algorithm in 2D, which extracts the contour of a 2D [signed distance
+/// field](https://en.wikipedia.org/wiki/Signed_distance_function) as line segments.
+///
+/// This works like [`surface_nets`](crate::surface_nets) on a slice of the samples: every square of 4 samples with a sign change
+/// gets a vertex at the centroid of its edge crossings, and every crossed edge gets a segment between the vertices of the two
+/// squares that share it. Like in 3D, no segments are made on the positive boundaries of the extent from `min` to `max`, so the
+/// contours of neighboring chunks fit together seamlessly.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape2u32};
+/// use fast_surface_nets::{surface_nets_2d, SurfaceNets2dBuffer};
+///
+/// type ChunkShape = ConstShape2u32<34, 34>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y] = ChunkShape::delinearize(i).map(|c| c as f32 - 16.5);
+///     sdf[i as usize] = (x * x + y * y).sqrt() - 12.0;
+/// }
+/// let mut buffer = SurfaceNets2dBuffer::default();
+/// surface_nets_2d(&sdf, &ChunkShape {}, [0; 2], [33; 2], &mut buffer);
+///
+/// // Every vertex is near the circle, with a normal pointing away from its center.
+/// assert!(!buffer.positions.is_empty());
+/// for (p, n) in buffer.positions.iter().zip(buffer.normals.iter()) {
+///     let [x, y] = [p[0] - 16.5, p[1] - 16.5];
+///     assert!(((x * x + y * y).sqrt() - 12.0).abs() < 0.05);
+///     assert!(n[0] * x + n[1] * y > 0.0);
+/// }
+///
+/// // The segments form a single closed loop: every vertex starts one segment and ends another.
+/// let num_vertices = buffer.positions.len();
+/// assert_eq!(buffer.indices.len(), 2 * num_vertices);
+/// let mut next = vec![None; num_vertices];
+/// for segment in buffer.indices.chunks(2) {
+///     assert!(next[segment[0] as usize].replace(segment[1]).is_none());
+/// }
+/// let (mut v, mut loop_len) = (0, 0);
+/// loop {
+///     v = next[v].unwrap() as usize;
+///     loop_len += 1;
+///     if v == 0 {
+///         break;
+///     }
+/// }
+/// assert_eq!(loop_len, num_vertices);
+///
+/// // The loop runs counterclockwise around the interior.
+/// let signed_area: f32 = buffer
+///     .indices
+///     .chunks(2)
+///     .map(|s| {
+///         let [a, b] = [buffer.positions[s[0] as usize], buffer.positions[s[1] as usize]];
+///         0.5 * (a[0] * b[1] - b[0] * a[1])
+///     })
+///     .sum();
+/// assert!((signed_area / (std::f32::consts::PI * 12.0 * 12.0) - 1.0).abs() < 0.01);
+/// ```
+pub fn surface_nets_2d<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    output: &mut SurfaceNets2dBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<2, Coord = u32>,
+{
+    assert!((shape.linearize(max) as usize) < sdf.len());
+
+    let size = [max[0] - min[0], max[1] - min[1]];
+    output.reset((size[0] * size[1]) as usize);
+
+    estimate_contour(sdf, shape, min, max, output);
+    make_all_segments(sdf, shape, min, max, output);
+}
+
+// Find all vertex positions and normals, and fill `square_to_index`.
+fn estimate_contour<T, S>(
+    sdf: &[T],
+    shape: &S,
+    [minx, miny]: [u32; 2],
+    [maxx, maxy]: [u32; 2],
+    output: &mut SurfaceNets2dBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<2, Coord = u32>,
+{
+    let corner_strides = SQUARE_CORNERS.map(|corner| shape.linearize(corner));
+    let mut square = 0;
+    for y in miny..maxy {
+        for x in minx..maxx {
+            let stride = shape.linearize([x, y]);
+            let corners = corner_strides.map(|c| sdf[(stride + c) as usize]);
+            let p = Vec2::new(x as f32, y as f32);
+            if estimate_contour_in_square(corners, p, output) {
+                output.square_to_index[square] = output.positions.len() as u32 - 1;
+                output.surface_points.push([x, y]);
+                output.surface_strides.push(stride);
+            } else {
+                output.square_to_index[square] = NULL_VERTEX;
+            }
+            square += 1;
+        }
+    }
+}
+
+// Consider the grid-aligned square where `p` is the minimal corner. Find a point inside this square that is approximately on the
+// contour.
+//
+// This is done by estimating, for each square edge, where the contour crosses the edge (if it does at all). Then the estimated
+// contour point is the average of these edge crossings.
+fn estimate_contour_in_square<T>(corners: [T; 4], p: Vec2, output: &mut SurfaceNets2dBuffer) -> bool
+where
+    T: SignedDistance,
+{
+    let num_negative = corners.iter().filter(|d| d.is_negative()).count();
+    if num_negative == 0 || num_negative == 4 {
+        // No crossings.
+        return false;
+    }
+
+    // The signed distance values at each corner of this square, in `0b_yx` order.
+    let dists = corners.map(|d| d.into() - d.surface_offset());
+
+    let mut count = 0;
+    let mut sum = Vec2::ZERO;
+    for &[corner1, corner2] in SQUARE_EDGES.iter() {
+        let d1 = dists[corner1];
+        let d2 = dists[corner2];
+        if (d1 < 0.0) != (d2 < 0.0) {
+            count += 1;
+            let interp = d1 / (d1 - d2);
+            sum += (1.0 - interp) * SQUARE_CORNER_VECTORS[corner1]
+                + interp * SQUARE_CORNER_VECTORS[corner2];
+        }
+    }
+    let c = sum / count as f32;
+
+    output.positions.push((p + c).into());
+    output.normals.push(sdf_gradient_2d(&dists, c).into());
+
+    true
+}
+
+// The gradient of the bilinear interpolation of `dists` at `s`.
+fn sdf_gradient_2d(dists: &[f32; 4], s: Vec2) -> Vec2 {
+    let neg = Vec2::ONE - s;
+    Vec2::new(
+        neg.y * (dists[0b01] - dists[0b00]) + s.y * (dists[0b11] - dists[0b10]),
+        neg.x * (dists[0b10] - dists[0b00]) + s.x * (dists[0b11] - dists[0b01]),
+    )
+}
+
+// For every edge that crosses the contour, make a segment between the vertices of the two squares that share it.
+fn make_all_segments<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    output: &mut SurfaceNets2dBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<2, Coord = u32>,
+{
+    let xy_strides = [shape.linearize([1, 0]), shape.linearize([0, 1])];
+    let square_strides = [1, (max[0] - min[0]) as usize];
+    for (&p, &p_stride) in output
+        .surface_points
+        .iter()
+        .zip(output.surface_strides.iter())
+    {
+        let p_square = (p[1] - min[1]) as usize * square_strides[1] + (p[0] - min[0]) as usize;
+
+        // Do edges parallel with the X and Y axes, at the minimal corner of the square.
+        for axis in 0..2 {
+            let other = 1 - axis;
+            if p[other] == min[other] || p[axis] == max[axis] - 1 {
+                continue;
+            }
+            let d1 = sdf[p_stride as usize].is_negative();
+            let d2 = sdf[(p_stride + xy_strides[axis]) as usize].is_negative();
+            if d1 == d2 {
+                continue;
+            }
+
+            // The squares at `p` and `p - other` share the edge. Walking from one to the other keeps the negative end of the edge
+            // on the left.
+            let v1 = output.square_to_index[p_square];
+            let v2 = output.square_to_index[p_square - square_strides[other]];
+            let forward = (axis == 0) != d1;
+            if forward {
+                output.indices.extend_from_slice(&[v1, v2]);
+            } else {
+                output.indices.extend_from_slice(&[v2, v1]);
+            }
+        }
+    }
+}
+
+const SQUARE_CORNERS: [[u32; 2]; 4] = [[0, 0], [1, 0], [0, 1], [1, 1]];
+const SQUARE_CORNER_VECTORS: [Vec2; 4] = [
+    Vec2::from_array([0.0, 0.0]),
+    Vec2::from_array([1.0, 0.0]),
+    Vec2::from_array([0.0, 1.0]),
+    Vec2::from_array([1.0, 1.0]),
+];
+const SQUARE_EDGES: [[usize; 2]; 4] = [[0b00, 0b01], [0b00, 0b10], [0b01, 0b11], [0b10, 0b11]];
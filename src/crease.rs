@@ -0,0 +1,169 @@
+use crate::topology::{triangle_neighbors, NO_NEIGHBOR};
+use crate::SurfaceNetsBuffer;
+
+use glam::Vec3A;
+
+/// Splits the vertices of `mesh` along its creases, i.e. the edges between triangles whose face normals differ by more than
+/// `angle_degrees`, and recomputes the normals of every smoothing group, like the "smoothing angle" of a DCC tool. This keeps
+/// the hard edges of blocky shapes sharp, while smooth regions stay smooth.
+///
+/// Around each vertex, the triangles are grouped by flood-filling across the edges that aren't creases. The first group to
+/// use a vertex keeps it, and every other group gets a copy (with all of its per-vertex attributes), so existing vertices keep
+/// their index and `cube_to_index` stays valid. The normal of each vertex is then the normalized, area-weighted sum of the
+/// face normals of its group, following the winding of the triangles. `normals` is filled in even if it was empty; vertices
+/// that aren't used by any triangle keep their normal, or get a zero normal.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{sdf, split_by_crease_angle, surface_nets, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut field = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     field[i as usize] = sdf::cuboid([x, y, z], [5.2, 5.2, 5.2]);
+/// }
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&field, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// let num_vertices = mesh.positions.len();
+///
+/// split_by_crease_angle(&mut mesh, 30.0);
+/// assert_eq!(mesh.normals.len(), mesh.positions.len());
+///
+/// // The vertices on the edges of the box are split, since each of them is shared by several faces.
+/// assert!(mesh.positions.len() > num_vertices);
+/// let on_box_edge = |p: [f32; 3]| p.iter().filter(|&&c| (c - 8.5).abs() > 4.5).count() >= 2;
+/// for copy in num_vertices..mesh.positions.len() {
+///     assert!(on_box_edge(mesh.positions[copy]));
+/// }
+///
+/// // Away from the edges, the flat faces still share their vertices, whose normals are exactly perpendicular to the face.
+/// let inside_face = |p: [f32; 3]| p.iter().filter(|&&c| (c - 8.5).abs() < 3.5).count() == 2;
+/// for (&p, &n) in mesh.positions[..num_vertices].iter().zip(mesh.normals.iter()) {
+///     if inside_face(p) {
+///         assert_eq!(n.iter().filter(|&&c| c == 0.0).count(), 2);
+///     }
+/// }
+///
+/// // And every triangle is smooth across the corners it shares with its group.
+/// let face_normal = |tri: &[u32]| {
+///     let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[tri[i] as usize]);
+///     let [u, v] = [[b[0] - a[0], b[1] - a[1], b[2] - a[2]], [c[0] - a[0], c[1] - a[1], c[2] - a[2]]];
+///     let n = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+///     let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+///     n.map(|c| c / length)
+/// };
+/// for tri in mesh.indices.chunks(3) {
+///     let f = face_normal(tri);
+///     for &v in tri {
+///         let n = mesh.normals[v as usize];
+///         assert!(f[0] * n[0] + f[1] * n[1] + f[2] * n[2] > 30f32.to_radians().cos() - 1e-4);
+///     }
+/// }
+/// ```
+pub fn split_by_crease_angle(mesh: &mut SurfaceNetsBuffer, angle_degrees: f32) {
+    let face_normals: Vec<Vec3A> = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|tri| {
+            let [p0, p1, p2] = [0, 1, 2].map(|i| Vec3A::from(mesh.positions[tri[i] as usize]));
+            (p1 - p0).cross(p2 - p0)
+        })
+        .collect();
+
+    // Link the corners of neighboring triangles at the two ends of every edge that isn't a crease.
+    let min_cos = angle_degrees.to_radians().cos();
+    let neighbors = triangle_neighbors(&mesh.indices);
+    let mut groups = CornerGroups::new(mesh.indices.len());
+    for (t, tri_neighbors) in neighbors.iter().enumerate() {
+        for (e, &n) in tri_neighbors.iter().enumerate() {
+            if n == NO_NEIGHBOR || (n as usize) < t {
+                continue;
+            }
+            let n = n as usize;
+            if face_normals[t]
+                .normalize_or_zero()
+                .dot(face_normals[n].normalize_or_zero())
+                < min_cos
+            {
+                continue;
+            }
+            for corner in [3 * t + e, 3 * t + (e + 1) % 3] {
+                let v = mesh.indices[corner];
+                if let Some(i) = mesh.indices[3 * n..3 * n + 3].iter().position(|&w| w == v) {
+                    groups.union(corner, 3 * n + i);
+                }
+            }
+        }
+    }
+
+    // Give every group its own vertex. The first group to use a vertex keeps the original.
+    let mut vertex_groups = vec![NO_GROUP; mesh.positions.len()];
+    let mut group_vertices = vec![NO_GROUP; mesh.indices.len()];
+    for corner in 0..mesh.indices.len() {
+        let v = mesh.indices[corner];
+        let group = groups.find(corner) as u32;
+        if group_vertices[group as usize] == NO_GROUP {
+            group_vertices[group as usize] = if vertex_groups[v as usize] == NO_GROUP {
+                v
+            } else {
+                mesh.duplicate_vertex(v)
+            };
+            if group_vertices[group as usize] as usize == vertex_groups.len() {
+                vertex_groups.push(group);
+            } else {
+                vertex_groups[v as usize] = group;
+            }
+        }
+        mesh.indices[corner] = group_vertices[group as usize];
+    }
+
+    // Recompute the normals of the vertices that are used.
+    let mut normal_sums = vec![Vec3A::ZERO; mesh.positions.len()];
+    for (tri, &normal) in mesh.indices.chunks_exact(3).zip(face_normals.iter()) {
+        for &v in tri {
+            normal_sums[v as usize] += normal;
+        }
+    }
+    mesh.normals.resize(mesh.positions.len(), [0.0; 3]);
+    for ((normal, sum), &group) in mesh
+        .normals
+        .iter_mut()
+        .zip(normal_sums.iter())
+        .zip(vertex_groups.iter())
+    {
+        if group != NO_GROUP {
+            *normal = sum.normalize_or_zero().into();
+        }
+    }
+}
+
+const NO_GROUP: u32 = u32::MAX;
+
+// A union-find forest over the corners of the triangles.
+struct CornerGroups {
+    parents: Vec<usize>,
+}
+
+impl CornerGroups {
+    fn new(num_corners: usize) -> Self {
+        Self {
+            parents: (0..num_corners).collect(),
+        }
+    }
+
+    fn find(&mut self, mut corner: usize) -> usize {
+        while self.parents[corner] != corner {
+            self.parents[corner] = self.parents[self.parents[corner]];
+            corner = self.parents[corner];
+        }
+        corner
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        // The smaller corner is the root, so groups are numbered by their first corner.
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::SurfaceNetsBuffer;
+
+use std::collections::{HashMap, HashSet};
+
+/// The differences between two meshes of the same chunk, as returned by [`mesh_diff`].
+///
+/// Vertices are matched by the cube they belong to (their `surface_strides` entry), so vertex indices refer to `old` for
+/// removed things and to `new` for everything else.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MeshDelta {
+    /// The strides of the cubes that only have a vertex in `old`.
+    pub removed_strides: Vec<u32>,
+    /// The strides of the cubes that only have a vertex in `new`.
+    pub added_strides: Vec<u32>,
+    /// The indices (in `new`) of the vertices in both meshes whose position or normal changed.
+    pub moved_vertices: Vec<u32>,
+    /// The indices (into `old.indices / 3`) of the triangles that are only in `old`.
+    pub removed_triangles: Vec<u32>,
+    /// The indices (into `new.indices / 3`) of the triangles that are only in `new`.
+    pub added_triangles: Vec<u32>,
+}
+
+impl MeshDelta {
+    /// Whether the meshes are the same, up to the order of their vertices and triangles.
+    pub fn is_empty(&self) -> bool {
+        self.removed_strides.is_empty()
+            && self.added_strides.is_empty()
+            && self.moved_vertices.is_empty()
+            && self.removed_triangles.is_empty()
+            && self.added_triangles.is_empty()
+    }
+}
+
+/// Compares two meshes of the same chunk, e.g. before and after an edit, so that only the changed parts need to be uploaded to
+/// the GPU.
+///
+/// A triangle is the same in both meshes if its corners belong to the same cubes, in the same winding order. Cubes with
+/// several vertices (like skirts) are matched by the order of their vertices.
+///
+/// Panics if either mesh doesn't have `surface_strides` for every vertex.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{mesh_diff, surface_nets, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+/// }
+/// let mut old = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut old);
+/// let mut new = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut new);
+/// assert!(mesh_diff(&old, &new).is_empty());
+///
+/// // Carve out a single voxel on the surface.
+/// let edited = [8, 8, 3];
+/// sdf[ChunkShape::linearize(edited) as usize] = 1.0;
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut new);
+/// let delta = mesh_diff(&old, &new);
+/// assert!(!delta.is_empty());
+///
+/// // Only the cubes that have the edited voxel as a corner changed.
+/// let touches_edit = |stride: u32| {
+///     let cube = ChunkShape::delinearize(stride);
+///     (0..3).all(|i| cube[i] <= edited[i] && edited[i] <= cube[i] + 1)
+/// };
+/// assert!(delta.removed_strides.iter().all(|&s| touches_edit(s)));
+/// assert!(delta.added_strides.iter().all(|&s| touches_edit(s)));
+/// assert!(delta.moved_vertices.iter().all(|&v| touches_edit(new.surface_strides[v as usize])));
+///
+/// // So the delta is much smaller than the mesh.
+/// assert!(delta.moved_vertices.len() + delta.added_strides.len() <= 8);
+/// assert!(0 < delta.added_triangles.len() && delta.added_triangles.len() < 50);
+/// assert!(0 < delta.removed_triangles.len() && delta.removed_triangles.len() < 50);
+/// assert!(50 < new.indices.len() / 3);
+///
+/// // Every triangle that isn't in the delta is in both meshes.
+/// let num_kept = new.indices.len() / 3 - delta.added_triangles.len();
+/// assert_eq!(num_kept, old.indices.len() / 3 - delta.removed_triangles.len());
+/// ```
+pub fn mesh_diff(old: &SurfaceNetsBuffer, new: &SurfaceNetsBuffer) -> MeshDelta {
+    let old_keys = vertex_keys(old);
+    let new_keys = vertex_keys(new);
+    let old_key_to_index: HashMap<(u32, u32), u32> = old_keys
+        .iter()
+        .enumerate()
+        .map(|(v, &key)| (key, v as u32))
+        .collect();
+    let new_key_set: HashSet<(u32, u32)> = new_keys.iter().copied().collect();
+
+    let mut delta = MeshDelta::default();
+    for &key in old_keys.iter() {
+        if !new_key_set.contains(&key) {
+            delta.removed_strides.push(key.0);
+        }
+    }
+    for (v, key) in new_keys.iter().enumerate() {
+        match old_key_to_index.get(key) {
+            Some(&old_v) => {
+                let old_v = old_v as usize;
+                if old.positions[old_v] != new.positions[v]
+                    || old.normals.get(old_v) != new.normals.get(v)
+                {
+                    delta.moved_vertices.push(v as u32);
+                }
+            }
+            None => delta.added_strides.push(key.0),
+        }
+    }
+
+    let old_triangles = triangle_keys(old, &old_keys);
+    let new_triangles = triangle_keys(new, &new_keys);
+    let old_triangle_set: HashSet<_> = old_triangles.iter().copied().collect();
+    let new_triangle_set: HashSet<_> = new_triangles.iter().copied().collect();
+    delta.removed_triangles = (0..old_triangles.len() as u32)
+        .filter(|&t| !new_triangle_set.contains(&old_triangles[t as usize]))
+        .collect();
+    delta.added_triangles = (0..new_triangles.len() as u32)
+        .filter(|&t| !old_triangle_set.contains(&new_triangles[t as usize]))
+        .collect();
+
+    delta
+}
+
+// Identifies every vertex by its cube's stride, and how many vertices of the same cube came before it.
+fn vertex_keys(mesh: &SurfaceNetsBuffer) -> Vec<(u32, u32)> {
+    assert_eq!(
+        mesh.surface_strides.len(),
+        mesh.positions.len(),
+        "every vertex needs a surface stride"
+    );
+
+    let mut num_seen = HashMap::<u32, u32>::new();
+    mesh.surface_strides
+        .iter()
+        .map(|&stride| {
+            let seen = num_seen.entry(stride).or_insert(0);
+            *seen += 1;
+            (stride, *seen - 1)
+        })
+        .collect()
+}
+
+// Identifies every triangle by the keys of its corners, rotated to start at the smallest one so the winding is preserved.
+fn triangle_keys(mesh: &SurfaceNetsBuffer, keys: &[(u32, u32)]) -> Vec<[(u32, u32); 3]> {
+    mesh.indices
+        .chunks_exact(3)
+        .map(|tri| {
+            let mut corners = [0, 1, 2].map(|i| keys[tri[i] as usize]);
+            let first = (0..3).min_by_key(|&i| corners[i]).unwrap();
+            corners.rotate_left(first);
+            corners
+        })
+        .collect()
+}
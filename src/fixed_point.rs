@@ -0,0 +1,78 @@
+use crate::SignedDistance;
+
+/// A signed distance stored as an `i8` in fixed point, with `SCALE_BITS` fractional bits, i.e. the distance is the integer
+/// divided by `2^SCALE_BITS`. Like [`FixedPoint16`], but for even smaller fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FixedPoint8<const SCALE_BITS: u32>(pub i8);
+
+impl<const SCALE_BITS: u32> FixedPoint8<SCALE_BITS> {
+    /// Encodes `distance`, rounded to the nearest step and saturated to the range of `i8`.
+    pub fn from_distance(distance: f32) -> Self {
+        Self((distance * (1 << SCALE_BITS) as f32).round() as i8)
+    }
+}
+
+impl<const SCALE_BITS: u32> From<FixedPoint8<SCALE_BITS>> for f32 {
+    fn from(d: FixedPoint8<SCALE_BITS>) -> f32 {
+        d.0 as f32 / (1 << SCALE_BITS) as f32
+    }
+}
+
+impl<const SCALE_BITS: u32> SignedDistance for FixedPoint8<SCALE_BITS> {
+    fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+}
+
+/// A signed distance stored as an `i16` in fixed point, with `SCALE_BITS` fractional bits, i.e. the distance is the integer
+/// divided by `2^SCALE_BITS`. This halves the size of an `f32` field, e.g. to send it over the network, and it's decoded to
+/// real units as it's meshed. The sign test doesn't decode it.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, FixedPoint16, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+/// }
+/// // With 8 fractional bits, the distances are rounded to 1/256 of a voxel, up to 128 voxels away.
+/// let fixed_point_sdf = sdf.map(FixedPoint16::<8>::from_distance);
+///
+/// let mut reference = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut reference);
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&fixed_point_sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+///
+/// assert_eq!(mesh.indices, reference.indices);
+/// for (p, q) in mesh.positions.iter().zip(reference.positions.iter()) {
+///     assert!((0..3).all(|i| (p[i] - q[i]).abs() < 1e-2));
+/// }
+/// for (n, m) in mesh.normals.iter().zip(reference.normals.iter()) {
+///     assert!((0..3).all(|i| (n[i] - m[i]).abs() < 1e-2));
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FixedPoint16<const SCALE_BITS: u32>(pub i16);
+
+impl<const SCALE_BITS: u32> FixedPoint16<SCALE_BITS> {
+    /// Encodes `distance`, rounded to the nearest step and saturated to the range of `i16`.
+    pub fn from_distance(distance: f32) -> Self {
+        Self((distance * (1 << SCALE_BITS) as f32).round() as i16)
+    }
+}
+
+impl<const SCALE_BITS: u32> From<FixedPoint16<SCALE_BITS>> for f32 {
+    fn from(d: FixedPoint16<SCALE_BITS>) -> f32 {
+        d.0 as f32 / (1 << SCALE_BITS) as f32
+    }
+}
+
+impl<const SCALE_BITS: u32> SignedDistance for FixedPoint16<SCALE_BITS> {
+    fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+}
@@ -0,0 +1,449 @@
+//! A [`wgpu`] compute backend, for meshing large volumes on the GPU.
+//!
+//! The SDF is uploaded as `f32` samples, then one pass estimates a surface point in every cube (with
+//! [`VertexPlacement::EdgeCentroid`](crate::VertexPlacement::EdgeCentroid) and gradient normals, like the default
+//! [`SurfaceNetsConfig`](crate::SurfaceNetsConfig)), and a second pass makes the triangles around every edge that crosses the
+//! surface. Both passes append to their buffers with atomic counters, so the vertices and triangles come out in no particular
+//! order, but they are the same ones that [`surface_nets`](crate::surface_nets) makes for the whole array.
+//!
+//! The resulting [`GpuMesh`] buffers can be bound for rendering as they are, or read back into a
+//! [`SurfaceNetsBuffer`].
+//!
+//! The GPU output is checked against the CPU's by `tests/gpu.rs`, which needs an adapter and so is ignored by default; run it
+//! with `cargo test --features wgpu --test gpu -- --ignored`.
+//!
+//! ```no_run
+//! use fast_surface_nets::gpu::GpuSurfaceNets;
+//! use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+//! use fast_surface_nets::{wgpu, SurfaceNetsBuffer};
+//!
+//! type ChunkShape = ConstShape3u32<18, 18, 18>;
+//!
+//! let mut sdf = [1.0; ChunkShape::USIZE];
+//! for i in 0u32..ChunkShape::SIZE {
+//!     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+//!     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+//! }
+//!
+//! let instance = wgpu::Instance::default();
+//! let adapter = pollster::block_on(instance.request_adapter(&Default::default())).unwrap();
+//! let (device, queue) = pollster::block_on(adapter.request_device(&Default::default())).unwrap();
+//!
+//! let gpu = GpuSurfaceNets::new(&device);
+//! let gpu_mesh = gpu.mesh(&device, &queue, &sdf, [18; 3], 2000, 12000);
+//! let mut mesh = SurfaceNetsBuffer::default();
+//! gpu_mesh.read_back(&device, &queue, &mut mesh);
+//! ```
+
+use crate::SurfaceNetsBuffer;
+
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = r#"
+struct Params {
+    shape: vec3<u32>,
+    max_vertices: u32,
+    max_indices: u32,
+}
+
+const NULL_VERTEX: u32 = 0xffffffffu;
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> sdf: array<f32>;
+@group(0) @binding(2) var<storage, read_write> cube_to_index: array<u32>;
+@group(0) @binding(3) var<storage, read_write> positions: array<vec4<f32>>;
+@group(0) @binding(4) var<storage, read_write> normals: array<vec4<f32>>;
+@group(0) @binding(5) var<storage, read_write> indices: array<u32>;
+@group(0) @binding(6) var<storage, read_write> counts: array<atomic<u32>, 2>;
+
+fn linearize(p: vec3<u32>) -> u32 {
+    return p.x + params.shape.x * (p.y + params.shape.y * p.z);
+}
+
+fn corner(i: u32) -> vec3<u32> {
+    return vec3<u32>(i & 1u, (i >> 1u) & 1u, (i >> 2u) & 1u);
+}
+
+fn is_cube(p: vec3<u32>) -> bool {
+    return all(p + 1u < params.shape);
+}
+
+// Like `estimate_surface_in_cube`, with the centroid of the edge crossings and the trilinear gradient at it.
+@compute @workgroup_size(4, 4, 4)
+fn estimate_surface(@builtin(global_invocation_id) p: vec3<u32>) {
+    if !is_cube(p) {
+        return;
+    }
+    var dists: array<f32, 8>;
+    var num_negative = 0u;
+    for (var i = 0u; i < 8u; i++) {
+        dists[i] = sdf[linearize(p + corner(i))];
+        num_negative += u32(dists[i] < 0.0);
+    }
+    if num_negative == 0u || num_negative == 8u {
+        cube_to_index[linearize(p)] = NULL_VERTEX;
+        return;
+    }
+
+    var sum = vec3<f32>(0.0);
+    var count = 0.0;
+    for (var i = 0u; i < 8u; i++) {
+        for (var axis = 0u; axis < 3u; axis++) {
+            let j = i | (1u << axis);
+            if j == i || (dists[i] < 0.0) == (dists[j] < 0.0) {
+                continue;
+            }
+            let t = dists[i] / (dists[i] - dists[j]);
+            sum += mix(vec3<f32>(corner(i)), vec3<f32>(corner(j)), t);
+            count += 1.0;
+        }
+    }
+    let c = sum / count;
+
+    var gradient = vec3<f32>(0.0);
+    for (var i = 0u; i < 8u; i++) {
+        let k = vec3<f32>(corner(i));
+        let w = mix(1.0 - c, c, k);
+        gradient += dists[i] * (2.0 * k - 1.0) * vec3<f32>(w.y * w.z, w.x * w.z, w.x * w.y);
+    }
+
+    let v = atomicAdd(&counts[0], 1u);
+    cube_to_index[linearize(p)] = v;
+    if v < params.max_vertices {
+        positions[v] = vec4<f32>(vec3<f32>(p) + c, 1.0);
+        normals[v] = vec4<f32>(gradient, 0.0);
+    }
+}
+
+// Like `make_cube_quads`, splitting every quad along its shorter diagonal.
+@compute @workgroup_size(4, 4, 4)
+fn make_quads(@builtin(global_invocation_id) p: vec3<u32>) {
+    if !is_cube(p) || cube_to_index[linearize(p)] == NULL_VERTEX {
+        return;
+    }
+    for (var axis = 0u; axis < 3u; axis++) {
+        let b_axis = (axis + 1u) % 3u;
+        let c_axis = (axis + 2u) % 3u;
+        if p[b_axis] == 0u || p[c_axis] == 0u || p[axis] + 2u == params.shape[axis] {
+            continue;
+        }
+        var a = vec3<u32>(0u);
+        var b = vec3<u32>(0u);
+        var c = vec3<u32>(0u);
+        a[axis] = 1u;
+        b[b_axis] = 1u;
+        c[c_axis] = 1u;
+        let is_negative1 = sdf[linearize(p)] < 0.0;
+        let is_negative2 = sdf[linearize(p + a)] < 0.0;
+        if is_negative1 == is_negative2 {
+            continue;
+        }
+        let negative_face = is_negative2;
+
+        // The triangle points, viewed face-front, look like this:
+        // v1 v3
+        // v2 v4
+        let v1 = cube_to_index[linearize(p)];
+        let v2 = cube_to_index[linearize(p - b)];
+        let v3 = cube_to_index[linearize(p - c)];
+        let v4 = cube_to_index[linearize(p - b - c)];
+        if max(max(v1, v2), max(v3, v4)) >= params.max_vertices {
+            continue;
+        }
+        let d14 = positions[v1].xyz - positions[v4].xyz;
+        let d23 = positions[v2].xyz - positions[v3].xyz;
+        var quad: array<u32, 6>;
+        if dot(d14, d14) < dot(d23, d23) {
+            if negative_face {
+                quad = array<u32, 6>(v1, v4, v2, v1, v3, v4);
+            } else {
+                quad = array<u32, 6>(v1, v2, v4, v1, v4, v3);
+            }
+        } else if negative_face {
+            quad = array<u32, 6>(v2, v3, v4, v2, v1, v3);
+        } else {
+            quad = array<u32, 6>(v2, v4, v3, v2, v3, v1);
+        }
+
+        let start = atomicAdd(&counts[1], 6u);
+        if start + 6u <= params.max_indices {
+            for (var i = 0u; i < 6u; i++) {
+                indices[start + i] = quad[i];
+            }
+        }
+    }
+}
+"#;
+
+/// The compute pipelines of the GPU mesher, created once per device.
+#[derive(Debug)]
+pub struct GpuSurfaceNets {
+    bind_group_layout: wgpu::BindGroupLayout,
+    estimate_surface: wgpu::ComputePipeline,
+    make_quads: wgpu::ComputePipeline,
+}
+
+/// A mesh made on the GPU by [`GpuSurfaceNets::mesh`].
+///
+/// `positions` and `normals` hold a `[f32; 4]` per vertex (with a `w` of 1 and 0), so they can be bound as vertex buffers with
+/// a 16 byte stride, and `indices` holds a `u32` triangle list. `counts` holds the number of vertices and indices that were
+/// made, which can exceed the capacities if the buffers were too small.
+#[derive(Debug)]
+pub struct GpuMesh {
+    pub positions: wgpu::Buffer,
+    pub normals: wgpu::Buffer,
+    pub indices: wgpu::Buffer,
+    pub counts: wgpu::Buffer,
+    pub max_vertices: u32,
+    pub max_indices: u32,
+}
+
+impl GpuSurfaceNets {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("surface nets"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("surface nets"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1, true),
+                storage(2, false),
+                storage(3, false),
+                storage(4, false),
+                storage(5, false),
+                storage(6, false),
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("surface nets"),
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: 0,
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        Self {
+            estimate_surface: pipeline("estimate_surface"),
+            make_quads: pipeline("make_quads"),
+            bind_group_layout,
+        }
+    }
+
+    /// Meshes the whole array `sdf` of the given `shape` (with X varying fastest, then Y, then Z), like
+    /// [`surface_nets`](crate::surface_nets) with `min = [0; 3]` and `max = shape - 1`.
+    ///
+    /// The vertices and indices beyond `max_vertices` and `max_indices` are dropped (and so are the triangles that use dropped
+    /// vertices), but still counted in [`GpuMesh::counts`]. At most one vertex is made per cube, and 18 indices.
+    ///
+    /// Panics if `sdf` is shorter than the shape.
+    pub fn mesh(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sdf: &[f32],
+        shape: [u32; 3],
+        max_vertices: u32,
+        max_indices: u32,
+    ) -> GpuMesh {
+        let num_samples = shape.iter().product::<u32>() as usize;
+        assert!(num_samples <= sdf.len());
+
+        let params = [
+            shape[0],
+            shape[1],
+            shape[2],
+            max_vertices,
+            max_indices,
+            0,
+            0,
+            0,
+        ];
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("surface nets params"),
+            contents: &params.map(u32::to_ne_bytes).concat(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let sdf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("surface nets sdf"),
+            contents: &sdf[..num_samples]
+                .iter()
+                .flat_map(|d| d.to_ne_bytes())
+                .collect::<Vec<_>>(),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let buffer = |label, size: u32, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                // Empty bindings aren't allowed.
+                size: 4 * size.max(1) as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | usage,
+                mapped_at_creation: false,
+            })
+        };
+        let cube_to_index = buffer(
+            "surface nets cube_to_index",
+            num_samples as u32,
+            wgpu::BufferUsages::empty(),
+        );
+        let mesh = GpuMesh {
+            positions: buffer(
+                "surface nets positions",
+                4 * max_vertices,
+                wgpu::BufferUsages::VERTEX,
+            ),
+            normals: buffer(
+                "surface nets normals",
+                4 * max_vertices,
+                wgpu::BufferUsages::VERTEX,
+            ),
+            indices: buffer(
+                "surface nets indices",
+                max_indices,
+                wgpu::BufferUsages::INDEX,
+            ),
+            counts: buffer("surface nets counts", 2, wgpu::BufferUsages::COPY_DST),
+            max_vertices,
+            max_indices,
+        };
+        queue.write_buffer(&mesh.counts, 0, &[0; 8]);
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("surface nets"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                &params,
+                &sdf,
+                &cube_to_index,
+                &mesh.positions,
+                &mesh.normals,
+                &mesh.indices,
+                &mesh.counts,
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>(),
+        });
+
+        // The quads need every surface point, so they are made in a second pass.
+        let mut encoder = device.create_command_encoder(&Default::default());
+        let workgroups = shape.map(|s| s.saturating_sub(1).div_ceil(4));
+        for pipeline in [&self.estimate_surface, &self.make_quads] {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
+        }
+        queue.submit([encoder.finish()]);
+
+        mesh
+    }
+}
+
+impl GpuMesh {
+    /// Copies the mesh into the `positions`, `normals` and `indices` of `output`, and updates its `aabb`. The other buffers
+    /// are cleared, since the GPU doesn't make them.
+    ///
+    /// Panics if the mesh didn't fit into the capacities it was made with.
+    pub fn read_back(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        output: &mut SurfaceNetsBuffer,
+    ) {
+        let [num_vertices, num_indices] = {
+            let counts = read_buffer(device, queue, &self.counts, 8);
+            [0, 1].map(|i| u32::from_ne_bytes(std::array::from_fn(|b| counts[4 * i + b])))
+        };
+        assert!(
+            num_vertices <= self.max_vertices && num_indices <= self.max_indices,
+            "the mesh has {num_vertices} vertices and {num_indices} indices, more than its capacity"
+        );
+
+        output.reset(0);
+        let to_vec3s = |bytes: Vec<u8>| {
+            let floats = from_bytes(&bytes, f32::from_ne_bytes);
+            floats.chunks_exact(4).map(|v| [v[0], v[1], v[2]]).collect()
+        };
+        let vertex_bytes = 16 * num_vertices as u64;
+        output.positions = to_vec3s(read_buffer(device, queue, &self.positions, vertex_bytes));
+        output.normals = to_vec3s(read_buffer(device, queue, &self.normals, vertex_bytes));
+        let index_bytes = read_buffer(device, queue, &self.indices, 4 * num_indices as u64);
+        output.indices = from_bytes(&index_bytes, u32::from_ne_bytes);
+        output.update_aabb();
+    }
+}
+
+// Reads the first `size` bytes of `buffer` back from the GPU, blocking until they are available.
+fn read_buffer(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    buffer: &wgpu::Buffer,
+    size: u64,
+) -> Vec<u8> {
+    if size == 0 {
+        return Vec::new();
+    }
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("surface nets read back"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, size);
+    queue.submit([encoder.finish()]);
+
+    let slice = staging.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| {
+        result.expect("failed to map the buffer")
+    });
+    device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .expect("failed to wait for the GPU");
+    let bytes = slice
+        .get_mapped_range()
+        .expect("failed to map the buffer")
+        .to_vec();
+    staging.unmap();
+    bytes
+}
+
+fn from_bytes<T>(bytes: &[u8], from_ne_bytes: fn([u8; 4]) -> T) -> Vec<T> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
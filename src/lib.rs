@@ -48,37 +48,939 @@
 //! let mut watertight_buffer = SurfaceNetsBuffer::default();
 //! let config = SurfaceNetsConfig {
 //!     generate_boundary_faces: true,
+//!     ..Default::default()
 //! };
 //! surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut watertight_buffer);
 //!
 //! // The watertight mesh will have more triangles due to boundary faces.
 //! assert!(watertight_buffer.indices.len() >= buffer.indices.len());
 //! ```
+//!
+//! # Array-Based API
+//!
+//! `glam` is only used internally. Every public function and buffer in this crate takes and returns plain arrays like
+//! `[f32; 3]` and `[u32; 3]`, and new helpers must follow the same rule. The `glam` re-export exists purely for
+//! convenience. The following compiles without importing `glam` at all (and `tests/array_api.rs` checks the signatures of every
+//! public item for `glam` types):
+//!
+//! ```
+//! use fast_surface_nets::ndshape::ConstShape3u32;
+//! use fast_surface_nets::{surface_nets, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+//!
+//! type ChunkShape = ConstShape3u32<18, 18, 18>;
+//!
+//! let _: fn(&[f32], &ChunkShape, [u32; 3], [u32; 3], &mut SurfaceNetsBuffer) = surface_nets::<f32, ChunkShape>;
+//! let _: fn(&[f32], &ChunkShape, [u32; 3], [u32; 3], SurfaceNetsConfig, &mut SurfaceNetsBuffer) =
+//!     surface_nets_with_config::<f32, ChunkShape>;
+//!
+//! let buffer = SurfaceNetsBuffer::default();
+//! let _: &[[f32; 3]] = &buffer.positions;
+//! let _: &[[f32; 3]] = &buffer.normals;
+//! let _: &[u32] = &buffer.indices;
+//! let _: &[[u32; 3]] = &buffer.surface_points;
+//! let _: &[u32] = &buffer.surface_strides;
+//! let _: &[u32] = &buffer.cube_to_index;
+//! ```
 
+mod atlas;
+mod chunks;
+mod contour;
+mod crease;
+mod diff;
+mod fixed_point;
+#[cfg(feature = "wgpu")]
+pub mod gpu;
+mod lod;
+mod merge;
+#[cfg(feature = "ndarray")]
+mod ndarray_interop;
+mod obj;
+mod orient;
+mod pack;
+mod relax;
+#[cfg(feature = "rerun")]
+mod rerun_interop;
+pub mod sdf;
+mod simplify;
+#[cfg(feature = "smallvec")]
+mod small;
+mod solid;
+mod topology;
+mod weld;
+
+pub use atlas::*;
+pub use chunks::*;
+pub use contour::*;
+pub use crease::*;
+pub use diff::*;
+pub use fixed_point::*;
 pub use glam;
+#[cfg(feature = "half")]
+pub use half;
+pub use lod::*;
+pub use merge::*;
+#[cfg(feature = "ndarray")]
+pub use ndarray;
+#[cfg(feature = "ndarray")]
+pub use ndarray_interop::*;
 pub use ndshape;
+pub use obj::*;
+pub use orient::*;
+pub use pack::*;
+pub use relax::*;
+#[cfg(feature = "rerun")]
+pub use rerun;
+#[cfg(feature = "rerun")]
+pub use rerun_interop::*;
+pub use simplify::*;
+#[cfg(feature = "smallvec")]
+pub use small::*;
+#[cfg(feature = "smallvec")]
+pub use smallvec;
+pub use solid::*;
+pub use weld::*;
+#[cfg(feature = "wgpu")]
+pub use wgpu;
 
-use glam::{Vec3A, Vec3Swizzles};
-use ndshape::Shape;
+use glam::{Mat3A, Vec3A, Vec3Swizzles};
+use ndshape::{RuntimeShape, Shape};
+use std::marker::PhantomData;
 
 /// Configuration options for surface mesh generation.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct SurfaceNetsConfig {
     /// Whether to generate faces on the boundaries of the sampling volume to create watertight meshes.
     /// When enabled, faces will be generated on cube boundaries where the SDF is negative.
+    ///
+    /// The cap vertex of a cube on an edge or corner of the sampling volume is placed on every boundary face of that cube, i.e.
+    /// on the edge or corner itself, and its normal is the normalized sum of those faces' normals.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+    ///
+    /// type ChunkShape = ConstShape3u32<6, 6, 6>;
+    ///
+    /// // A sphere around the minimal corner of the chunk.
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i);
+    ///     sdf[i as usize] = ((x * x + y * y + z * z) as f32).sqrt() - 2.5;
+    /// }
+    /// let mut buffer = SurfaceNetsBuffer::default();
+    /// let config = SurfaceNetsConfig {
+    ///     generate_boundary_faces: true,
+    ///     ..Default::default()
+    /// };
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [5; 3], config, &mut buffer);
+    ///
+    /// // The extent has 5x5x5 cubes, numbered from the origin.
+    /// let cap_vertex = |p| buffer.cube_to_index[ConstShape3u32::<5, 5, 5>::linearize(p) as usize] as usize;
+    ///
+    /// // The corner cube's cap vertex is on the corner, with a diagonal normal.
+    /// let corner = cap_vertex([0, 0, 0]);
+    /// assert_eq!(buffer.positions[corner], [0.0, 0.0, 0.0]);
+    /// let n = -(1.0f32 / 3.0).sqrt();
+    /// assert!(buffer.normals[corner].iter().all(|&c| (c - n).abs() < 1e-6));
+    ///
+    /// // The cap vertex of a cube on the edge between the min Y and min Z faces is on that edge.
+    /// let edge = cap_vertex([1, 0, 0]);
+    /// assert_eq!(buffer.positions[edge], [1.5, 0.0, 0.0]);
+    /// let n = -(0.5f32).sqrt();
+    /// let [nx, ny, nz] = buffer.normals[edge];
+    /// assert!(nx == 0.0 && (ny - n).abs() < 1e-6 && (nz - n).abs() < 1e-6);
+    /// ```
+    ///
+    /// Where the surface crosses a boundary plane diagonally, the cap still meets it without a gap:
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+    /// use std::collections::HashMap;
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// // A wedge through the min X plane, whose cross section there is a triangle with a diagonal side.
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+    ///     sdf[i as usize] = (x - 4.5).max(2.5 - y).max(2.5 - z).max((y + z - 12.5) / 2f32.sqrt());
+    /// }
+    /// let mut buffer = SurfaceNetsBuffer::default();
+    /// let config = SurfaceNetsConfig {
+    ///     generate_boundary_faces: true,
+    ///     ..Default::default()
+    /// };
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut buffer);
+    /// assert!(buffer.positions.iter().any(|p| p[0] == 0.0));
+    ///
+    /// // The mesh is closed: every edge is shared by exactly two triangles.
+    /// let mut edges = HashMap::new();
+    /// for tri in buffer.indices.chunks(3) {
+    ///     for i in 0..3 {
+    ///         let (a, b) = (tri[i], tri[(i + 1) % 3]);
+    ///         *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+    ///     }
+    /// }
+    /// assert!(edges.values().all(|&count| count == 2));
+    /// ```
+    ///
+    /// The caps fill the inside of the surface's rim on each plane, like the faces of [`surface_nets_2d`] would, so they
+    /// don't repeat any surface triangles, and they're wound to face out of the volume:
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+    /// use std::collections::HashMap;
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// // A sphere clipped by all six boundary planes.
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+    ///     sdf[i as usize] = ((x - 8.3).powi(2) + (y - 8.5).powi(2) + (z - 8.5).powi(2)).sqrt() - 10.0;
+    /// }
+    /// let mut buffer = SurfaceNetsBuffer::default();
+    /// let config = SurfaceNetsConfig {
+    ///     generate_boundary_faces: true,
+    ///     ..Default::default()
+    /// };
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut buffer);
+    ///
+    /// // Every edge is shared by exactly two triangles, with opposite windings.
+    /// let mut directed_edges = HashMap::new();
+    /// for tri in buffer.indices.chunks(3) {
+    ///     for i in 0..3 {
+    ///         *directed_edges.entry([tri[i], tri[(i + 1) % 3]]).or_insert(0) += 1;
+    ///     }
+    /// }
+    /// assert!(directed_edges.values().all(|&count| count == 1));
+    /// assert!(directed_edges.keys().all(|&[a, b]| directed_edges.contains_key(&[b, a])));
+    ///
+    /// // The flat triangles on the planes face out of the chunk.
+    /// for tri in buffer.indices.chunks(3) {
+    ///     let [a, b, c] = [0, 1, 2].map(|i| buffer.positions[tri[i] as usize]);
+    ///     let normal_along = |axis: usize| {
+    ///         let [u, v] = [(axis + 1) % 3, (axis + 2) % 3];
+    ///         (b[u] - a[u]) * (c[v] - a[v]) - (b[v] - a[v]) * (c[u] - a[u])
+    ///     };
+    ///     for axis in 0..3 {
+    ///         if [a, b, c].iter().all(|p| p[axis] == 0.0) {
+    ///             assert!(normal_along(axis) < 0.0);
+    ///         }
+    ///         if [a, b, c].iter().all(|p| p[axis] == 17.0) {
+    ///             assert!(normal_along(axis) > 0.0);
+    ///         }
+    ///     }
+    /// }
+    /// ```
     pub generate_boundary_faces: bool,
+    /// How the surface point is placed inside of each cube that intersects the isosurface.
+    pub vertex_placement: VertexPlacement,
+    /// How (and whether) vertex normals are computed.
+    pub normal_mode: NormalMode,
+    /// The stencil radius (in voxels) of [`NormalMode::CentralDifference`]. `0` (the default) is the same as `1`.
+    pub normal_stencil_radius: u32,
+    /// Which way the vertex normals point, relative to the surface.
+    pub normal_direction: NormalDirection,
+    /// Where the cap vertices are placed when `generate_boundary_faces` is enabled.
+    pub boundary_cap_mode: BoundaryCapMode,
+    /// Which boundary planes are closed when `generate_boundary_faces` is enabled.
+    pub boundary_planes: BoundaryPlanes,
+    /// Whether to put the boundary faces of each plane in [`SurfaceNetsBuffer::boundary_indices`] instead of `indices`, e.g. to
+    /// shade the caps separately from the surface.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// // A sphere clipped by all six boundary planes.
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+    ///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 10.0;
+    /// }
+    ///
+    /// let mut combined = SurfaceNetsBuffer::default();
+    /// let config = SurfaceNetsConfig {
+    ///     generate_boundary_faces: true,
+    ///     ..Default::default()
+    /// };
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut combined);
+    ///
+    /// let mut separate = SurfaceNetsBuffer::default();
+    /// let config = SurfaceNetsConfig {
+    ///     separate_boundary_indices: true,
+    ///     ..config
+    /// };
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut separate);
+    ///
+    /// // The surface triangles are unchanged, and the boundary faces are split up by plane.
+    /// let num_surface_indices = separate.indices.len();
+    /// assert_eq!(separate.positions, combined.positions);
+    /// assert_eq!(separate.indices[..], combined.indices[..num_surface_indices]);
+    /// assert_eq!(separate.boundary_indices.concat(), combined.indices[num_surface_indices..]);
+    /// for (plane, indices) in separate.boundary_indices.iter().enumerate() {
+    ///     let (axis, bound) = (plane / 2, if plane % 2 == 0 { 0 } else { 16 });
+    ///     assert!(!indices.is_empty());
+    ///     assert!(indices.iter().all(|&v| separate.surface_points[v as usize][axis] == bound));
+    /// }
+    /// ```
+    pub separate_boundary_indices: bool,
+    /// Whether to generate the faces on the six boundary planes in parallel when `generate_boundary_faces` is enabled. The
+    /// output is identical either way. This is only worthwhile for large chunks.
+    ///
+    /// Requires the `rayon` feature; ignored otherwise.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+    ///
+    /// type ChunkShape = ConstShape3u32<66, 66, 66>;
+    ///
+    /// // A sphere clipped by all six boundary planes.
+    /// let mut sdf = vec![1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 32.5);
+    ///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 40.0;
+    /// }
+    ///
+    /// let mut serial = SurfaceNetsBuffer::default();
+    /// let config = SurfaceNetsConfig {
+    ///     generate_boundary_faces: true,
+    ///     ..Default::default()
+    /// };
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [65; 3], config, &mut serial);
+    ///
+    /// let mut parallel = SurfaceNetsBuffer::default();
+    /// let config = SurfaceNetsConfig {
+    ///     parallel_boundary_faces: true,
+    ///     ..config
+    /// };
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [65; 3], config, &mut parallel);
+    ///
+    /// assert_eq!(parallel.positions, serial.positions);
+    /// assert_eq!(parallel.indices, serial.indices);
+    /// ```
+    pub parallel_boundary_faces: bool,
+    /// If set, hang a "skirt" of this depth (in voxels) from every open edge of the mesh on the boundary of the sampling
+    /// volume, to hide the cracks between neighboring chunks at different levels of detail without closing the volume.
+    ///
+    /// Each skirt vertex is a copy of a boundary surface vertex, moved by `skirt_depth` within the boundary plane, toward the
+    /// interior of the surface (so skirts hang down from terrain). This is meant as an alternative to
+    /// `generate_boundary_faces`.
+    pub skirt_depth: Option<f32>,
+    /// Whether to fill in [`SurfaceNetsBuffer::surface_mask`].
+    pub generate_surface_mask: bool,
+    /// A permutation and/or flip of the output axes, applied to `positions` and `normals` after meshing. `surface_points` and
+    /// the other voxel-space outputs are not affected.
+    pub axis_remap: AxisRemap,
+    /// Which axes are periodic, e.g. for a planet wrapped around a torus or a tileable texture.
+    ///
+    /// On a wrapped axis, the samples from `min` to `max` are one period, and the sample after `max` is the one at `min`. So
+    /// there is one more cube, between `max` and `min`, and the mesh connects across the periodic boundary instead of being cut
+    /// there. Positions are not wrapped, so the quads across the boundary span the whole extent; map them onto the periodic
+    /// domain as needed. Wrapped axes have no boundary planes for `generate_boundary_faces` or `skirt_depth`.
+    pub wrap: [bool; 3],
+    /// Whether to check that all four cubes around a sign-changing edge have a vertex before making its quad, and to skip the
+    /// quad otherwise, instead of assuming so. This leaves a hole rather than reading a bogus vertex, e.g. for finite element
+    /// meshing, where a missing quad is better than a non-manifold one.
+    ///
+    /// The meshing passes in this crate always give those cubes a vertex, so this only changes the output if that invariant is
+    /// broken.
+    pub safe_quads: bool,
+    /// If set, a surface point that lands within this distance of the surface point of an already estimated neighbor (the cube
+    /// at -X, -Y or -Z) reuses that neighbor's vertex instead of making a new one, so that very thin features don't produce
+    /// collapsed quads. Triangles that become degenerate are dropped.
+    ///
+    /// A merged cube has no entry of its own in `surface_points` and `surface_strides`, but `cube_to_index` maps it to the
+    /// vertex it reuses. Ignored by [`surface_nets_from_cubes`].
+    pub vertex_merge_distance: Option<f32>,
+    /// Whether to give every triangle its own three vertices, with the barycentric coordinates `(1, 0, 0)`, `(0, 1, 0)` and
+    /// `(0, 0, 1)` in [`SurfaceNetsBuffer::barycentrics`], for drawing anti-aliased wireframes in a fragment shader.
+    ///
+    /// This gives up vertex sharing entirely: `indices` just counts up from 0, and every vertex attribute is copied to each
+    /// corner that uses the vertex, so the mesh has 3 vertices per triangle. Vertices that aren't used by any triangle are
+    /// dropped, and `cube_to_index` maps each cube to the first copy of its vertex (or [`NULL_VERTEX`] if it was dropped).
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+    ///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+    /// }
+    /// let mut indexed = SurfaceNetsBuffer::default();
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], Default::default(), &mut indexed);
+    /// let mut wireframe = SurfaceNetsBuffer::default();
+    /// let config = SurfaceNetsConfig {
+    ///     barycentric_wireframe: true,
+    ///     ..Default::default()
+    /// };
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut wireframe);
+    ///
+    /// // Every triangle has its own vertices, with the standard basis vectors as barycentric coordinates.
+    /// assert_eq!(wireframe.positions.len(), indexed.indices.len());
+    /// assert_eq!(wireframe.normals.len(), wireframe.positions.len());
+    /// assert!(wireframe.indices.iter().copied().eq(0..wireframe.positions.len() as u32));
+    /// for tri in wireframe.indices.chunks(3) {
+    ///     let barycentrics = [0, 1, 2].map(|i| wireframe.barycentrics[tri[i] as usize]);
+    ///     assert_eq!(barycentrics, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+    /// }
+    ///
+    /// // And the triangles are the same.
+    /// assert!(wireframe.triangles().eq(indexed.triangles()));
+    /// assert!(indexed.barycentrics.is_empty());
+    /// ```
+    pub barycentric_wireframe: bool,
+    /// If set, each surface point is pulled back to within this distance of the center of its cube, which bounds how far a
+    /// vertex can wander on pathological SDFs (and so how skinny its triangles can get), at the cost of some fidelity. This
+    /// applies to every [`VertexPlacement`], and the normal is computed at the clamped point.
+    pub max_vertex_offset: Option<f32>,
+    /// The level of the isosurface: the mesh is where `value - iso_value` crosses zero, e.g. 0.5 for a density field in `[0, 1]`,
+    /// without subtracting the threshold from every sample first. This applies to every test and interpolation, including the
+    /// boundary faces. It adds to the [`SignedDistance::surface_offset`] of the samples.
+    ///
+    /// Values below the level are inside of the volume, like negative distances. For a density that is higher inside, negate
+    /// it, along with the level, e.g. mesh `-density` at `-0.5`, or the normals and winding face inward.
+    pub iso_value: f32,
+    /// The size of a voxel along each axis, for samples on an anisotropic grid. If set, the positions are scaled by it, and the
+    /// normals by its reciprocal, so they stay perpendicular to the scaled surface. `None` is the same as `[1.0; 3]`.
+    ///
+    /// Meshing itself happens on the unit grid, and the scaling is applied to the finished mesh, before
+    /// [`axis_remap`](Self::axis_remap). Functions that take a mesh and the samples it came from, like [`max_surface_error`],
+    /// expect it in grid units.
+    pub voxel_size: Option<[f32; 3]>,
+    /// When enabled, every face is emitted as a quad of four indices in [`SurfaceNetsBuffer::quad_indices`], wound the same way
+    /// as the two triangles it would otherwise be split into, and `indices` is left empty. This includes the boundary faces,
+    /// which always go to `quad_indices`, even with [`separate_boundary_indices`](Self::separate_boundary_indices).
+    ///
+    /// Quads aren't planar in general, since the four vertices around an edge are placed independently. Post-processing that
+    /// works on triangles, i.e. [`skirt_depth`](Self::skirt_depth) and [`barycentric_wireframe`](Self::barycentric_wireframe),
+    /// doesn't apply to quads.
+    pub quads: bool,
+    /// Whether to skip every triangle with an area under [`DEGENERATE_TRIANGLE_AREA`], e.g. for physics engines that reject
+    /// zero-area triangles.
+    ///
+    /// Surface Nets can place the vertices of neighboring cubes at (nearly) the same point, e.g. where the SDF is exactly zero at
+    /// a sample, which makes slivers or collapses a quad entirely. Each triangle of a quad (or a boundary face) is checked on
+    /// its own, so only the degenerate half of a quad is dropped. This doesn't apply to [`SurfaceNetsConfig::quads`].
+    pub skip_degenerate_triangles: bool,
+    /// If set, each coordinate of a surface point is clamped into its cube, inset by this margin on every side (so `Some(0.0)`
+    /// is the whole cube), which keeps the vertices of neighboring cubes from overlapping on noisy fields. Unlike
+    /// [`max_vertex_offset`](Self::max_vertex_offset), this bounds each axis separately. The normal is computed at the clamped
+    /// point.
+    ///
+    /// The edge crossings are always inside of the cube, and so is their centroid, so `Some(0.0)` only makes a difference if
+    /// the samples aren't finite.
+    pub clamp_vertices_to_cube: Option<f32>,
+    /// Fill in [`SurfaceNetsBuffer::line_indices`] with every unique edge of the mesh, once the rest of the mesh is done. This
+    /// saves deriving the edges from the triangles again for every frame of debug rendering.
+    pub generate_line_indices: bool,
+    /// Fill in [`SurfaceNetsBuffer::face_normals`] with the geometric normal of every triangle, once the rest of the mesh is
+    /// done.
+    pub generate_face_normals: bool,
+    /// Fill in [`SurfaceNetsBuffer::uvs`] by projecting every vertex onto a plane of the output coordinates, once the rest of
+    /// the mesh is done. See [`UvProjection`].
+    pub generate_uvs: Option<UvProjection>,
+    /// If set, each surface point is moved toward the isosurface of its cube's trilinear interpolation before the normal is
+    /// computed, after [`vertex_placement`](Self::vertex_placement) and before [`max_vertex_offset`](Self::max_vertex_offset)
+    /// and [`clamp_vertices_to_cube`](Self::clamp_vertices_to_cube). See [`VertexRelaxation`].
+    pub relaxation: Option<VertexRelaxation>,
+}
+
+/// A signed permutation of the X, Y and Z axes, e.g. to convert the output from a Z-up to a Y-up coordinate system.
+///
+/// Output axis `i` takes the coordinate of input axis `axes[i]`, negated if `negate[i]`. If the remap is a reflection (it has an
+/// odd number of swaps and negations), the triangle winding is reversed too, so faces stay front-facing.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_with_config, AxisRemap, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A Z-up sphere, centered at (8, 7, 9).
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+///     let [x, y, z] = [x - 8.0, y - 7.0, z - 9.0];
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 5.0;
+/// }
+///
+/// let sub = |a: [f32; 3], b: [f32; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+/// let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+/// let cross = |a: [f32; 3], b: [f32; 3]| {
+///     [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+/// };
+///
+/// // Z-up to Y-up is a rotation, and negating X is a reflection.
+/// let reflect_x = AxisRemap::new([0, 1, 2], [true, false, false]);
+/// for (axis_remap, center) in [
+///     (AxisRemap::Z_UP_TO_Y_UP, [8.0, 9.0, -7.0]),
+///     (reflect_x, [-8.0, 7.0, 9.0]),
+/// ] {
+///     let mut buffer = SurfaceNetsBuffer::default();
+///     let config = SurfaceNetsConfig {
+///         axis_remap,
+///         ..Default::default()
+///     };
+///     surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut buffer);
+///
+///     // Normals and triangles still face outward.
+///     for (&p, &n) in buffer.positions.iter().zip(buffer.normals.iter()) {
+///         assert!(dot(n, sub(p, center)) > 0.0);
+///     }
+///     for tri in buffer.indices.chunks(3) {
+///         let [a, b, c] = [0, 1, 2].map(|i| buffer.positions[tri[i] as usize]);
+///         assert!(dot(cross(sub(b, a), sub(c, a)), sub(a, center)) > 0.0);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisRemap {
+    axes: [usize; 3],
+    negate: [bool; 3],
+}
+
+impl AxisRemap {
+    /// Leaves every axis alone.
+    pub const IDENTITY: Self = Self::new([0, 1, 2], [false; 3]);
+
+    /// Converts from a right-handed Z-up system to a right-handed Y-up system: `(x, y, z) -> (x, z, -y)`.
+    pub const Z_UP_TO_Y_UP: Self = Self::new([0, 2, 1], [false, false, true]);
+
+    /// Panics if `axes` is not a permutation of `[0, 1, 2]`.
+    pub const fn new(axes: [usize; 3], negate: [bool; 3]) -> Self {
+        assert!(
+            axes[0] < 3
+                && axes[1] < 3
+                && axes[2] < 3
+                && axes[0] != axes[1]
+                && axes[1] != axes[2]
+                && axes[0] != axes[2],
+            "axes must be a permutation of [0, 1, 2]"
+        );
+        Self { axes, negate }
+    }
+
+    /// Whether this remap mirrors the mesh, which reverses the triangle winding.
+    pub const fn is_reflection(&self) -> bool {
+        // A permutation is odd iff it's a single swap, i.e. it has exactly one fixed point.
+        let num_fixed =
+            (self.axes[0] == 0) as u32 + (self.axes[1] == 1) as u32 + (self.axes[2] == 2) as u32;
+        let num_negated = self.negate[0] as u32 + self.negate[1] as u32 + self.negate[2] as u32;
+        ((num_fixed == 1) as u32 + num_negated) % 2 == 1
+    }
+
+    /// Applies the remap to a position or normal.
+    pub fn apply(&self, v: [f32; 3]) -> [f32; 3] {
+        [0, 1, 2].map(|i| {
+            let c = v[self.axes[i]];
+            if self.negate[i] {
+                -c
+            } else {
+                c
+            }
+        })
+    }
 }
 
-impl Default for SurfaceNetsConfig {
+impl Default for AxisRemap {
     fn default() -> Self {
-        Self {
-            generate_boundary_faces: false,
-        }
+        Self::IDENTITY
     }
 }
 
+/// Strategies for placing the cap vertices created by `generate_boundary_faces`.
+///
+/// Every cap vertex lies on the boundary face of a cube on the edge of the sampling volume. These modes trade speed for how
+/// closely the cap meets the actual surface. They only move the vertex within the face, so a cap vertex on an edge or corner of
+/// the sampling volume stays there.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BoundaryCapMode {
+    /// The center of the cube's boundary face. This is the fastest.
+    #[default]
+    FaceCenter,
+    /// Moves from the face center toward the zero crossing of the bilinearly interpolated SDF on the face (by at most half a
+    /// voxel, so it stays within the face). This brings the rim of the cap closer to the true surface.
+    FaceCrossing,
+    /// The nearest surface vertex of the neighboring boundary cubes, projected onto the face and clamped to it. Falls back to
+    /// the face center if none of the neighbors has a surface vertex.
+    SnapToInterior,
+}
+
+/// Which of the six boundary planes of the sampling volume are closed by `generate_boundary_faces`.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{
+///     surface_nets, surface_nets_with_config, BoundaryPlanes, SurfaceNetsBuffer, SurfaceNetsConfig,
+/// };
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let sphere = |center: [f32; 3], radius: f32| {
+///     let mut sdf = [1.0; ChunkShape::USIZE];
+///     for i in 0u32..ChunkShape::SIZE {
+///         let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+///         let [x, y, z] = [x - center[0], y - center[1], z - center[2]];
+///         sdf[i as usize] = (x * x + y * y + z * z).sqrt() - radius;
+///     }
+///     sdf
+/// };
+/// let config = SurfaceNetsConfig {
+///     generate_boundary_faces: true,
+///     boundary_planes: BoundaryPlanes::Clipped,
+///     ..Default::default()
+/// };
+/// let mesh = |sdf: &[f32], config| {
+///     let mut buffer = SurfaceNetsBuffer::default();
+///     surface_nets_with_config(sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut buffer);
+///     buffer
+/// };
+///
+/// // A sphere entirely inside of the chunk gets no boundary faces.
+/// let interior = sphere([8.5; 3], 6.0);
+/// let mut open_mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&interior, &ChunkShape {}, [0; 3], [17; 3], &mut open_mesh);
+/// assert_eq!(mesh(&interior, config).indices, open_mesh.indices);
+///
+/// // A sphere clipped by the min X plane gets exactly the cap on that plane, like with `All`.
+/// let clipped = sphere([2.0, 8.5, 8.5], 6.0);
+/// let clipped_mesh = mesh(&clipped, config);
+/// let all_mesh = mesh(
+///     &clipped,
+///     SurfaceNetsConfig {
+///         boundary_planes: BoundaryPlanes::All,
+///         ..config
+///     },
+/// );
+/// surface_nets(&clipped, &ChunkShape {}, [0; 3], [17; 3], &mut open_mesh);
+/// assert!(clipped_mesh.indices.len() > open_mesh.indices.len());
+/// assert_eq!(clipped_mesh.positions, all_mesh.positions);
+/// assert_eq!(clipped_mesh.indices, all_mesh.indices);
+///
+/// // A completely solid chunk has no surface to close.
+/// let solid = [-1.0; ChunkShape::USIZE];
+/// assert!(mesh(&solid, config).indices.is_empty());
+/// let all_config = SurfaceNetsConfig {
+///     boundary_planes: BoundaryPlanes::All,
+///     ..config
+/// };
+/// assert!(!mesh(&solid, all_config).indices.is_empty());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BoundaryPlanes {
+    /// Every plane is closed wherever its samples are negative. A completely solid chunk becomes a closed box.
+    #[default]
+    All,
+    /// Only the planes that the isosurface actually crosses, i.e. that have surface points on them, are closed. This gives the
+    /// minimal watertight mesh: planes that are entirely inside or outside of the volume are left alone.
+    Clipped,
+    /// Only the planes set here, in the order min X, max X, min Y, max Y, min Z, max Z, are closed like with `All`. The others
+    /// are left open, and the cap vertices of the cubes next to them are placed as if they weren't boundaries, e.g. to close a
+    /// group of chunks on the outside and leave the faces between them to [`stitch_chunks`].
+    Selected([bool; 6]),
+}
+
+/// The sign convention of the vertex normals.
+///
+/// This only affects the stored normals, not the triangle winding.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_with_config, NormalDirection, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = ((x * x + y * y + z * z) as f32).sqrt() - 15.0;
+/// }
+///
+/// let mut exterior = SurfaceNetsBuffer::default();
+/// let mut interior = SurfaceNetsBuffer::default();
+/// for (direction, buffer) in [
+///     (NormalDirection::TowardExterior, &mut exterior),
+///     (NormalDirection::TowardInterior, &mut interior),
+/// ] {
+///     let config = SurfaceNetsConfig {
+///         generate_boundary_faces: true,
+///         normal_direction: direction,
+///         ..Default::default()
+///     };
+///     surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, buffer);
+/// }
+///
+/// assert_eq!(interior.positions, exterior.positions);
+/// assert_eq!(interior.indices, exterior.indices);
+/// for (i, e) in interior.normals.iter().zip(exterior.normals.iter()) {
+///     assert_eq!(*i, e.map(|c| -c));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NormalDirection {
+    /// Normals point toward increasing SDF values, i.e. out of the solid and into empty space.
+    #[default]
+    TowardExterior,
+    /// Normals point toward decreasing SDF values, i.e. into the solid.
+    TowardInterior,
+}
+
+/// Strategies for computing the vertex normals in [`SurfaceNetsBuffer::normals`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NormalMode {
+    /// The SDF gradient at the surface point, bilinearly interpolated from the cube's edge differences. Not normalized.
+    #[default]
+    RawGradient,
+    /// Don't compute normals at all. `SurfaceNetsBuffer::normals` is left empty, which saves time when only positions are
+    /// needed, e.g. for collision meshes.
+    None,
+    /// The average of the `RawGradient` normals of the cube and its neighbors in a 3x3x3 block of cubes, which reduces the
+    /// noise of normals on quantized fields. This is a second pass over the surface points, and doesn't read any more samples
+    /// of the SDF. Not normalized.
+    Smoothed,
+    /// The central-difference gradient of the SDF at the corners of the cube, trilinearly interpolated to the surface point. The
+    /// difference along each axis is taken between the samples `normal_stencil_radius` voxels away on either side, so a wider
+    /// stencil smooths out high-frequency noise, at the cost of some sharpness. Not normalized.
+    ///
+    /// The stencil reads outside of the extent, so the chunk needs [`required_padding`] voxels of padding. The surface points
+    /// whose stencil doesn't fit in the array fall back to the `RawGradient` normal.
+    CentralDifference,
+    /// Like `RawGradient`, but scaled to unit length (in output coordinates, after any
+    /// [`voxel_size`](SurfaceNetsConfig::voxel_size) is applied), e.g. for physics or exporters that expect unit normals.
+    Normalized,
+    /// The unit-length average of the normals of the triangles (or quads) around each vertex, weighted by their areas, once the
+    /// mesh is done. This follows the faces rather than the field, like flat-shading normals would, but stays smooth across
+    /// vertices. Vertices without any faces keep their `Normalized` gradient.
+    AreaWeightedFaceAverage,
+}
+
+/// Strategies for placing the single surface point inside of a cube that intersects the isosurface.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VertexPlacement {
+    /// The unweighted average of the points where the isosurface crosses the cube edges.
+    #[default]
+    EdgeCentroid,
+    /// Always the center of the cube. This gives a blockier look, but it's the fastest option since no edge crossings are
+    /// computed.
+    CubeCenter,
+    /// Like `EdgeCentroid`, but each edge crossing is weighted by the magnitude of its negative corner value, pulling the
+    /// vertex toward the more negative (interior) side of the cube.
+    MassCenter,
+    /// Like `EdgeCentroid`, but each edge crossing is weighted by `|d1| + |d2|`, the change of the SDF along its edge. Crossings
+    /// where the SDF is steep are placed more reliably than grazing ones, where the linear interpolation is a poor fit, so this
+    /// keeps vertices closer to the surface, especially on thin features.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{sdf, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig, VertexPlacement};
+    ///
+    /// type ChunkShape = ConstShape3u32<22, 22, 22>;
+    ///
+    /// // A thin ring, like a chain link, moving slowly along X.
+    /// let ring = |[x, y, z]: [f32; 3], t: f32| sdf::torus([x - 10.3 - t, y - 10.3, z - 10.3], 6.0, 0.7);
+    ///
+    /// // The mean distance of the vertices from the ring, over all of the frames.
+    /// let mean_error = |vertex_placement| {
+    ///     let (mut sum, mut count) = (0.0, 0);
+    ///     for frame in 0..10 {
+    ///         let t = 0.05 * frame as f32;
+    ///         let mut field = [1.0; ChunkShape::USIZE];
+    ///         for i in 0u32..ChunkShape::SIZE {
+    ///             field[i as usize] = ring(ChunkShape::delinearize(i).map(|c| c as f32), t);
+    ///         }
+    ///         let mut mesh = SurfaceNetsBuffer::default();
+    ///         let config = SurfaceNetsConfig { vertex_placement, ..Default::default() };
+    ///         surface_nets_with_config(&field, &ChunkShape {}, [0; 3], [21; 3], config, &mut mesh);
+    ///         sum += mesh.positions.iter().map(|&p| ring(p, t).abs()).sum::<f32>();
+    ///         count += mesh.positions.len();
+    ///     }
+    ///     sum / count as f32
+    /// };
+    /// assert!(mean_error(VertexPlacement::GradientWeighted) < 0.95 * mean_error(VertexPlacement::EdgeCentroid));
+    /// ```
+    GradientWeighted,
+    /// Like Dual Contouring, the point that best fits the planes through the edge crossings, perpendicular to the gradient of
+    /// the SDF there, i.e. the minimizer of a quadratic error function (QEF). This keeps the sharp edges and corners of the
+    /// volume that the other placements round off. Directions in which the planes don't constrain the point (e.g. along an
+    /// edge) fall back to the `EdgeCentroid`, and the point is clamped to the cube.
+    ///
+    /// The crossings and gradients are estimated from the samples of the cube, so a feature is only as sharp as they are. That
+    /// holds for features on samples, but a corner in the middle of a cube, whose samples are all close to several faces,
+    /// stays rounded.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{sdf, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig, VertexPlacement};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// // A box with its corners on samples.
+    /// let (center, half_extents) = ([8.0, 9.0, 8.0], [5.0, 4.0, 6.0]);
+    /// let mut box_sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let p = ChunkShape::delinearize(i).map(|c| c as f32);
+    ///     box_sdf[i as usize] = sdf::cuboid([0, 1, 2].map(|a| p[a] - center[a]), half_extents);
+    /// }
+    /// let corners: Vec<[f32; 3]> = (0..8)
+    ///     .map(|c| [0, 1, 2].map(|a| center[a] + if c >> a & 1 == 1 { half_extents[a] } else { -half_extents[a] }))
+    ///     .collect();
+    ///
+    /// // The distance from each corner of the box to the closest vertex.
+    /// let corner_errors = |placement| {
+    ///     let mut buffer = SurfaceNetsBuffer::default();
+    ///     let config = SurfaceNetsConfig { vertex_placement: placement, ..Default::default() };
+    ///     surface_nets_with_config(&box_sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut buffer);
+    ///     corners
+    ///         .iter()
+    ///         .map(|corner| {
+    ///             buffer
+    ///                 .positions
+    ///                 .iter()
+    ///                 .map(|p| (0..3).map(|a| (p[a] - corner[a]).powi(2)).sum::<f32>().sqrt())
+    ///                 .fold(f32::INFINITY, f32::min)
+    ///         })
+    ///         .collect::<Vec<f32>>()
+    /// };
+    ///
+    /// assert!(corner_errors(VertexPlacement::QuadraticErrorFunction).iter().all(|&e| e < 1e-2));
+    /// assert!(corner_errors(VertexPlacement::EdgeCentroid).iter().all(|&e| e > 0.1));
+    /// ```
+    QuadraticErrorFunction,
+}
+
+/// How [`SurfaceNetsConfig::generate_uvs`] maps positions to texture coordinates.
+///
+/// Both project a vertex onto the plane perpendicular to the axis that its faces point along the most, using their
+/// area-weighted normal, so a texture tiles once per unit of the output positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UvProjection {
+    /// The other two coordinates, in the order (Z, Y), (X, Z) or (X, Y) for vertices facing along X, Y or Z, whichever way
+    /// the vertex faces along that axis. This is the planar mapping of triplanar texturing, with a single plane per vertex.
+    Triplanar,
+    /// Like `Triplanar`, but with the first coordinate negated on the max X and min Z sides, and the second one on the max Y
+    /// side, so the texture reads the right way around on all six sides of a box, seen from outside.
+    Box,
+}
+
+/// Settings for [`SurfaceNetsConfig::relaxation`].
+///
+/// Each iteration takes a damped Newton step `c -= step * d(c) * ∇d(c) / |∇d(c)|²`, where `d` is the trilinear interpolation
+/// of the 8 corners of the vertex's cube, so `step = 1.0` projects straight onto the interpolated surface. Unlike
+/// [`relax_vertices`], this runs while the cube's samples are at hand, and the normal is computed at the relaxed point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexRelaxation {
+    /// The number of steps to take. Vertices stop early where the gradient vanishes.
+    pub iterations: u32,
+    /// The fraction of each Newton step to take, in `(0, 1]`.
+    pub step: f32,
+    /// Keep every vertex inside of its cube after each step, so the mesh can't fold over.
+    pub clamp_to_cube: bool,
+}
+
+/// A sample of the field being meshed.
+///
+/// The isosurface is where the value crosses `surface_offset`, which is 0 unless overridden. For example, an occupancy field in
+/// `[0, 1]` that is 0 inside of the volume can put its surface at the 0.5 level:
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, SignedDistance, SurfaceNetsBuffer};
+///
+/// #[derive(Clone, Copy)]
+/// struct Occupancy(f32);
+///
+/// impl From<Occupancy> for f32 {
+///     fn from(o: Occupancy) -> f32 {
+///         o.0
+///     }
+/// }
+///
+/// impl SignedDistance for Occupancy {
+///     fn surface_offset(self) -> f32 {
+///         0.5
+///     }
+/// }
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A sphere with a soft edge, 0 inside and 1 outside.
+/// let occupancy = |i: u32| {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     (0.5 + ((x * x + y * y + z * z).sqrt() - 6.0) / 4.0).clamp(0.0, 1.0)
+/// };
+/// let mut occupancy_sdf = [Occupancy(0.0); ChunkShape::USIZE];
+/// let mut shifted_sdf = [0.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     occupancy_sdf[i as usize] = Occupancy(occupancy(i));
+///     shifted_sdf[i as usize] = occupancy(i) - 0.5;
+/// }
+///
+/// // The mesh is the 0.5-level isosurface.
+/// let mut occupancy_mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&occupancy_sdf, &ChunkShape {}, [0; 3], [17; 3], &mut occupancy_mesh);
+/// let mut shifted_mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&shifted_sdf, &ChunkShape {}, [0; 3], [17; 3], &mut shifted_mesh);
+/// assert!(!occupancy_mesh.indices.is_empty());
+/// assert_eq!(occupancy_mesh.positions, shifted_mesh.positions);
+/// assert_eq!(occupancy_mesh.normals, shifted_mesh.normals);
+/// assert_eq!(occupancy_mesh.indices, shifted_mesh.indices);
+/// ```
+///
+/// Quantized fields can be meshed directly from `i8` or `i16` samples, at 1 or 2 bytes per sample. The edge crossings only
+/// depend on the ratios of the distances, so the samples can be in any units, and the positions are the same as for the
+/// decoded field. Only the normals are in the units of the samples. To decode the distances to voxel units as well, use
+/// [`FixedPoint8`] or [`FixedPoint16`].
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, FixedPoint8, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // Distances in 1/16 of a voxel, saturated 8 voxels away from the surface.
+/// let mut quantized_sdf = [0i8; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     quantized_sdf[i as usize] = (16.0 * ((x * x + y * y + z * z).sqrt() - 6.0)).round() as i8;
+/// }
+/// let fixed_point_sdf = quantized_sdf.map(FixedPoint8::<4>);
+///
+/// let mut quantized_mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&quantized_sdf, &ChunkShape {}, [0; 3], [17; 3], &mut quantized_mesh);
+/// let mut fixed_point_mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&fixed_point_sdf, &ChunkShape {}, [0; 3], [17; 3], &mut fixed_point_mesh);
+///
+/// assert!(!quantized_mesh.indices.is_empty());
+/// assert_eq!(quantized_mesh.indices, fixed_point_mesh.indices);
+/// for (p, q) in quantized_mesh.positions.iter().zip(fixed_point_mesh.positions.iter()) {
+///     assert!((0..3).all(|i| (p[i] - q[i]).abs() < 1e-5));
+/// }
+/// for (n, m) in quantized_mesh.normals.iter().zip(fixed_point_mesh.normals.iter()) {
+///     assert!((0..3).all(|i| (n[i] - 16.0 * m[i]).abs() < 1e-3));
+/// }
+/// ```
 pub trait SignedDistance: Into<f32> + Copy {
-    fn is_negative(self) -> bool;
+    /// Whether this sample is inside of the volume. This must agree with `surface_offset`, which the default implementation
+    /// does.
+    fn is_negative(self) -> bool {
+        self.into() < self.surface_offset()
+    }
+
+    /// The value of the field on the isosurface. Edge crossings and normals are computed from `value - surface_offset`.
+    fn surface_offset(self) -> f32 {
+        0.0
+    }
 }
 
 impl SignedDistance for f32 {
@@ -87,89 +989,3067 @@ impl SignedDistance for f32 {
     }
 }
 
-/// The output buffers used by [`surface_nets`]. These buffers can be reused to avoid reallocating memory.
-#[derive(Default, Clone)]
-pub struct SurfaceNetsBuffer {
-    /// The triangle mesh positions.
-    ///
-    /// These are in array-local coordinates, i.e. at array position `(x, y, z)`, the vertex position would be `(x, y, z) +
-    /// centroid` if the isosurface intersects that voxel.
-    pub positions: Vec<[f32; 3]>,
-    /// The triangle mesh normals.
-    ///
-    /// The normals are **not** normalized, since that is done most efficiently on the GPU.
-    pub normals: Vec<[f32; 3]>,
-    /// The triangle mesh indices.
-    pub indices: Vec<u32>,
+impl SignedDistance for i8 {
+    fn is_negative(self) -> bool {
+        self < 0
+    }
+}
 
-    /// Local 3D array coordinates of every voxel that intersects the isosurface.
-    pub surface_points: Vec<[u32; 3]>,
-    /// Stride of every voxel that intersects the isosurface. Can be used for efficient post-processing.
-    pub surface_strides: Vec<u32>,
-    /// Used to map back from voxel stride to vertex index.
-    pub stride_to_index: Vec<u32>,
+impl SignedDistance for i16 {
+    fn is_negative(self) -> bool {
+        self < 0
+    }
 }
 
-impl SurfaceNetsBuffer {
-    /// Clears all of the buffers, but keeps the memory allocated for reuse.
-    fn reset(&mut self, array_size: usize) {
-        self.positions.clear();
-        self.normals.clear();
-        self.indices.clear();
-        self.surface_points.clear();
-        self.surface_strides.clear();
+/// Half precision samples, e.g. from a GPU, are widened one at a time as they're meshed, so the field is never copied to
+/// `f32`.
+///
+/// ```
+/// use fast_surface_nets::half::f16;
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [f16::ZERO; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     sdf[i as usize] = f16::from_f32((x * x + y * y + z * z).sqrt() - 6.0);
+/// }
+/// let widened_sdf = sdf.map(f32::from);
+///
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// let mut widened_mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&widened_sdf, &ChunkShape {}, [0; 3], [17; 3], &mut widened_mesh);
+///
+/// assert!(!mesh.indices.is_empty());
+/// assert_eq!(mesh.indices, widened_mesh.indices);
+/// assert_eq!(mesh.positions, widened_mesh.positions);
+/// assert_eq!(mesh.normals, widened_mesh.normals);
+/// ```
+#[cfg(feature = "half")]
+impl SignedDistance for half::f16 {
+    fn is_negative(self) -> bool {
+        self < half::f16::ZERO
+    }
+}
+
+/// Random access to the samples of an SDF by their stride in a shape, for [`surface_nets_from_source`]. This lets sparse
+/// volumes (octrees, brick maps, run-length encoded columns, or procedural fields) be meshed without first copying each chunk
+/// into a padded dense array.
+///
+/// A dense slice is a source too, which meshes exactly like the slice does with [`surface_nets_with_config`].
+pub trait SdfSource {
+    type Sample: SignedDistance;
+
+    /// The sample at `stride`, the linear index of a point of the shape being meshed. Only the strides of the meshed extent
+    /// are read, and each of them is read several times, so this should be cheap.
+    fn sample(&self, stride: usize) -> Self::Sample;
+}
+
+impl<T> SdfSource for [T]
+where
+    T: SignedDistance,
+{
+    type Sample = T;
+
+    #[inline]
+    fn sample(&self, stride: usize) -> T {
+        self[stride]
+    }
+}
+
+// Random access to the samples being meshed, by stride. This lets the meshing pipeline transform samples on the fly, e.g. to
+// apply a threshold field, without copying the SDF.
+trait Samples {
+    // Whether the samples only hold a sign, so the surface vertices are smoothed after they're estimated to round off the
+    // blocky mesh.
+    const SMOOTH_POSITIONS: bool = false;
+
+    // The signed distance from the isosurface at `stride`.
+    fn distance(&self, stride: usize) -> f32;
+
+    fn is_negative(&self, stride: usize) -> bool;
+
+    // The number of samples, i.e. one past the largest stride that can be read.
+    fn len(&self) -> usize;
+
+    // Where the isosurface crosses the edge from `corner1` to `corner2` of the cube with minimal corner `p`, relative to `p`,
+    // given the distances `d1` and `d2` at those corners.
+    #[inline]
+    fn edge_crossing(&self, _p: Vec3A, corner1: u32, corner2: u32, d1: f32, d2: f32) -> Vec3A {
+        estimate_surface_edge_intersection(corner1, corner2, d1, d2)
+    }
+
+    // The gradient at `c`, relative to the cube with minimal corner `p`, given the distances `dists` at its corners. This is
+    // the normal of the plane through an edge crossing for `VertexPlacement::QuadraticErrorFunction`.
+    #[inline]
+    fn crossing_gradient(&self, _p: Vec3A, dists: &[f32; 8], c: Vec3A) -> Vec3A {
+        sdf_gradient(dists, c)
+    }
+}
+
+impl<T> Samples for [T]
+where
+    T: SignedDistance,
+{
+    #[inline]
+    fn distance(&self, stride: usize) -> f32 {
+        let d = *unsafe { self.get_unchecked(stride) };
+        d.into() - d.surface_offset()
+    }
+
+    #[inline]
+    fn is_negative(&self, stride: usize) -> bool {
+        unsafe { self.get_unchecked(stride) }.is_negative()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+}
+
+// An SDF with a per-sample threshold subtracted.
+struct ThresholdedSamples<'a, T> {
+    sdf: &'a [T],
+    threshold: &'a [f32],
+}
+
+impl<T> Samples for ThresholdedSamples<'_, T>
+where
+    T: SignedDistance,
+{
+    #[inline]
+    fn distance(&self, stride: usize) -> f32 {
+        self.sdf.distance(stride) - unsafe { self.threshold.get_unchecked(stride) }
+    }
+
+    #[inline]
+    fn is_negative(&self, stride: usize) -> bool {
+        self.distance(stride) < 0.0
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.sdf.len().min(self.threshold.len())
+    }
+}
+
+// Samples with the isosurface moved to `iso_value`.
+struct IsoSamples<'a, D: ?Sized> {
+    samples: &'a D,
+    iso_value: f32,
+}
+
+impl<D> Samples for IsoSamples<'_, D>
+where
+    D: Samples + ?Sized,
+{
+    const SMOOTH_POSITIONS: bool = D::SMOOTH_POSITIONS;
+
+    #[inline]
+    fn distance(&self, stride: usize) -> f32 {
+        self.samples.distance(stride) - self.iso_value
+    }
+
+    #[inline]
+    fn is_negative(&self, stride: usize) -> bool {
+        self.distance(stride) < 0.0
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[inline]
+    fn edge_crossing(&self, p: Vec3A, corner1: u32, corner2: u32, d1: f32, d2: f32) -> Vec3A {
+        self.samples.edge_crossing(p, corner1, corner2, d1, d2)
+    }
+
+    #[inline]
+    fn crossing_gradient(&self, p: Vec3A, dists: &[f32; 8], c: Vec3A) -> Vec3A {
+        self.samples.crossing_gradient(p, dists, c)
+    }
+}
+
+// Samples whose edge crossings are refined by resampling `field`, the same signed distance at any point, with
+// `bisection_steps` of bisection followed by linear interpolation. The isosurface is at `iso_value`, which is applied to the
+// field here and to the samples by `IsoSamples`.
+struct ResampledSamples<'a, D: ?Sized, F> {
+    samples: &'a D,
+    field: F,
+    iso_value: f32,
+    bisection_steps: u32,
+}
+
+impl<D, F> Samples for ResampledSamples<'_, D, F>
+where
+    D: Samples + ?Sized,
+    F: Fn([f32; 3]) -> f32,
+{
+    #[inline]
+    fn distance(&self, stride: usize) -> f32 {
+        self.samples.distance(stride)
+    }
+
+    #[inline]
+    fn is_negative(&self, stride: usize) -> bool {
+        self.samples.is_negative(stride)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn edge_crossing(&self, p: Vec3A, corner1: u32, corner2: u32, d1: f32, d2: f32) -> Vec3A {
+        let [a, b] = [corner1, corner2].map(|corner| CUBE_CORNER_VECTORS[corner as usize]);
+        let ([mut t1, mut t2], [mut d1, mut d2]) = ([0.0, 1.0], [d1, d2]);
+        for _ in 0..self.bisection_steps {
+            let t = 0.5 * (t1 + t2);
+            let d = (self.field)((p + a.lerp(b, t)).into()) - self.iso_value;
+            if (d < 0.0) == (d1 < 0.0) {
+                (t1, d1) = (t, d);
+            } else {
+                (t2, d2) = (t, d);
+            }
+        }
+        a.lerp(b, t1 + (t2 - t1) * d1 / (d1 - d2))
+    }
+}
+
+// Samples with Hermite data: the gradient of the field at any point in array coordinates, for the planes of the QEF.
+struct HermiteSamples<'a, D: ?Sized, G> {
+    samples: &'a D,
+    gradient: G,
+}
+
+impl<D, G> Samples for HermiteSamples<'_, D, G>
+where
+    D: Samples + ?Sized,
+    G: Fn([f32; 3]) -> [f32; 3],
+{
+    #[inline]
+    fn distance(&self, stride: usize) -> f32 {
+        self.samples.distance(stride)
+    }
+
+    #[inline]
+    fn is_negative(&self, stride: usize) -> bool {
+        self.samples.is_negative(stride)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[inline]
+    fn edge_crossing(&self, p: Vec3A, corner1: u32, corner2: u32, d1: f32, d2: f32) -> Vec3A {
+        self.samples.edge_crossing(p, corner1, corner2, d1, d2)
+    }
+
+    fn crossing_gradient(&self, p: Vec3A, _dists: &[f32; 8], c: Vec3A) -> Vec3A {
+        Vec3A::from((self.gradient)((p + c).into()))
+    }
+}
+
+// A boolean occupancy grid, read as a field of -1 inside and +1 outside.
+struct OccupancySamples<'a> {
+    occupancy: &'a [bool],
+}
+
+impl Samples for OccupancySamples<'_> {
+    const SMOOTH_POSITIONS: bool = true;
+
+    #[inline]
+    fn distance(&self, stride: usize) -> f32 {
+        if self.is_negative(stride) {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    #[inline]
+    fn is_negative(&self, stride: usize) -> bool {
+        *unsafe { self.occupancy.get_unchecked(stride) }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.occupancy.len()
+    }
+}
+
+// An SDF stored as separate Z-planes, addressed by the stride into the contiguous array they make up.
+struct ZPlaneSamples<'a, T, P> {
+    planes: &'a [P],
+    plane_len: usize,
+    sample: PhantomData<T>,
+}
+
+impl<T, P> ZPlaneSamples<'_, T, P> {
+    #[inline]
+    fn split(&self, stride: usize) -> (usize, usize) {
+        (stride / self.plane_len, stride % self.plane_len)
+    }
+}
+
+impl<T, P> Samples for ZPlaneSamples<'_, T, P>
+where
+    T: SignedDistance,
+    P: AsRef<[T]>,
+{
+    #[inline]
+    fn distance(&self, stride: usize) -> f32 {
+        let (z, i) = self.split(stride);
+        unsafe { self.planes.get_unchecked(z) }.as_ref().distance(i)
+    }
+
+    #[inline]
+    fn is_negative(&self, stride: usize) -> bool {
+        let (z, i) = self.split(stride);
+        unsafe { self.planes.get_unchecked(z) }
+            .as_ref()
+            .is_negative(i)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.planes.len() * self.plane_len
+    }
+}
+
+// A double-precision SDF, with the iso value subtracted before it is narrowed. Signs are taken from the narrowed distance,
+// so a sample too small for `f32` never counts as a crossing whose distances are both zero.
+struct F64Samples<'a> {
+    sdf: &'a [f64],
+    iso_value: f64,
+}
+
+impl Samples for F64Samples<'_> {
+    #[inline]
+    fn distance(&self, stride: usize) -> f32 {
+        (*unsafe { self.sdf.get_unchecked(stride) } - self.iso_value) as f32
+    }
+
+    #[inline]
+    fn is_negative(&self, stride: usize) -> bool {
+        self.distance(stride) < 0.0
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.sdf.len()
+    }
+}
+
+// An `SdfSource`, read through its trait.
+struct SourceSamples<'a, D: ?Sized>(&'a D);
+
+impl<D> Samples for SourceSamples<'_, D>
+where
+    D: SdfSource + ?Sized,
+{
+    #[inline]
+    fn distance(&self, stride: usize) -> f32 {
+        let d = self.0.sample(stride);
+        d.into() - d.surface_offset()
+    }
+
+    #[inline]
+    fn is_negative(&self, stride: usize) -> bool {
+        self.0.sample(stride).is_negative()
+    }
+
+    // A source has no length, and checks its own reads.
+    #[inline]
+    fn len(&self) -> usize {
+        usize::MAX
+    }
+}
+
+/// The output buffers used by [`surface_nets`]. These buffers can be reused to avoid reallocating memory.
+///
+/// Buffers are never shrunk, even the ones that a particular config leaves empty. So a pooled buffer can be reused with
+/// normals disabled for some chunks and enabled for others without ever reallocating `normals`, as long as its capacity
+/// suffices:
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_with_config, NormalMode, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = ((x * x + y * y + z * z) as f32).sqrt() - 15.0;
+/// }
+/// let with_normals = SurfaceNetsConfig::default();
+/// let without_normals = SurfaceNetsConfig {
+///     normal_mode: NormalMode::None,
+///     ..Default::default()
+/// };
+///
+/// let mut buffer = SurfaceNetsBuffer::default();
+/// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], with_normals, &mut buffer);
+/// let capacity = buffer.normals.capacity();
+/// let allocation = buffer.normals.as_ptr();
+///
+/// for config in [without_normals, with_normals, without_normals] {
+///     surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut buffer);
+///     assert_eq!(buffer.normals.is_empty(), config.normal_mode == NormalMode::None);
+///     assert_eq!(buffer.normals.capacity(), capacity);
+///     assert_eq!(buffer.normals.as_ptr(), allocation);
+/// }
+/// ```
+#[derive(Default, Clone)]
+pub struct SurfaceNetsBuffer {
+    /// The triangle mesh positions.
+    ///
+    /// These are in array-local coordinates, i.e. at array position `(x, y, z)`, the vertex position would be `(x, y, z) +
+    /// centroid` if the isosurface intersects that voxel.
+    pub positions: Vec<[f32; 3]>,
+    /// The triangle mesh normals.
+    ///
+    /// The normals are **not** normalized, since that is done most efficiently on the GPU. This is empty if
+    /// [`NormalMode::None`] is used.
+    pub normals: Vec<[f32; 3]>,
+    /// The unit geometric normal of every triangle in `indices`, facing the side that its winding faces, e.g. for collision or
+    /// picking. A degenerate triangle has a zero normal. Only filled in if [`SurfaceNetsConfig::generate_face_normals`] is
+    /// enabled (and [`SurfaceNetsConfig::quads`] is not), and empty otherwise.
+    ///
+    /// Like [`aabb`](Self::aabb), they're computed once the mesh is done, and kept up to date by
+    /// [`apply_transform`](Self::apply_transform), but not by other post-processing.
+    pub face_normals: Vec<[f32; 3]>,
+    /// The triangle mesh indices.
+    pub indices: Vec<u32>,
+    /// The faces of the mesh as quads, with four indices each. Only filled in if [`SurfaceNetsConfig::quads`] is enabled, in
+    /// which case `indices` is empty. Empty otherwise.
+    pub quad_indices: Vec<u32>,
+    /// Every unique edge of the faces once, as a line list with two indices per edge, e.g. for drawing the topology of the mesh
+    /// with lines. The faces are the quads of `quad_indices` if [`SurfaceNetsConfig::quads`] is enabled, so their diagonals
+    /// aren't edges, and the triangles of `indices` otherwise. Only filled in if [`SurfaceNetsConfig::generate_line_indices`]
+    /// is enabled, and empty otherwise.
+    pub line_indices: Vec<u32>,
+    /// The triangles of the boundary faces on each plane of the sampling volume, in the order min X, max X, min Y, max Y, min Z,
+    /// max Z. Only filled in if [`SurfaceNetsConfig::separate_boundary_indices`] is enabled, in which case the boundary faces
+    /// are not in `indices`. Empty otherwise.
+    pub boundary_indices: [Vec<u32>; 6],
+    /// The offset in `indices` (or `quad_indices` with [`SurfaceNetsConfig::quads`]) where the boundary faces begin, if
+    /// [`SurfaceNetsConfig::generate_boundary_faces`] is enabled: the faces before it are the surface, and the ones after it are
+    /// the caps. With [`SurfaceNetsConfig::separate_boundary_indices`], the caps are in `boundary_indices` instead, so this is
+    /// the end of `indices`.
+    ///
+    /// This is `None` without boundary faces, and after appending to the buffer (e.g. with [`surface_nets_append`]) or merging
+    /// its triangles with [`merge_coplanar`], which move the caps around.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// // A sphere clipped by all six boundary planes.
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+    ///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 10.0;
+    /// }
+    /// let mut open = SurfaceNetsBuffer::default();
+    /// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut open);
+    /// assert_eq!(open.boundary_indices_start, None);
+    ///
+    /// let mut watertight = SurfaceNetsBuffer::default();
+    /// let config = SurfaceNetsConfig {
+    ///     generate_boundary_faces: true,
+    ///     ..Default::default()
+    /// };
+    /// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut watertight);
+    ///
+    /// // The surface triangles come first, and the caps after them. With the `eval-max-plane` feature, the open mesh also has
+    /// // surface quads on the max planes, which the caps replace.
+    /// let start = watertight.boundary_indices_start.unwrap();
+    /// if cfg!(not(feature = "eval-max-plane")) {
+    ///     assert_eq!(watertight.indices[..start], open.indices[..]);
+    /// }
+    /// assert!(start < watertight.indices.len());
+    /// ```
+    pub boundary_indices_start: Option<usize>,
+    /// Texture coordinates for every vertex.
+    ///
+    /// Only filled in if [`SurfaceNetsConfig::generate_uvs`] is set, or by post-processing like [`compute_atlas_uvs`], and left
+    /// empty otherwise.
+    pub uvs: Vec<[f32; 2]>,
+    /// The barycentric coordinates of every vertex in its triangle. Only filled in if
+    /// [`SurfaceNetsConfig::barycentric_wireframe`] is enabled, and empty otherwise.
+    pub barycentrics: Vec<[f32; 3]>,
+    /// The material of every vertex. Only filled in by [`surface_nets_with_materials`], and empty otherwise.
+    pub vertex_materials: Vec<u32>,
+    /// The material of every face, i.e. every triangle in `indices`, or every quad in `quad_indices` if
+    /// [`SurfaceNetsConfig::quads`] is enabled. Only filled in by [`surface_nets_with_materials`], and empty otherwise.
+    pub triangle_materials: Vec<u32>,
+    /// The axis-aligned bounding box of `positions`, as `(min, max)`, or `None` if there are no vertices, e.g. for frustum
+    /// culling.
+    ///
+    /// It's computed once the mesh is done, so it includes every vertex (like boundary caps and skirts) in the final
+    /// coordinates, i.e. after [`SurfaceNetsConfig::voxel_size`] and [`SurfaceNetsConfig::axis_remap`]. It's kept up to date
+    /// by [`apply_transform`](Self::apply_transform), but not by other post-processing that moves vertices.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{sdf, surface_nets, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// // An off-center sphere, cut off by the min X and max Z planes.
+    /// let mut field = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+    ///     field[i as usize] = sdf::sphere([x - 2.3, y - 9.1, z - 14.2], 5.0);
+    /// }
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// assert_eq!(mesh.aabb, None);
+    ///
+    /// for generate_boundary_faces in [false, true] {
+    ///     let config = SurfaceNetsConfig {
+    ///         generate_boundary_faces,
+    ///         ..Default::default()
+    ///     };
+    ///     surface_nets_with_config(&field, &ChunkShape {}, [0; 3], [17; 3], config, &mut mesh);
+    ///
+    ///     // The box bounds every vertex, and touches the extreme ones.
+    ///     let (lo, hi) = mesh.aabb.unwrap();
+    ///     for axis in 0..3 {
+    ///         assert!(mesh.positions.iter().all(|p| lo[axis] <= p[axis] && p[axis] <= hi[axis]));
+    ///         assert!(mesh.positions.iter().any(|p| p[axis] == lo[axis]));
+    ///         assert!(mesh.positions.iter().any(|p| p[axis] == hi[axis]));
+    ///     }
+    ///     // The caps close the sphere on the min X plane.
+    ///     assert_eq!(lo[0] == 0.0, generate_boundary_faces);
+    ///     assert_eq!(mesh.num_boundary_vertices > 0, generate_boundary_faces);
+    ///     assert_eq!(mesh.num_surface_cubes as usize, mesh.positions.len() - mesh.num_boundary_vertices as usize);
+    /// }
+    ///
+    /// // Nothing to bound.
+    /// surface_nets(&[1.0; ChunkShape::USIZE], &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+    /// assert_eq!(mesh.aabb, None);
+    /// assert_eq!(mesh.num_surface_cubes, 0);
+    /// ```
+    pub aabb: Option<([f32; 3], [f32; 3])>,
+    /// The number of cubes that intersect the isosurface, including any whose vertex was merged into a neighbor's (see
+    /// [`SurfaceNetsConfig::vertex_merge_distance`]).
+    pub num_surface_cubes: u32,
+    /// The number of boundary cap vertices, which are added after the surface vertices (see
+    /// [`SurfaceNetsConfig::generate_boundary_faces`]).
+    pub num_boundary_vertices: u32,
+
+    /// Local 3D array coordinates of every voxel that intersects the isosurface.
+    pub surface_points: Vec<[u32; 3]>,
+    /// Stride of every voxel that intersects the isosurface. Can be used for efficient post-processing.
+    pub surface_strides: Vec<u32>,
+    /// Used to map back from cube to vertex index, or [`NULL_VERTEX`] if the cube has no vertex.
+    ///
+    /// Only the meshed extent is covered, so this stays small when meshing a small extent of a large array. Cubes are numbered
+    /// like in [`surface_mask`](Self::surface_mask), i.e. the cube with minimal corner `[x, y, z]` is at
+    /// `((z - min_z) * size_y + (y - min_y)) * size_x + (x - min_x)`, rather than at its stride in the array.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{RuntimeShape, Shape};
+    /// use fast_surface_nets::{surface_nets, SurfaceNetsBuffer};
+    ///
+    /// // A large array, of which only a small box is meshed.
+    /// let shape = RuntimeShape::<u32, 3>::new([128, 128, 128]);
+    /// let mut sdf = vec![1.0; shape.usize()];
+    /// for i in 0u32..shape.size() {
+    ///     let [x, y, z] = shape.delinearize(i).map(|c| c as f32 - 40.5);
+    ///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 5.0;
+    /// }
+    /// let mut buffer = SurfaceNetsBuffer::default();
+    /// let [min, max] = [[32, 33, 34], [49, 48, 47]];
+    /// surface_nets(&sdf, &shape, min, max, &mut buffer);
+    ///
+    /// // The scratch is sized by the box, not the array.
+    /// let size = [0, 1, 2].map(|i| max[i] - min[i]);
+    /// assert_eq!(buffer.cube_to_index.len(), (size[0] * size[1] * size[2]) as usize);
+    /// assert!(buffer.cube_to_index.len() < sdf.len() / 500);
+    ///
+    /// let cube_shape = RuntimeShape::<u32, 3>::new(size);
+    /// for (v, &[x, y, z]) in buffer.surface_points.iter().enumerate() {
+    ///     let cube = cube_shape.linearize([x - min[0], y - min[1], z - min[2]]);
+    ///     assert_eq!(buffer.cube_to_index[cube as usize], v as u32);
+    /// }
+    /// ```
+    pub cube_to_index: Vec<u32>,
+    /// A bitset with one bit per cube of the meshed extent, set iff the cube intersects the isosurface. Only filled in if
+    /// [`SurfaceNetsConfig::generate_surface_mask`] is enabled, and empty otherwise.
+    ///
+    /// Cubes are numbered like the voxels of an array of the extent's size, with X varying fastest, i.e. the cube with minimal
+    /// corner `[x, y, z]` is bit `i = ((z - min_z) * size_y + (y - min_y)) * size_x + (x - min_x)`, which is
+    /// `surface_mask[i / 64] & (1 << (i % 64))`.
+    pub surface_mask: Vec<u64>,
+}
+
+impl SurfaceNetsBuffer {
+    /// Clears all of the buffers, but keeps the memory allocated for reuse.
+    fn reset(&mut self, num_cubes: usize) {
+        self.positions.clear();
+        self.normals.clear();
+        self.face_normals.clear();
+        self.indices.clear();
+        self.quad_indices.clear();
+        self.line_indices.clear();
+        for indices in self.boundary_indices.iter_mut() {
+            indices.clear();
+        }
+        self.boundary_indices_start = None;
+        self.uvs.clear();
+        self.barycentrics.clear();
+        self.vertex_materials.clear();
+        self.triangle_materials.clear();
+        self.aabb = None;
+        self.num_surface_cubes = 0;
+        self.num_boundary_vertices = 0;
+        self.surface_points.clear();
+        self.surface_strides.clear();
+        self.surface_mask.clear();
+
+        // Just make sure this buffer is big enough for the meshed extent, whether or not we've used it before. Its size follows
+        // the extent rather than the whole array, so meshing a small box out of a large array stays cheap.
+        self.cube_to_index.resize(num_cubes, NULL_VERTEX);
+    }
+
+    /// Reorders the vertices, e.g. after sorting them spatially for better GPU cache locality. Vertex `v` moves to index
+    /// `permutation[v]`.
+    ///
+    /// Every per-vertex attribute buffer that is populated (i.e. the same length as `positions`) is permuted, and `indices` and
+    /// `cube_to_index` are rewritten to match, so the mesh itself is unchanged.
+    ///
+    /// Panics if `permutation` is not a bijection on the vertex indices.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets, SurfaceNetsBuffer};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i);
+    ///     sdf[i as usize] = ((x * x + y * y + z * z) as f32).sqrt() - 15.0;
+    /// }
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+    /// let num_vertices = mesh.positions.len() as u32;
+    ///
+    /// // The identity permutation doesn't change anything.
+    /// let mut remapped = mesh.clone();
+    /// remapped.remap_vertices(&(0..num_vertices).collect::<Vec<_>>());
+    /// assert_eq!(remapped.positions, mesh.positions);
+    /// assert_eq!(remapped.normals, mesh.normals);
+    /// assert_eq!(remapped.indices, mesh.indices);
+    ///
+    /// // Reversing the vertices reverses every attribute, and the triangles still have the same corners.
+    /// let mut reversed = mesh.clone();
+    /// reversed.remap_vertices(&(0..num_vertices).rev().collect::<Vec<_>>());
+    /// assert!(reversed.positions.iter().eq(mesh.positions.iter().rev()));
+    /// assert!(reversed.surface_points.iter().eq(mesh.surface_points.iter().rev()));
+    /// for (&v, &reversed_v) in mesh.indices.iter().zip(reversed.indices.iter()) {
+    ///     assert_eq!(reversed_v, num_vertices - 1 - v);
+    ///     assert_eq!(reversed.positions[reversed_v as usize], mesh.positions[v as usize]);
+    /// }
+    /// for &p in mesh.surface_points.iter() {
+    ///     let v = reversed.cube_to_index[ConstShape3u32::<17, 17, 17>::linearize(p) as usize];
+    ///     assert_eq!(reversed.surface_points[v as usize], p);
+    /// }
+    /// ```
+    pub fn remap_vertices(&mut self, permutation: &[u32]) {
+        fn permute<A: Copy>(attribute: &mut Vec<A>, new_to_old: &[u32]) {
+            if attribute.len() == new_to_old.len() {
+                *attribute = new_to_old
+                    .iter()
+                    .map(|&old| attribute[old as usize])
+                    .collect();
+            }
+        }
+
+        let num_vertices = self.positions.len();
+        assert_eq!(
+            permutation.len(),
+            num_vertices,
+            "permutation must have one entry per vertex"
+        );
+        let mut new_to_old = vec![NULL_VERTEX; num_vertices];
+        for (old, &new) in permutation.iter().enumerate() {
+            assert!(
+                (new as usize) < num_vertices && new_to_old[new as usize] == NULL_VERTEX,
+                "permutation is not a bijection"
+            );
+            new_to_old[new as usize] = old as u32;
+        }
+
+        permute(&mut self.normals, &new_to_old);
+        permute(&mut self.uvs, &new_to_old);
+        permute(&mut self.barycentrics, &new_to_old);
+        permute(&mut self.vertex_materials, &new_to_old);
+        permute(&mut self.surface_points, &new_to_old);
+        permute(&mut self.surface_strides, &new_to_old);
+        permute(&mut self.positions, &new_to_old);
+        for v in self
+            .indices
+            .iter_mut()
+            .chain(self.quad_indices.iter_mut())
+            .chain(self.line_indices.iter_mut())
+            .chain(self.boundary_indices.iter_mut().flatten())
+        {
+            *v = permutation[*v as usize];
+        }
+        for v in self.cube_to_index.iter_mut() {
+            // Entries outside of the last meshed extent may be stale, so leave any that aren't valid vertices alone.
+            if let Some(&new) = permutation.get(*v as usize) {
+                *v = new;
+            }
+        }
+    }
+
+    /// Transforms the mesh by the affine map `p -> matrix * p + translation`, e.g. to place a chunk's mesh in the world.
+    ///
+    /// `matrix` is given by its columns, like `glam::Mat3::from_cols_array_2d`, and must be invertible. Positions are
+    /// transformed by the full map, and normals by the inverse transpose of `matrix` (so they stay perpendicular to the surface
+    /// under non-uniform scales, and are not normalized). If `matrix` mirrors the mesh (its determinant is negative), the
+    /// winding of every triangle is flipped too, so that front faces stay front faces.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{sdf, surface_nets, SurfaceNetsBuffer};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// let center = [7.0, 8.5, 9.0];
+    /// let mut field = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+    ///     field[i as usize] = sdf::sphere([x - center[0], y - center[1], z - center[2]], 5.0);
+    /// }
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// surface_nets(&field, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+    ///
+    /// // Mirror along X, and stretch along Y.
+    /// mesh.apply_transform([[-1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 1.0]], [1.0, 2.0, 3.0]);
+    /// let center = [1.0 - center[0], 2.0 + 2.0 * center[1], 3.0 + center[2]];
+    ///
+    /// let sub = |a: [f32; 3], b: [f32; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    /// let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    /// let cross = |a: [f32; 3], b: [f32; 3]| {
+    ///     [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+    /// };
+    ///
+    /// // The normals still point outward.
+    /// for (&p, &n) in mesh.positions.iter().zip(mesh.normals.iter()) {
+    ///     assert!(dot(n, sub(p, center)) > 0.0);
+    /// }
+    /// // And the triangles are still wound counterclockwise, seen from outside.
+    /// for tri in mesh.indices.chunks(3) {
+    ///     let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[tri[i] as usize]);
+    ///     let face_normal = cross(sub(b, a), sub(c, a));
+    ///     assert!(dot(face_normal, sub(a, center)) > 0.0);
+    ///     assert!(dot(face_normal, mesh.normals[tri[0] as usize]) > 0.0);
+    /// }
+    /// ```
+    pub fn apply_transform(&mut self, matrix: [[f32; 3]; 3], translation: [f32; 3]) {
+        let matrix = Mat3A::from_cols_array_2d(&matrix);
+        let translation = Vec3A::from(translation);
+        let normal_matrix = matrix.inverse().transpose();
+        for p in self.positions.iter_mut() {
+            *p = (matrix * Vec3A::from(*p) + translation).into();
+        }
+        for n in self.normals.iter_mut() {
+            *n = (normal_matrix * Vec3A::from(*n)).into();
+        }
+        for n in self.face_normals.iter_mut() {
+            *n = (normal_matrix * Vec3A::from(*n)).normalize_or_zero().into();
+        }
+        if matrix.determinant() < 0.0 {
+            self.flip_winding();
+        }
+        self.update_aabb();
+    }
+
+    /// Iterates over the corner positions of every triangle in `indices`, without allocating a triangle soup.
+    ///
+    /// `indices` always holds whole triangles. If it somehow doesn't, this panics in debug builds, and the trailing indices are
+    /// skipped otherwise.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets, SurfaceNetsBuffer};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+    ///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+    /// }
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// assert_eq!(mesh.triangles().count(), 0);
+    ///
+    /// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+    /// assert_eq!(mesh.triangles().count(), mesh.indices.len() / 3);
+    /// for (triangle, indices) in mesh.triangles().zip(mesh.indices.chunks(3)) {
+    ///     assert_eq!(triangle, [0, 1, 2].map(|i| mesh.positions[indices[i] as usize]));
+    /// }
+    ///
+    /// // With the normals, e.g. to check that every triangle faces away from the center of the sphere.
+    /// for (positions, normals) in mesh.triangles_with_normals() {
+    ///     for (p, n) in positions.iter().zip(normals.iter()) {
+    ///         assert!((p[0] - 8.5) * n[0] + (p[1] - 8.5) * n[1] + (p[2] - 8.5) * n[2] > 0.0);
+    ///     }
+    /// }
+    /// ```
+    pub fn triangles(&self) -> impl Iterator<Item = [[f32; 3]; 3]> + '_ {
+        debug_assert_eq!(self.indices.len() % 3, 0);
+        self.indices
+            .chunks_exact(3)
+            .map(|tri| [0, 1, 2].map(|i| self.positions[tri[i] as usize]))
+    }
+
+    /// Like [`triangles`](Self::triangles), but also yields the normals of the three corners.
+    ///
+    /// Panics if `normals` isn't populated.
+    pub fn triangles_with_normals(
+        &self,
+    ) -> impl Iterator<Item = ([[f32; 3]; 3], [[f32; 3]; 3])> + '_ {
+        assert_eq!(self.normals.len(), self.positions.len());
+        self.indices
+            .chunks_exact(3)
+            .zip(self.triangles())
+            .map(|(tri, positions)| (positions, [0, 1, 2].map(|i| self.normals[tri[i] as usize])))
+    }
+
+    /// Returns a copy of the mesh for flat shading, where every triangle has its own three vertices, whose normals are the
+    /// triangle's (unit) face normal. The indices just count up from 0, including the boundary faces in `boundary_indices`
+    /// after them, and the other per-vertex attributes are copied along.
+    ///
+    /// The face normals follow the winding, so they point out of the volume just like the gradient normals (unless
+    /// [`NormalDirection::TowardInterior`] is used).
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{sdf, surface_nets, SurfaceNetsBuffer};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// // A cube, with its faces at 3.5 and 13.5.
+    /// let mut field = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let p = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+    ///     field[i as usize] = sdf::cuboid(p, [5.0; 3]);
+    /// }
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// surface_nets(&field, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+    ///
+    /// let flat = mesh.to_flat_shaded();
+    /// assert_eq!(flat.positions.len(), mesh.indices.len());
+    /// assert!(flat.indices.iter().copied().eq(0..flat.positions.len() as u32));
+    ///
+    /// // Every triangle on a face of the cube has the face's normal at all three of its vertices.
+    /// for axis in 0..3 {
+    ///     for (plane, sign) in [(3.5, -1.0), (13.5, 1.0)] {
+    ///         let mut expected = [0.0; 3];
+    ///         expected[axis] = sign;
+    ///         let mut num_triangles = 0;
+    ///         for (positions, normals) in flat.triangles_with_normals() {
+    ///             if positions.iter().all(|p| p[axis] == plane) {
+    ///                 assert!(normals.iter().all(|&n| n == expected));
+    ///                 num_triangles += 1;
+    ///             }
+    ///         }
+    ///         assert!(num_triangles > 0);
+    ///     }
+    /// }
+    /// ```
+    pub fn to_flat_shaded(&self) -> SurfaceNetsBuffer {
+        let mut flat = self.clone();
+        flat.unweld();
+        flat.normals.clear();
+        flat.normals.resize(flat.positions.len(), [0.0; 3]);
+        let boundary_indices = flat.boundary_indices.iter();
+        for tri in std::iter::once(&flat.indices)
+            .chain(boundary_indices)
+            .flat_map(|indices| indices.chunks_exact(3))
+        {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3A::from(flat.positions[tri[i] as usize]));
+            let normal = (b - a).cross(c - a).normalize_or_zero().into();
+            for &v in tri {
+                flat.normals[v as usize] = normal;
+            }
+        }
+        flat
+    }
+
+    /// Fills `line_indices` with the unique edges of the quads or the triangles.
+    fn make_line_indices(&mut self, quads: bool) {
+        self.line_indices = if quads {
+            topology::unique_edges(&self.quad_indices, 4)
+        } else {
+            topology::unique_edges(&self.indices, 3)
+        };
+    }
+
+    /// Fills `face_normals` with the unit normal of every triangle.
+    fn make_face_normals(&mut self) {
+        let positions = &self.positions;
+        self.face_normals = self
+            .indices
+            .chunks_exact(3)
+            .map(|tri| {
+                let [a, b, c] = [0, 1, 2].map(|i| Vec3A::from(positions[tri[i] as usize]));
+                (b - a).cross(c - a).normalize_or_zero().into()
+            })
+            .collect();
+    }
+
+    /// Replaces the normals of the vertices with faces by the unit-length, area-weighted average of their face normals, which
+    /// point out of the volume unless `normal_direction` is `TowardInterior`.
+    fn make_area_weighted_normals(&mut self, quads: bool, normal_direction: NormalDirection) {
+        if self.normals.len() != self.positions.len() {
+            return;
+        }
+        let (faces, sides) = if quads {
+            (&self.quad_indices, 4)
+        } else {
+            (&self.indices, 3)
+        };
+        let positions = &self.positions;
+        let mut sums = vec![Vec3A::ZERO; positions.len()];
+        let boundary_faces = self.boundary_indices.iter().map(|indices| (indices, 3));
+        for (faces, sides) in std::iter::once((faces, sides)).chain(boundary_faces) {
+            for face in faces.chunks_exact(sides) {
+                let [a, b, c] = [0, 1, 2].map(|i| Vec3A::from(positions[face[i] as usize]));
+                let mut normal = (b - a).cross(c - a);
+                if sides == 4 {
+                    let d = Vec3A::from(positions[face[3] as usize]);
+                    normal += (c - a).cross(d - a);
+                }
+                for &v in face {
+                    sums[v as usize] += normal;
+                }
+            }
+        }
+        for (n, sum) in self.normals.iter_mut().zip(sums) {
+            if sum != Vec3A::ZERO {
+                *n = match normal_direction {
+                    NormalDirection::TowardExterior => sum.normalize_or_zero().into(),
+                    NormalDirection::TowardInterior => (-sum).normalize_or_zero().into(),
+                };
+            }
+        }
+    }
+
+    /// Fills `uvs` by projecting every vertex along the dominant axis of the area-weighted normal of its quads or triangles.
+    fn make_uvs(&mut self, projection: UvProjection, quads: bool) {
+        let (faces, sides) = if quads {
+            (&self.quad_indices, 4)
+        } else {
+            (&self.indices, 3)
+        };
+        let positions = &self.positions;
+        let mut normals = vec![Vec3A::ZERO; positions.len()];
+        for face in faces.chunks_exact(sides) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3A::from(positions[face[i] as usize]));
+            let mut normal = (b - a).cross(c - a);
+            if sides == 4 {
+                let d = Vec3A::from(positions[face[3] as usize]);
+                normal += (c - a).cross(d - a);
+            }
+            for &v in face {
+                normals[v as usize] += normal;
+            }
+        }
+        self.uvs = positions
+            .iter()
+            .zip(normals)
+            .map(|(&[x, y, z], n)| {
+                let n_abs = n.abs();
+                let (uv, is_flipped) = if n_abs.x >= n_abs.y && n_abs.x >= n_abs.z {
+                    ([z, y], [n.x > 0.0, false])
+                } else if n_abs.y >= n_abs.z {
+                    ([x, z], [false, n.y > 0.0])
+                } else {
+                    ([x, y], [n.z < 0.0, false])
+                };
+                match projection {
+                    UvProjection::Triplanar => uv,
+                    UvProjection::Box => [0, 1].map(|i| if is_flipped[i] { -uv[i] } else { uv[i] }),
+                }
+            })
+            .collect();
+    }
+
+    /// Fills in `triangle_materials` for the faces after the ones that already have a material (i.e. the skirts and boundary
+    /// caps) with the material of their first vertex.
+    fn finish_triangle_materials(&mut self, quads: bool) {
+        let (faces, sides) = if quads {
+            (&self.quad_indices, 4)
+        } else {
+            (&self.indices, 3)
+        };
+        let done = self.triangle_materials.len();
+        let vertex_materials = &self.vertex_materials;
+        self.triangle_materials.extend(
+            faces[done * sides..]
+                .chunks_exact(sides)
+                .map(|face| vertex_materials[face[0] as usize]),
+        );
+    }
+
+    /// Recomputes `aabb` from `positions`.
+    pub(crate) fn update_aabb(&mut self) {
+        self.aabb = self
+            .positions
+            .iter()
+            .map(|&p| Vec3A::from(p))
+            .fold(None, |aabb, p| match aabb {
+                Some((lo, hi)) => Some((p.min(lo), p.max(hi))),
+                None => Some((p, p)),
+            })
+            .map(|(lo, hi)| (lo.into(), hi.into()));
+    }
+
+    /// Reverses the winding of every triangle, including the boundary faces in `boundary_indices`, and every quad.
+    fn flip_winding(&mut self) {
+        let boundary_indices = self.boundary_indices.iter_mut();
+        for indices in std::iter::once(&mut self.indices).chain(boundary_indices) {
+            for tri in indices.chunks_exact_mut(3) {
+                tri.swap(1, 2);
+            }
+        }
+        for quad in self.quad_indices.chunks_exact_mut(4) {
+            quad.swap(1, 3);
+        }
+    }
+
+    /// Gives every corner of every triangle its own copy of its vertex, with barycentric coordinates. See
+    /// [`SurfaceNetsConfig::barycentric_wireframe`].
+    fn unweld_triangles(&mut self) {
+        self.unweld();
+        self.barycentrics.clear();
+        self.barycentrics.extend(
+            [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+                .into_iter()
+                .cycle()
+                .take(self.positions.len()),
+        );
+    }
+
+    /// Gives every corner of every triangle its own copy of its vertex, with all of its per-vertex attributes, so the indices
+    /// just count up from 0. `cube_to_index` points at the first copy of each vertex.
+    fn unweld(&mut self) {
+        fn gather<A: Copy>(attribute: &mut Vec<A>, num_vertices: usize, new_to_old: &[u32]) {
+            if attribute.len() == num_vertices {
+                *attribute = new_to_old
+                    .iter()
+                    .map(|&old| attribute[old as usize])
+                    .collect();
+            }
+        }
+
+        let num_vertices = self.positions.len();
+        let mut first_copy = vec![NULL_VERTEX; num_vertices];
+        let mut new_to_old = Vec::new();
+        for v in self
+            .indices
+            .iter_mut()
+            .chain(self.boundary_indices.iter_mut().flatten())
+        {
+            let new = new_to_old.len() as u32;
+            if first_copy[*v as usize] == NULL_VERTEX {
+                first_copy[*v as usize] = new;
+            }
+            new_to_old.push(*v);
+            *v = new;
+        }
+
+        gather(&mut self.normals, num_vertices, &new_to_old);
+        gather(&mut self.uvs, num_vertices, &new_to_old);
+        gather(&mut self.barycentrics, num_vertices, &new_to_old);
+        gather(&mut self.vertex_materials, num_vertices, &new_to_old);
+        gather(&mut self.surface_points, num_vertices, &new_to_old);
+        gather(&mut self.surface_strides, num_vertices, &new_to_old);
+        gather(&mut self.positions, num_vertices, &new_to_old);
+        for v in self.cube_to_index.iter_mut() {
+            if let Some(&new) = first_copy.get(*v as usize) {
+                *v = new;
+            }
+        }
+    }
+
+    /// Appends all of the vertices and triangles of `other`, offsetting its indices past the existing vertices.
+    ///
+    /// Per-vertex attribute buffers are only extended if they are populated in both buffers.
+    fn append_vertices_and_indices(&mut self, other: &SurfaceNetsBuffer) {
+        fn extend<A: Copy>(
+            attribute: &mut Vec<A>,
+            num_vertices: usize,
+            other: &[A],
+            other_num_vertices: usize,
+        ) {
+            if attribute.len() == num_vertices && other.len() == other_num_vertices {
+                attribute.extend_from_slice(other);
+            }
+        }
+
+        let (num_vertices, other_num_vertices) = (self.positions.len(), other.positions.len());
+        extend(
+            &mut self.normals,
+            num_vertices,
+            &other.normals,
+            other_num_vertices,
+        );
+        extend(&mut self.uvs, num_vertices, &other.uvs, other_num_vertices);
+        extend(
+            &mut self.barycentrics,
+            num_vertices,
+            &other.barycentrics,
+            other_num_vertices,
+        );
+        extend(
+            &mut self.vertex_materials,
+            num_vertices,
+            &other.vertex_materials,
+            other_num_vertices,
+        );
+        extend(
+            &mut self.surface_points,
+            num_vertices,
+            &other.surface_points,
+            other_num_vertices,
+        );
+        extend(
+            &mut self.surface_strides,
+            num_vertices,
+            &other.surface_strides,
+            other_num_vertices,
+        );
+        // The face normals are per triangle, so they're only kept if both buffers have one per triangle.
+        if self.face_normals.len() * 3 == self.indices.len()
+            && other.face_normals.len() * 3 == other.indices.len()
+        {
+            self.face_normals.extend_from_slice(&other.face_normals);
+        } else {
+            self.face_normals.clear();
+        }
+        let num_faces =
+            |mesh: &SurfaceNetsBuffer| mesh.indices.len() / 3 + mesh.quad_indices.len() / 4;
+        if self.triangle_materials.len() == num_faces(self)
+            && other.triangle_materials.len() == num_faces(other)
+        {
+            self.triangle_materials
+                .extend_from_slice(&other.triangle_materials);
+        } else {
+            self.triangle_materials.clear();
+        }
+        self.positions.extend_from_slice(&other.positions);
+        let offset = num_vertices as u32;
+        self.indices
+            .extend(other.indices.iter().map(|&v| v + offset));
+        self.quad_indices
+            .extend(other.quad_indices.iter().map(|&v| v + offset));
+        self.line_indices
+            .extend(other.line_indices.iter().map(|&v| v + offset));
+        self.aabb = match (self.aabb, other.aabb) {
+            (Some((lo, hi)), Some((other_lo, other_hi))) => Some((
+                Vec3A::from(lo).min(Vec3A::from(other_lo)).into(),
+                Vec3A::from(hi).max(Vec3A::from(other_hi)).into(),
+            )),
+            (aabb, other_aabb) => aabb.or(other_aabb),
+        };
+        self.num_surface_cubes += other.num_surface_cubes;
+        self.num_boundary_vertices += other.num_boundary_vertices;
+        for (indices, other_indices) in self
+            .boundary_indices
+            .iter_mut()
+            .zip(other.boundary_indices.iter())
+        {
+            indices.extend(other_indices.iter().map(|&v| v + offset));
+        }
+        // The caps of both buffers are no longer at the end.
+        self.boundary_indices_start = None;
+    }
+
+    /// Appends a copy of vertex `v`, including all of its per-vertex attributes, and returns the index of the copy.
+    ///
+    /// Attribute buffers that aren't populated (i.e. not the same length as `positions`) are left alone.
+    pub(crate) fn duplicate_vertex(&mut self, v: u32) -> u32 {
+        fn push_copy<A: Copy>(attribute: &mut Vec<A>, num_vertices: usize, v: u32) {
+            if attribute.len() == num_vertices {
+                attribute.push(attribute[v as usize]);
+            }
+        }
+
+        let num_vertices = self.positions.len();
+        push_copy(&mut self.normals, num_vertices, v);
+        push_copy(&mut self.uvs, num_vertices, v);
+        push_copy(&mut self.barycentrics, num_vertices, v);
+        push_copy(&mut self.vertex_materials, num_vertices, v);
+        push_copy(&mut self.surface_points, num_vertices, v);
+        push_copy(&mut self.surface_strides, num_vertices, v);
+        self.positions.push(self.positions[v as usize]);
+        num_vertices as u32
+    }
+}
+
+/// This cube of the meshed extent did not produce a vertex (see [`SurfaceNetsBuffer::cube_to_index`]).
+pub const NULL_VERTEX: u32 = u32::MAX;
+
+/// The area (in units of samples, before [`SurfaceNetsConfig::voxel_size`]) under which a triangle is degenerate, and skipped
+/// with [`SurfaceNetsConfig::skip_degenerate_triangles`].
+pub const DEGENERATE_TRIANGLE_AREA: f32 = 1e-6;
+
+/// The Naive Surface Nets smooth voxel meshing algorithm.
+///
+/// Extracts an isosurface mesh from the [signed distance field](https://en.wikipedia.org/wiki/Signed_distance_function) `sdf`.
+/// Each value in the field determines how close that point is to the isosurface. Negative values are considered "interior" of
+/// the surface volume, and positive values are considered "exterior." These lattice points will be considered corners of unit
+/// cubes. For each unit cube, at most one isosurface vertex will be estimated, as below, where `p` is a positive corner value,
+/// `n` is a negative corner value, `s` is an isosurface vertex, and `|` or `-` are mesh polygons connecting the vertices.
+///
+/// ```text
+/// p   p   p   p
+///   s---s
+/// p | n | p   p
+///   s   s---s
+/// p | n   n | p
+///   s---s---s
+/// p   p   p   p
+/// ```
+///
+/// The set of corners sampled is exactly the set of points in `[min, max]`. `sdf` must contain all of those points.
+///
+/// Note that the scheme illustrated above implies that chunks must be padded with a 1-voxel border copied from neighboring
+/// voxels in order to connect seamlessly.
+pub fn surface_nets<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    surface_nets_with_config(sdf, shape, min, max, SurfaceNetsConfig::default(), output);
+}
+
+/// The Naive Surface Nets smooth voxel meshing algorithm with configuration options.
+///
+/// Extracts an isosurface mesh from the [signed distance field](https://en.wikipedia.org/wiki/Signed_distance_function) `sdf`
+/// with additional configuration options for controlling mesh generation behavior.
+///
+/// When `config.generate_boundary_faces` is true, this function will generate faces on the boundaries of the sampling volume
+/// where the SDF is negative, creating watertight meshes.
+pub fn surface_nets_with_config<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+
+    mesh_samples(sdf, shape, min, max, config, None, output);
+}
+
+/// Like [`surface_nets_with_config`], but the isosurface is where `sdf[i] - threshold[i]` crosses zero, so the threshold can vary
+/// per voxel. This avoids allocating a copy of the SDF with the threshold subtracted.
+///
+/// `threshold` must be the same length as `sdf`.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{
+///     surface_nets_with_config, surface_nets_with_threshold_field, SurfaceNetsBuffer, SurfaceNetsConfig,
+/// };
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A flat plane, with a threshold that tilts it.
+/// let mut sdf = [0.0; ChunkShape::USIZE];
+/// let mut threshold = [0.0; ChunkShape::USIZE];
+/// let mut shifted_sdf = [0.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, _, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = z as f32 - 6.5;
+///     threshold[i as usize] = 0.25 * x as f32;
+///     shifted_sdf[i as usize] = sdf[i as usize] - threshold[i as usize];
+/// }
+///
+/// let config = SurfaceNetsConfig::default();
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets_with_threshold_field(&sdf, &threshold, &ChunkShape {}, [0; 3], [17; 3], config, &mut mesh);
+///
+/// // The surface is at z = 6.5 + x / 4.
+/// assert!(!mesh.indices.is_empty());
+/// for &[x, _, z] in mesh.positions.iter() {
+///     assert!((z - (6.5 + 0.25 * x)).abs() < 1e-4);
+/// }
+///
+/// // Which is the same as meshing the SDF with the threshold subtracted.
+/// let mut shifted_mesh = SurfaceNetsBuffer::default();
+/// surface_nets_with_config(&shifted_sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut shifted_mesh);
+/// assert_eq!(mesh.positions, shifted_mesh.positions);
+/// assert_eq!(mesh.indices, shifted_mesh.indices);
+/// ```
+pub fn surface_nets_with_threshold_field<T, S>(
+    sdf: &[T],
+    threshold: &[f32],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    // SAFETY
+    // Make sure the slices match the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+    assert_eq!(threshold.len(), sdf.len());
+
+    mesh_samples(
+        &ThresholdedSamples { sdf, threshold },
+        shape,
+        min,
+        max,
+        config,
+        None,
+        output,
+    );
+}
+
+/// Like [`surface_nets_with_config`], but also gives every vertex and face a material, from the per-sample `materials` (e.g.
+/// material ids) stored parallel to `sdf`, in [`SurfaceNetsBuffer::vertex_materials`] and
+/// [`SurfaceNetsBuffer::triangle_materials`].
+///
+/// The material of a surface vertex is `combine`d from the materials of the 8 corners of its cube, in `0b_zyx` order, with
+/// weights that sum to 1. Only the negative (inside) corners have weight: each one's share in the
+/// [`EdgeCentroid`](VertexPlacement::EdgeCentroid) of the edge crossings, so the material outside the surface (e.g. air) never
+/// wins. [`dominant_material`] picks the material with the most weight. Every face of the surface gets the material of the
+/// negative sample of the edge it crosses, e.g. to pick a texture per face.
+///
+/// A boundary cap vertex (see [`SurfaceNetsConfig::generate_boundary_faces`]) gets the material of the sample at the minimal
+/// corner of its cube, and skirt vertices get the material of the vertex they are copied from. Skirt and cap faces get the
+/// material of their first vertex.
+///
+/// The materials can be any integer id that fits in a `u32`, e.g. `u8` or `u16`, and they're output as `u32`. `materials` must
+/// be the same length as `sdf`.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{dominant_material, surface_nets_with_materials, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A flat floor, made of stone (1) on one side of a diagonal and grass (300) on the other, with air (0) above it.
+/// let mut sdf = [0.0; ChunkShape::USIZE];
+/// let mut materials = [0u16; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = z as f32 - 8.3;
+///     if z <= 8 {
+///         materials[i as usize] = if x + y >= 17 { 300 } else { 1 };
+///     }
+/// }
+///
+/// let config = SurfaceNetsConfig::default();
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets_with_materials(&sdf, &materials, &ChunkShape {}, [0; 3], [17; 3], config, dominant_material, &mut mesh);
+/// assert_eq!(mesh.vertex_materials.len(), mesh.positions.len());
+///
+/// // A cube across the diagonal has both materials at its inside corners, and its vertex gets the one at 3 of them.
+/// for (&[x, y, _], &material) in mesh.surface_points.iter().zip(mesh.vertex_materials.iter()) {
+///     assert_eq!(material, if x + y >= 16 { 300 } else { 1 });
+/// }
+///
+/// // Every triangle crosses a Z edge, and has the material of the sample below the floor.
+/// assert_eq!(mesh.triangle_materials.len(), mesh.indices.len() / 3);
+/// for (tri, &material) in mesh.indices.chunks(3).zip(mesh.triangle_materials.iter()) {
+///     let [x, y] = [0, 1].map(|i| tri.iter().map(|&v| mesh.surface_points[v as usize][i]).max().unwrap());
+///     assert_eq!(material, if x + y >= 17 { 300 } else { 1 });
+/// }
+///
+/// // The air above the floor is most of the weight of the edge crossings, but it's outside, so it never wins.
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = z as f32 - 2.6;
+///     materials[i as usize] = if z <= 2 { 7 } else { 0 };
+/// }
+/// surface_nets_with_materials(&sdf, &materials, &ChunkShape {}, [0; 3], [17; 3], config, dominant_material, &mut mesh);
+/// assert!(!mesh.vertex_materials.is_empty());
+/// assert!(mesh.vertex_materials.iter().chain(&mesh.triangle_materials).all(|&m| m == 7));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn surface_nets_with_materials<T, S, M, F>(
+    sdf: &[T],
+    materials: &[M],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    combine: F,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+    M: Copy + Into<u32>,
+    F: Fn([u32; 8], [f32; 8]) -> u32,
+{
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+    assert_eq!(materials.len(), sdf.len());
+
+    let material = |stride: usize| materials[stride].into();
+    let materials = MaterialSamples {
+        material: &material,
+        combine: &combine,
+    };
+    mesh_samples(sdf, shape, min, max, config, Some(&materials), output);
+    output.finish_triangle_materials(config.quads);
+}
+
+/// Combines the materials of the corners of a cube for [`surface_nets_with_materials`] by picking the one with the most total
+/// weight. Ties go to the material that comes first.
+pub fn dominant_material(materials: [u32; 8], weights: [f32; 8]) -> u32 {
+    let weight_of = |material| {
+        (0..8)
+            .filter(|&i| materials[i] == material)
+            .map(|i| weights[i])
+            .sum::<f32>()
+    };
+    let mut dominant = (materials[0], weight_of(materials[0]));
+    for &material in &materials[1..] {
+        let weight = weight_of(material);
+        if weight > dominant.1 {
+            dominant = (material, weight);
+        }
+    }
+    dominant.0
+}
+
+/// The default number of bisection steps for [`surface_nets_resampled`].
+pub const DEFAULT_BISECTION_STEPS: u32 = 4;
+
+/// Like [`surface_nets_with_config`], but for fields that are far from linear across a voxel (e.g. density or noise), where
+/// interpolating linearly between the samples misplaces the edge crossings. Each crossing is refined by resampling `field`,
+/// which gives the same signed distance as the samples (i.e. with their surface offset subtracted) at any point in array
+/// coordinates, with up to `bisection_steps` steps of bisection, and then interpolating linearly within the last interval.
+/// [`DEFAULT_BISECTION_STEPS`] is usually enough.
+///
+/// The samples still decide which cubes have a vertex, and the normals. Only the crossings are refined, so this costs
+/// `bisection_steps` calls to `field` per crossing, and [`surface_nets_with_config`] stays the fast path.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{
+///     surface_nets_resampled, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig, DEFAULT_BISECTION_STEPS,
+/// };
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A floor at z = 8.9, where the field is steep, so the crossing is at 0.9 of the edges from z = 8 to z = 9.
+/// let field = |[_, _, z]: [f32; 3]| (4.0 * (z - 8.9)).tanh();
+/// let mut sdf = [0.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     sdf[i as usize] = field(ChunkShape::delinearize(i).map(|c| c as f32));
+/// }
+/// let config = SurfaceNetsConfig::default();
+///
+/// let mut linear = SurfaceNetsBuffer::default();
+/// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut linear);
+/// let mut resampled = SurfaceNetsBuffer::default();
+/// surface_nets_resampled(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, field, DEFAULT_BISECTION_STEPS, &mut resampled);
+///
+/// // Linear interpolation is way off, and refining the crossings finds the floor.
+/// assert_eq!(linear.positions.len(), resampled.positions.len());
+/// for (l, r) in linear.positions.iter().zip(resampled.positions.iter()) {
+///     assert!((l[2] - 8.9).abs() > 0.1);
+///     assert!((r[2] - 8.9).abs() < 1e-3);
+/// }
+///
+/// // Without any bisection steps, it's the same as linear interpolation.
+/// surface_nets_resampled(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, field, 0, &mut resampled);
+/// assert_eq!(linear.positions, resampled.positions);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn surface_nets_resampled<T, S, F>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    field: F,
+    bisection_steps: u32,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+    F: Fn([f32; 3]) -> f32,
+{
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+
+    let samples = ResampledSamples {
+        samples: sdf,
+        field,
+        iso_value: config.iso_value,
+        bisection_steps,
+    };
+    mesh_samples(&samples, shape, min, max, config, None, output);
+}
+
+/// Dual Contouring: like [`surface_nets_with_config`] with [`VertexPlacement::QuadraticErrorFunction`], but with Hermite data.
+/// The edge crossings are refined by resampling `field` like in [`surface_nets_resampled`], and the plane through each of them
+/// is perpendicular to `gradient`, the gradient of the field at any point in array coordinates (e.g. analytic, or from central
+/// differences of `field`), rather than to the gradient interpolated from the samples of the cube. This recovers the sharp edges
+/// and corners of CSG-style volumes wherever they are, even in the middle of a cube, where the samples alone round them off.
+///
+/// `config.vertex_placement` is ignored. The normals are still estimated from the samples.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{
+///     dual_contouring, sdf, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig, VertexPlacement,
+///     DEFAULT_BISECTION_STEPS,
+/// };
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A box with its corners in the middle of cubes.
+/// let (center, half_extents) = ([8.3, 9.4, 7.6], [5.1, 3.7, 4.45]);
+/// let field = |p: [f32; 3]| sdf::cuboid([0, 1, 2].map(|a| p[a] - center[a]), half_extents);
+/// let gradient = |p: [f32; 3]| {
+///     let h = 1e-3;
+///     [0, 1, 2].map(|a| {
+///         let (mut p1, mut p2) = (p, p);
+///         p1[a] += h;
+///         p2[a] -= h;
+///         (field(p1) - field(p2)) / (2.0 * h)
+///     })
+/// };
+/// let mut box_sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     box_sdf[i as usize] = field(ChunkShape::delinearize(i).map(|c| c as f32));
+/// }
+///
+/// // The distance from each corner of the box to the closest vertex.
+/// let corner_errors = |mesh: &SurfaceNetsBuffer| {
+///     (0..8)
+///         .map(|c| {
+///             let corner = [0, 1, 2].map(|a| center[a] + if c >> a & 1 == 1 { half_extents[a] } else { -half_extents[a] });
+///             mesh.positions
+///                 .iter()
+///                 .map(|p| (0..3).map(|a| (p[a] - corner[a]).powi(2)).sum::<f32>().sqrt())
+///                 .fold(f32::INFINITY, f32::min)
+///         })
+///         .collect::<Vec<f32>>()
+/// };
+///
+/// let config = SurfaceNetsConfig::default();
+/// let mut hermite = SurfaceNetsBuffer::default();
+/// let steps = DEFAULT_BISECTION_STEPS;
+/// dual_contouring(&box_sdf, &ChunkShape {}, [0; 3], [17; 3], config, field, gradient, steps, &mut hermite);
+/// let mut interpolated = SurfaceNetsBuffer::default();
+/// let config = SurfaceNetsConfig { vertex_placement: VertexPlacement::QuadraticErrorFunction, ..config };
+/// surface_nets_with_config(&box_sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut interpolated);
+///
+/// assert!(corner_errors(&hermite).iter().all(|&e| e < 1e-2));
+/// assert!(corner_errors(&interpolated).iter().all(|&e| e > 0.1));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn dual_contouring<T, S, F, G>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    field: F,
+    gradient: G,
+    bisection_steps: u32,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+    F: Fn([f32; 3]) -> f32,
+    G: Fn([f32; 3]) -> [f32; 3],
+{
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+
+    let samples = HermiteSamples {
+        samples: &ResampledSamples {
+            samples: sdf,
+            field,
+            iso_value: config.iso_value,
+            bisection_steps,
+        },
+        gradient,
+    };
+    let config = SurfaceNetsConfig {
+        vertex_placement: VertexPlacement::QuadraticErrorFunction,
+        ..config
+    };
+    mesh_samples(&samples, shape, min, max, config, None, output);
+}
+
+/// Like [`surface_nets_with_config`], but for an SDF stored as a stack of separately allocated Z-planes, e.g. slices that are
+/// loaded independently, so they don't need to be copied into one contiguous array first.
+///
+/// Each plane is a `plane_shape[0] x plane_shape[1]` array with X varying fastest, and `planes[z]` is the plane at `z`. The
+/// mesh is the same as for the contiguous array of shape `[plane_shape[0], plane_shape[1], planes.len()]`, including the
+/// strides in `surface_strides`.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_with_config, surface_nets_z_planes, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+/// }
+/// let planes: Vec<Box<[f32]>> = sdf.chunks(18 * 18).map(Box::from).collect();
+///
+/// let config = SurfaceNetsConfig::default();
+/// let mut contiguous = SurfaceNetsBuffer::default();
+/// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut contiguous);
+/// let mut stacked = SurfaceNetsBuffer::default();
+/// surface_nets_z_planes(&planes, [18, 18], [0; 3], [17; 3], config, &mut stacked);
+///
+/// assert!(!stacked.indices.is_empty());
+/// assert_eq!(stacked.positions, contiguous.positions);
+/// assert_eq!(stacked.normals, contiguous.normals);
+/// assert_eq!(stacked.indices, contiguous.indices);
+/// assert_eq!(stacked.surface_strides, contiguous.surface_strides);
+/// ```
+pub fn surface_nets_z_planes<T, P>(
+    planes: &[P],
+    plane_shape: [u32; 2],
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    P: AsRef<[T]>,
+{
+    let [size_x, size_y] = plane_shape;
+    let plane_len = (size_x * size_y) as usize;
+
+    // SAFETY
+    // Make sure the planes match the shape before we start using get_unchecked.
+    assert!((0..3).all(|i| min[i] <= max[i]));
+    assert!(max[0] < size_x && max[1] < size_y && (max[2] as usize) < planes.len());
+    assert!(planes.iter().all(|plane| plane.as_ref().len() == plane_len));
+
+    let shape = RuntimeShape::<u32, 3>::new([size_x, size_y, planes.len() as u32]);
+    mesh_samples(
+        &ZPlaneSamples {
+            planes,
+            plane_len,
+            sample: PhantomData,
+        },
+        &shape,
+        min,
+        max,
+        config,
+        None,
+        output,
+    );
+}
+
+/// Like [`surface_nets_with_config`], but reads the samples from an [`SdfSource`] instead of a slice.
+///
+/// `shape` only defines the strides that the source is sampled at, so it can be the padded shape of a chunk that is never
+/// stored densely. Only the samples from `min` to `max` are read.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_from_source, surface_nets_with_config, SdfSource, SurfaceNetsBuffer};
+/// use std::collections::HashMap;
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let sphere = |[x, y, z]: [u32; 3]| {
+///     let [x, y, z] = [x, y, z].map(|c| c as f32 - 8.5);
+///     (x * x + y * y + z * z).sqrt() - 2.0
+/// };
+///
+/// // A brick map of 6x6x6 bricks, which only stores the bricks with samples inside of the volume.
+/// struct BrickMap(HashMap<[u32; 3], Box<[f32; 216]>>);
+/// impl SdfSource for BrickMap {
+///     type Sample = f32;
+///
+///     fn sample(&self, stride: usize) -> f32 {
+///         let p = ChunkShape::delinearize(stride as u32);
+///         match self.0.get(&p.map(|c| c / 6)) {
+///             Some(brick) => brick[(p[0] % 6 + 6 * (p[1] % 6 + 6 * (p[2] % 6))) as usize],
+///             None => 1.0,
+///         }
+///     }
+/// }
+/// let mut brick_map = BrickMap(HashMap::new());
+/// for brick in (0..27).map(|i| [i % 3, i / 3 % 3, i / 9]) {
+///     let samples: [f32; 216] =
+///         std::array::from_fn(|i| sphere([0, 1, 2].map(|a| 6 * brick[a] + i as u32 / 6u32.pow(a as u32) % 6)));
+///     if samples.iter().any(|&d| d < 0.0) {
+///         brick_map.0.insert(brick, Box::new(samples));
+///     }
+/// }
+/// assert_eq!(brick_map.0.len(), 1);
+///
+/// let mut dense = [0.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     dense[i as usize] = sphere(ChunkShape::delinearize(i));
+/// }
+/// let config = Default::default();
+/// let mut expected = SurfaceNetsBuffer::default();
+/// surface_nets_with_config(&dense, &ChunkShape {}, [0; 3], [17; 3], config, &mut expected);
+///
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets_from_source(&brick_map, &ChunkShape {}, [0; 3], [17; 3], config, &mut mesh);
+/// assert!(!mesh.indices.is_empty());
+/// assert_eq!(mesh.positions, expected.positions);
+/// assert_eq!(mesh.normals, expected.normals);
+/// assert_eq!(mesh.indices, expected.indices);
+///
+/// // A dense slice is a source too.
+/// surface_nets_from_source(&dense[..], &ChunkShape {}, [0; 3], [17; 3], config, &mut mesh);
+/// assert_eq!(mesh.positions, expected.positions);
+/// ```
+pub fn surface_nets_from_source<D, S>(
+    sdf: &D,
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    D: SdfSource + ?Sized,
+    S: Shape<3, Coord = u32>,
+{
+    assert!((0..3).all(|i| min[i] <= max[i]));
+
+    mesh_samples(&SourceSamples(sdf), shape, min, max, config, None, output);
+}
+
+/// Like [`surface_nets_with_config`], but for an `f64` SDF, with the positions also written to `world_positions` in double
+/// precision as `origin + voxel_size * p`, e.g. for simulation grids far from the world origin, where `f32` coordinates would
+/// visibly snap to a coarse grid.
+///
+/// Each surface point is still found relative to its cube in `f32` (which is accurate to about `1e-5` voxels for the extents
+/// that are meshed at once), so only the transform to world coordinates needs the extra precision. The iso value is
+/// subtracted from the samples in `f64` too. `output` holds the same mesh in grid coordinates, like
+/// [`surface_nets_with_config`] would, and `world_positions` is cleared first.
+///
+/// Panics if `config` sets a [`voxel_size`](SurfaceNetsConfig::voxel_size) or an [`axis_remap`](SurfaceNetsConfig::axis_remap),
+/// since the world transform is done here instead.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_f64, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A sphere with a 6 cm radius, a thousand kilometers away from the origin.
+/// let origin = [1.0e6, -2.0e6, 5.0e5];
+/// let voxel_size = [0.01; 3];
+/// let center = origin.map(|o| o + 0.085);
+/// let distance = |p: [f64; 3]| (0..3).map(|i| (p[i] - center[i]).powi(2)).sum::<f64>().sqrt() - 0.06;
+/// let mut sdf = [0.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let p = ChunkShape::delinearize(i);
+///     sdf[i as usize] = distance([0, 1, 2].map(|a| origin[a] + voxel_size[a] * p[a] as f64));
+/// }
+///
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// let mut world_positions = Vec::new();
+/// surface_nets_f64(
+///     &sdf,
+///     &ChunkShape {},
+///     [0; 3],
+///     [17; 3],
+///     origin,
+///     voxel_size,
+///     Default::default(),
+///     &mut mesh,
+///     &mut world_positions,
+/// );
+/// assert_eq!(world_positions.len(), mesh.positions.len());
+/// let mean_error = |positions: &[[f64; 3]]| positions.iter().map(|&p| distance(p).abs()).sum::<f64>() / positions.len() as f64;
+/// assert!(mean_error(&world_positions) < 0.001);
+///
+/// // In single precision, the coordinates out here are multiples of 6 cm, as large as the sphere itself.
+/// let f32_positions: Vec<[f64; 3]> = mesh
+///     .positions
+///     .iter()
+///     .map(|p| [0, 1, 2].map(|a| (origin[a] as f32 + voxel_size[a] as f32 * p[a]) as f64))
+///     .collect();
+/// assert!(mean_error(&f32_positions) > 0.01);
+///
+/// // A sample below the smallest `f32` is treated as zero rather than producing a degenerate crossing.
+/// type Small = ConstShape3u32<4, 4, 4>;
+/// let mut sdf = [1.0; Small::USIZE];
+/// sdf[Small::linearize([1, 1, 1]) as usize] = -1e-50;
+/// surface_nets_f64(&sdf, &Small {}, [0; 3], [3; 3], [0.0; 3], [1.0; 3], Default::default(), &mut mesh, &mut world_positions);
+/// assert!(mesh.positions.iter().flatten().all(|c| c.is_finite()));
+/// assert!(world_positions.iter().flatten().all(|c| c.is_finite()));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn surface_nets_f64<S>(
+    sdf: &[f64],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    origin: [f64; 3],
+    voxel_size: [f64; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+    world_positions: &mut Vec<[f64; 3]>,
+) where
+    S: Shape<3, Coord = u32>,
+{
+    assert!(
+        config.voxel_size.is_none() && config.axis_remap == AxisRemap::IDENTITY,
+        "the world transform is given by origin and voxel_size"
+    );
+
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+
+    let samples = F64Samples {
+        sdf,
+        iso_value: config.iso_value as f64,
+    };
+    let config = SurfaceNetsConfig {
+        iso_value: 0.0,
+        ..config
+    };
+    mesh_samples(&samples, shape, min, max, config, None, output);
+
+    // Take the offset of every surface point from the minimal corner of its cube (which is exact in `f32`) and add it to the
+    // corner in `f64`.
+    world_positions.clear();
+    world_positions.extend(
+        output
+            .positions
+            .iter()
+            .zip(output.surface_points.iter())
+            .map(|(position, cube)| {
+                std::array::from_fn(|i| {
+                    let offset = position[i] - cube[i] as f32;
+                    origin[i] + voxel_size[i] * (cube[i] as f64 + offset as f64)
+                })
+            }),
+    );
+}
+
+/// Like [`surface_nets_with_config`], but for a boolean occupancy grid (e.g. Minecraft-like voxels) instead of an SDF, where
+/// `true` is solid.
+///
+/// Occupancy is read as a field of -1 inside and +1 outside, which puts every edge crossing at the midpoint of its edge, so the
+/// raw mesh is blocky. To round it off, every surface vertex is then moved to the average of the surface vertices in the
+/// 3x3x3 block of cubes around it (including itself), before the quads, skirts and boundary caps are made.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_occupancy, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<34, 34, 34>;
+///
+/// let radius = |[x, y, z]: [f32; 3]| ((x - 16.5).powi(2) + (y - 16.5).powi(2) + (z - 16.5).powi(2)).sqrt();
+/// let mut occupancy = [false; ChunkShape::USIZE];
+/// let mut blocky_sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let inside = radius(ChunkShape::delinearize(i).map(|c| c as f32)) < 12.0;
+///     occupancy[i as usize] = inside;
+///     blocky_sdf[i as usize] = if inside { -1.0 } else { 1.0 };
+/// }
+///
+/// let config = SurfaceNetsConfig::default();
+/// let mut smooth = SurfaceNetsBuffer::default();
+/// surface_nets_occupancy(&occupancy, &ChunkShape {}, [0; 3], [33; 3], config, &mut smooth);
+/// let mut blocky = SurfaceNetsBuffer::default();
+/// surface_nets_with_config(&blocky_sdf, &ChunkShape {}, [0; 3], [33; 3], config, &mut blocky);
+/// assert_eq!(smooth.positions.len(), blocky.positions.len());
+///
+/// // The smoothed sphere is rounder, i.e. its radius varies less.
+/// let radius_spread = |mesh: &SurfaceNetsBuffer| {
+///     let radii: Vec<f32> = mesh.positions.iter().map(|&p| radius(p)).collect();
+///     let mean = radii.iter().sum::<f32>() / radii.len() as f32;
+///     (radii.iter().map(|r| (r - mean).powi(2)).sum::<f32>() / radii.len() as f32).sqrt()
+/// };
+/// assert!(radius_spread(&smooth) < 0.75 * radius_spread(&blocky));
+/// ```
+pub fn surface_nets_occupancy<S>(
+    occupancy: &[bool],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    S: Shape<3, Coord = u32>,
+{
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < occupancy.len());
+
+    mesh_samples(
+        &OccupancySamples { occupancy },
+        shape,
+        min,
+        max,
+        config,
+        None,
+        output,
+    );
+}
+
+/// Like [`surface_nets_with_config`], but appends the new mesh to whatever is already in `output` instead of replacing it, e.g.
+/// to accumulate several materials into a single buffer. The new triangles' indices are offset by the number of vertices
+/// already in `output`.
+///
+/// The meshing pass runs in `scratch`, which is left with the results of this pass alone, so `scratch.cube_to_index` and
+/// `scratch.surface_mask` describe this pass with indices local to it. `output.cube_to_index` and `output.surface_mask` are
+/// left untouched, since vertices from different passes can share a stride. Per-vertex buffers (`normals`, `surface_points`,
+/// etc.) are only extended if they are populated in both `output` and `scratch`.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, surface_nets_append, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let sphere = |center: f32, radius: f32| {
+///     let mut sdf = [1.0; ChunkShape::USIZE];
+///     for i in 0u32..ChunkShape::SIZE {
+///         let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - center);
+///         sdf[i as usize] = (x * x + y * y + z * z).sqrt() - radius;
+///     }
+///     sdf
+/// };
+/// let (sdf_a, sdf_b) = (sphere(5.0, 4.0), sphere(11.0, 5.0));
+///
+/// let mut combined = SurfaceNetsBuffer::default();
+/// let mut scratch = SurfaceNetsBuffer::default();
+/// let config = SurfaceNetsConfig::default();
+/// surface_nets_append(&sdf_a, &ChunkShape {}, [0; 3], [17; 3], config, &mut scratch, &mut combined);
+/// surface_nets_append(&sdf_b, &ChunkShape {}, [0; 3], [17; 3], config, &mut scratch, &mut combined);
+///
+/// // Concatenate the separate meshes by hand.
+/// let (mut mesh_a, mut mesh_b) = (SurfaceNetsBuffer::default(), SurfaceNetsBuffer::default());
+/// surface_nets(&sdf_a, &ChunkShape {}, [0; 3], [17; 3], &mut mesh_a);
+/// surface_nets(&sdf_b, &ChunkShape {}, [0; 3], [17; 3], &mut mesh_b);
+/// let offset = mesh_a.positions.len() as u32;
+/// let positions = [mesh_a.positions.clone(), mesh_b.positions.clone()].concat();
+/// let normals = [mesh_a.normals.clone(), mesh_b.normals.clone()].concat();
+/// let indices: Vec<u32> = mesh_a.indices.iter().copied().chain(mesh_b.indices.iter().map(|&v| v + offset)).collect();
+///
+/// assert_eq!(combined.positions, positions);
+/// assert_eq!(combined.normals, normals);
+/// assert_eq!(combined.indices, indices);
+/// assert_eq!(scratch.positions, mesh_b.positions);
+/// ```
+pub fn surface_nets_append<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    scratch: &mut SurfaceNetsBuffer,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    surface_nets_with_config(sdf, shape, min, max, config, scratch);
+    output.append_vertices_and_indices(scratch);
+}
+
+/// Like [`surface_nets_with_config`], but hands the mesh to `on_vertex(position, normal, voxel)` and `on_triangle(indices)`
+/// instead of leaving it in a buffer, e.g. to write it straight into a GPU staging buffer or an arena.
+///
+/// Every vertex is visited as soon as it's estimated, in index order, and every triangle as soon as its quad is made, so the
+/// normals and indices are never stored. Only the positions are kept in `scratch` (since the quads are split along their
+/// shorter diagonal), along with the per-cube lookups; reusing `scratch` for every chunk keeps those allocations around. All
+/// of the vertices are visited before any of the triangles. `voxel` is the minimal corner of the vertex's cube, like in
+/// [`SurfaceNetsBuffer::surface_points`], and `normal` is `[0.0; 3]` if normals are disabled. The
+/// [`voxel_size`](SurfaceNetsConfig::voxel_size) and [`axis_remap`](SurfaceNetsConfig::axis_remap) are applied to each
+/// vertex before it's visited.
+///
+/// Panics if `config` asks for anything that needs the whole mesh: [`quads`](SurfaceNetsConfig::quads), skirts, boundary
+/// faces, vertex merging, a barycentric wireframe, line indices, face normals, UVs, or a normal mode other than
+/// [`RawGradient`](NormalMode::RawGradient), [`Normalized`](NormalMode::Normalized) or [`None`](NormalMode::None).
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, surface_nets_visit, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+/// }
+///
+/// // Interleaved vertices, like a vertex buffer with a position and normal attribute.
+/// let (mut vertices, mut indices) = (Vec::<[f32; 6]>::new(), Vec::<u32>::new());
+/// let mut scratch = SurfaceNetsBuffer::default();
+/// surface_nets_visit(
+///     &sdf,
+///     &ChunkShape {},
+///     [0; 3],
+///     [17; 3],
+///     Default::default(),
+///     &mut scratch,
+///     |[x, y, z], [nx, ny, nz], _voxel| vertices.push([x, y, z, nx, ny, nz]),
+///     |tri| indices.extend_from_slice(&tri),
+/// );
+/// assert!(scratch.normals.is_empty() && scratch.indices.is_empty());
+///
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// assert_eq!(indices, mesh.indices);
+/// assert_eq!(vertices.len(), mesh.positions.len());
+/// for (v, (p, n)) in mesh.positions.iter().zip(mesh.normals.iter()).enumerate() {
+///     assert_eq!(vertices[v], [p[0], p[1], p[2], n[0], n[1], n[2]]);
+/// }
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn surface_nets_visit<T, S, V, F>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    scratch: &mut SurfaceNetsBuffer,
+    on_vertex: V,
+    on_triangle: F,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+    V: FnMut([f32; 3], [f32; 3], [u32; 3]),
+    F: FnMut([u32; 3]),
+{
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+    assert!(
+        !config.quads
+            && config.skirt_depth.is_none()
+            && !config.generate_boundary_faces
+            && config.vertex_merge_distance.is_none()
+            && !config.barycentric_wireframe
+            && !config.generate_line_indices
+            && !config.generate_face_normals
+            && config.generate_uvs.is_none()
+            && matches!(
+                config.normal_mode,
+                NormalMode::RawGradient | NormalMode::Normalized | NormalMode::None
+            ),
+        "only per-vertex options can be visited"
+    );
+
+    if config.iso_value != 0.0 {
+        let iso_samples = IsoSamples {
+            samples: sdf,
+            iso_value: config.iso_value,
+        };
+        visit_zero_crossing(
+            &iso_samples,
+            shape,
+            min,
+            max,
+            config,
+            scratch,
+            on_vertex,
+            on_triangle,
+        );
+    } else {
+        visit_zero_crossing(
+            sdf,
+            shape,
+            min,
+            max,
+            config,
+            scratch,
+            on_vertex,
+            on_triangle,
+        );
+    }
+}
+
+// Like `surface_nets_visit`, but the isosurface is where the samples cross zero.
+#[allow(clippy::too_many_arguments)]
+fn visit_zero_crossing<D, S, V, F>(
+    sdf: &D,
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    scratch: &mut SurfaceNetsBuffer,
+    mut on_vertex: V,
+    mut on_triangle: F,
+) where
+    D: Samples + ?Sized,
+    S: Shape<3, Coord = u32>,
+    V: FnMut([f32; 3], [f32; 3], [u32; 3]),
+    F: FnMut([u32; 3]),
+{
+    let max = cube_max(max, config.wrap);
+    scratch.reset(CubeIndexer::new(min, max).len);
+
+    // Visit each vertex in output coordinates, like `scale_to_voxel_size`, `remap_axes` and `finish_normals` would leave it,
+    // and drop its normal. The position stays for splitting the quads.
+    let renormalize = config.normal_mode == NormalMode::Normalized && config.voxel_size.is_some();
+    let mut visit_vertex = |voxel, scratch: &mut SurfaceNetsBuffer| {
+        let mut position = *scratch.positions.last().unwrap();
+        let mut normal = scratch.normals.pop().unwrap_or([0.0; 3]);
+        if let Some(voxel_size) = config.voxel_size {
+            let voxel_size = Vec3A::from(voxel_size);
+            position = (Vec3A::from(position) * voxel_size).into();
+            normal = (Vec3A::from(normal) / voxel_size).into();
+        }
+        let normal = config.axis_remap.apply(normal);
+        let normal = if renormalize {
+            Vec3A::from(normal).normalize_or_zero().into()
+        } else {
+            normal
+        };
+        on_vertex(config.axis_remap.apply(position), normal, voxel);
+    };
+    estimate_surface(sdf, shape, min, max, &config, scratch, &mut visit_vertex);
+
+    let xyz_strides = [
+        shape.linearize([1, 0, 0]) as usize,
+        shape.linearize([0, 1, 0]) as usize,
+        shape.linearize([0, 0, 1]) as usize,
+    ];
+    let cube_indexer = CubeIndexer::new(min, max);
+    let is_reflection = config.axis_remap.is_reflection();
+    let mut cube_indices = Vec::with_capacity(18);
+    for (&p, &p_stride) in scratch
+        .surface_points
+        .iter()
+        .zip(scratch.surface_strides.iter())
+    {
+        make_cube_quads(
+            sdf,
+            xyz_strides,
+            cube_indexer,
+            min,
+            max,
+            [false; 3],
+            &config,
+            p,
+            p_stride as usize,
+            &scratch.cube_to_index,
+            &scratch.positions,
+            &mut cube_indices,
+            None,
+        );
+        for tri in cube_indices.chunks_exact(3) {
+            on_triangle(if is_reflection {
+                [tri[0], tri[2], tri[1]]
+            } else {
+                [tri[0], tri[1], tri[2]]
+            });
+        }
+        cube_indices.clear();
+    }
+}
+
+/// Meshes a hollow shell of the volume with walls `wall_thickness` thick, e.g. to save material when 3D printing. This is the
+/// outer isosurface, as [`surface_nets_with_config`] would mesh it, plus the inner wall, which is the isosurface
+/// `wall_thickness` below [`iso_value`](SurfaceNetsConfig::iso_value). The inner wall faces into the hollow, i.e. its winding
+/// is flipped and its normals are negated.
+///
+/// Where the volume is thinner than the wall, there is no inner wall, so those regions are left solid. The inner wall always
+/// lies strictly inside of the outer one, so the shell never turns inside out.
+///
+/// The inner wall is meshed in `scratch` and appended to `output` like [`surface_nets_append`] does, so
+/// `output.cube_to_index` describes the outer isosurface and `scratch.cube_to_index` the inner one.
+///
+/// Panics unless `wall_thickness` is positive.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, surface_nets_shell, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let sphere = |radius: f32| {
+///     let mut sdf = [1.0; ChunkShape::USIZE];
+///     for i in 0u32..ChunkShape::SIZE {
+///         let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///         sdf[i as usize] = (x * x + y * y + z * z).sqrt() - radius;
+///     }
+///     sdf
+/// };
+/// let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+/// let centered = |p: [f32; 3]| p.map(|c| c - 8.5);
+///
+/// let config = SurfaceNetsConfig::default();
+/// let mut shell = SurfaceNetsBuffer::default();
+/// let mut scratch = SurfaceNetsBuffer::default();
+/// surface_nets_shell(&sphere(7.0), &ChunkShape {}, [0; 3], [17; 3], 2.5, config, &mut scratch, &mut shell);
+///
+/// // The shell is two concentric spheres, with the outer one facing out and the inner one facing into the hollow.
+/// let num_outer = shell.positions.len() - scratch.positions.len();
+/// assert!(num_outer > 0 && !scratch.positions.is_empty());
+/// for (v, (&p, &n)) in shell.positions.iter().zip(shell.normals.iter()).enumerate() {
+///     let radius = dot(centered(p), centered(p)).sqrt();
+///     if v < num_outer {
+///         assert!((radius - 7.0).abs() < 0.2);
+///         assert!(dot(centered(p), n) > 0.0);
+///     } else {
+///         assert!((radius - 4.5).abs() < 0.2);
+///         assert!(dot(centered(p), n) < 0.0);
+///     }
+/// }
+///
+/// // The winding of every triangle agrees with its normals.
+/// let face_normal = |tri: &[u32]| {
+///     let [a, b, c] = [0, 1, 2].map(|i| shell.positions[tri[i] as usize]);
+///     let [u, v] = [[b[0] - a[0], b[1] - a[1], b[2] - a[2]], [c[0] - a[0], c[1] - a[1], c[2] - a[2]]];
+///     [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]]
+/// };
+/// for tri in shell.indices.chunks(3) {
+///     assert!(dot(face_normal(tri), shell.normals[tri[0] as usize]) > 0.0);
+/// }
+///
+/// // A sphere that is thinner than the wall stays solid.
+/// surface_nets_shell(&sphere(2.0), &ChunkShape {}, [0; 3], [17; 3], 2.5, config, &mut scratch, &mut shell);
+/// let mut solid = SurfaceNetsBuffer::default();
+/// surface_nets(&sphere(2.0), &ChunkShape {}, [0; 3], [17; 3], &mut solid);
+/// assert!(scratch.positions.is_empty());
+/// assert_eq!(shell.positions, solid.positions);
+/// assert_eq!(shell.indices, solid.indices);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn surface_nets_shell<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    wall_thickness: f32,
+    config: SurfaceNetsConfig,
+    scratch: &mut SurfaceNetsBuffer,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    assert!(wall_thickness > 0.0);
+
+    surface_nets_with_config(sdf, shape, min, max, config, output);
+    let inner_config = SurfaceNetsConfig {
+        iso_value: config.iso_value - wall_thickness,
+        ..config
+    };
+    surface_nets_with_config(sdf, shape, min, max, inner_config, scratch);
+    scratch.flip_winding();
+    for normal in scratch.normals.iter_mut() {
+        *normal = normal.map(|c| -c);
+    }
+    output.append_vertices_and_indices(scratch);
+}
+
+/// Like [`surface_nets_with_config`], but only meshes the given `cubes` (by their minimal corners) instead of scanning the whole
+/// extent from `min` to `max`, e.g. to remesh only the cubes touched by an edit.
+///
+/// The output has exactly the quads that a full scan would generate for these cubes, in the same order. Since those quads
+/// also use the vertices of the neighboring cubes in the negative directions, the surface points of those neighbors are
+/// estimated too. Every other cube is left without a vertex in `cube_to_index`.
+///
+/// `config.generate_boundary_faces`, `config.skirt_depth` and `config.generate_surface_mask` need the whole extent, so they are
+/// ignored.
+///
+/// Panics if any cube is outside of the extent.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, surface_nets_from_cubes, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+/// }
+/// let mut full_mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut full_mesh);
+///
+/// // Mesh the cubes in one corner of the chunk, in any order and with duplicates.
+/// let mut cubes = Vec::new();
+/// for z in 0..9 {
+///     for y in 0..9 {
+///         for x in (0..9).rev() {
+///             cubes.push([x, y, z]);
+///             cubes.push([x, y, z]);
+///         }
+///     }
+/// }
+/// let mut partial_mesh = SurfaceNetsBuffer::default();
+/// let config = SurfaceNetsConfig::default();
+/// surface_nets_from_cubes(&sdf, &ChunkShape {}, &cubes, [0; 3], [17; 3], config, &mut partial_mesh);
+///
+/// // Every quad of the full mesh belongs to the cube of its maximal vertex.
+/// let quad_owner = |mesh: &SurfaceNetsBuffer, quad: &[u32]| {
+///     quad.iter().map(|&v| mesh.surface_points[v as usize]).max_by_key(|&[x, y, z]| x + y + z).unwrap()
+/// };
+/// let triangles = |mesh: &SurfaceNetsBuffer, indices: &[u32]| -> Vec<[[f32; 3]; 3]> {
+///     indices.chunks(3).map(|t| [0, 1, 2].map(|i| mesh.positions[t[i] as usize])).collect()
+/// };
+/// let owned_quads: Vec<u32> = full_mesh
+///     .indices
+///     .chunks(6)
+///     .filter(|quad| quad_owner(&full_mesh, quad).iter().all(|&c| c < 9))
+///     .flatten()
+///     .copied()
+///     .collect();
+/// assert!(!owned_quads.is_empty());
+/// assert_eq!(
+///     triangles(&partial_mesh, &partial_mesh.indices),
+///     triangles(&full_mesh, &owned_quads)
+/// );
+/// ```
+pub fn surface_nets_from_cubes<T, S>(
+    sdf: &[T],
+    shape: &S,
+    cubes: &[[u32; 3]],
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+    let max = cube_max(max, config.wrap);
+    for p in cubes.iter() {
+        assert!(
+            (0..3).all(|i| min[i] <= p[i] && p[i] < max[i]),
+            "cube {p:?} is outside of the extent"
+        );
+    }
+
+    if config.iso_value != 0.0 {
+        let iso_samples = IsoSamples {
+            samples: sdf,
+            iso_value: config.iso_value,
+        };
+        mesh_cubes(&iso_samples, shape, cubes, min, max, config, output);
+    } else {
+        mesh_cubes(sdf, shape, cubes, min, max, config, output);
+    }
+}
+
+// The meshing passes of `surface_nets_from_cubes`, on the zero crossing of `sdf`. `max` is the exclusive max of the cubes.
+fn mesh_cubes<D, S>(
+    sdf: &D,
+    shape: &S,
+    cubes: &[[u32; 3]],
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    D: Samples + ?Sized,
+    S: Shape<3, Coord = u32>,
+{
+    let cube_indexer = CubeIndexer::new(min, max);
+    output.reset(cube_indexer.len);
+    // Cubes that aren't estimated must not have stale vertices.
+    output.cube_to_index.fill(NULL_VERTEX);
+
+    // Estimate the surface points of the cubes and their neighbors in the negative directions (wrapping around on wrapped
+    // axes), in the same order as a full scan.
+    let mut estimated: Vec<(u32, [u32; 3])> = cubes
+        .iter()
+        .flat_map(|&p| {
+            CUBE_CORNERS.iter().filter_map(move |offset| {
+                let neighbor = [0, 1, 2].map(|i| match p[i].checked_sub(offset[i]) {
+                    Some(c) if c >= min[i] => Some(c),
+                    _ if config.wrap[i] => Some(max[i] - 1),
+                    _ => None,
+                });
+                match neighbor {
+                    [Some(x), Some(y), Some(z)] => Some([x, y, z]),
+                    _ => None,
+                }
+            })
+        })
+        .map(|p| (shape.linearize(p), p))
+        .collect();
+    estimated.sort_unstable();
+    estimated.dedup();
+    for &(stride, p) in estimated.iter() {
+        estimate_surface_at(
+            sdf,
+            shape,
+            min,
+            max,
+            &config,
+            cube_indexer,
+            stride,
+            p,
+            output,
+        );
+    }
+    match config.normal_mode {
+        NormalMode::Smoothed => smooth_normals(min, max, output),
+        NormalMode::CentralDifference => {
+            central_difference_normals(sdf, shape, min, max, &config, output)
+        }
+        NormalMode::RawGradient
+        | NormalMode::None
+        | NormalMode::Normalized
+        | NormalMode::AreaWeightedFaceAverage => {}
+    }
+
+    // Only make the quads of the given cubes, since their neighbors' neighbors weren't estimated.
+    let mut active: Vec<(u32, [u32; 3])> = cubes.iter().map(|&p| (shape.linearize(p), p)).collect();
+    active.sort_unstable();
+    active.dedup();
+    let xyz_strides = [
+        shape.linearize([1, 0, 0]) as usize,
+        shape.linearize([0, 1, 0]) as usize,
+        shape.linearize([0, 0, 1]) as usize,
+    ];
+    for &(stride, p) in active.iter() {
+        if output.cube_to_index[cube_indexer.index(p)] != NULL_VERTEX {
+            make_cube_quads(
+                sdf,
+                xyz_strides,
+                cube_indexer,
+                min,
+                max,
+                [false; 3],
+                &config,
+                p,
+                stride as usize,
+                &output.cube_to_index,
+                &output.positions,
+                if config.quads {
+                    &mut output.quad_indices
+                } else {
+                    &mut output.indices
+                },
+                None,
+            );
+        }
+    }
+    scale_to_voxel_size(config.voxel_size, output);
+    remap_axes(config.axis_remap, output);
+    finish_normals(&config, output);
+    if config.barycentric_wireframe {
+        output.unweld_triangles();
+    }
+    if config.generate_line_indices {
+        output.make_line_indices(config.quads);
+    }
+    if config.generate_face_normals {
+        output.make_face_normals();
+    }
+    if let Some(projection) = config.generate_uvs {
+        output.make_uvs(projection, config.quads);
+    }
+    output.update_aabb();
+}
+
+/// Remeshes the extent from `min` to `max` after its samples from `dirty_min` to `dirty_max` (inclusive) were edited, e.g. by a
+/// sculpting brush, by updating the mesh of the same extent and `config` that `output` holds from before the edit.
+///
+/// Only the cubes with a corner in the dirty region are estimated again. Every other surface point keeps its position and
+/// normal, so the SDF is only sampled around the edit. The quads are made again for the whole extent, which only reads the
+/// signs of the samples next to the surface points. The result is exactly the same as meshing the whole extent again with
+/// [`surface_nets_with_config`]: the vertices before the dirty region (with X varying fastest, then Y, then Z) keep their
+/// indices, and the ones after it are shifted by the number of surface points that the edit added or removed.
+///
+/// The options that need the whole extent (`generate_boundary_faces`, `skirt_depth`, `generate_surface_mask`,
+/// `vertex_merge_distance`, `barycentric_wireframe`, `voxel_size`, `axis_remap`, `wrap`, and the `Smoothed` and
+/// `CentralDifference` normal modes) remesh the whole extent instead. So does an `output` whose `cube_to_index` doesn't fit
+/// the extent, e.g. a new buffer.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{
+///     surface_nets_remesh_region, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig, UvProjection,
+/// };
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+/// }
+/// // The attributes made from the finished mesh are made again too.
+/// let config = SurfaceNetsConfig {
+///     generate_line_indices: true,
+///     generate_face_normals: true,
+///     generate_uvs: Some(UvProjection::Box),
+///     ..Default::default()
+/// };
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut mesh);
+///
+/// // Dig a hole into the side of the sphere, then add a bump on top of it.
+/// let edits = [([2, 7, 7], [5, 9, 10], 1.0), ([8, 8, 14], [9, 9, 15], -0.5)];
+/// for (dirty_min, dirty_max, value) in edits {
+///     for z in dirty_min[2]..=dirty_max[2] {
+///         for y in dirty_min[1]..=dirty_max[1] {
+///             for x in dirty_min[0]..=dirty_max[0] {
+///                 sdf[ChunkShape::linearize([x, y, z]) as usize] = value;
+///             }
+///         }
+///     }
+///     let first_dirty_vertex = mesh.surface_points.iter().position(|p| {
+///         (0..3).all(|i| dirty_min[i] <= p[i] + 1 && p[i] <= dirty_max[i])
+///     });
+///     let previous = mesh.clone();
+///     surface_nets_remesh_region(&sdf, &ChunkShape {}, [0; 3], [17; 3], dirty_min, dirty_max, config, &mut mesh);
+///
+///     let mut full = SurfaceNetsBuffer::default();
+///     surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut full);
+///     assert_eq!(mesh.positions, full.positions);
+///     assert_eq!(mesh.normals, full.normals);
+///     assert_eq!(mesh.indices, full.indices);
+///     assert_eq!(mesh.surface_points, full.surface_points);
+///     assert_eq!(mesh.surface_strides, full.surface_strides);
+///     assert_eq!(mesh.cube_to_index, full.cube_to_index);
+///     assert_eq!(mesh.line_indices, full.line_indices);
+///     assert_eq!(mesh.face_normals, full.face_normals);
+///     assert_eq!(mesh.uvs, full.uvs);
+///     assert_eq!(mesh.aabb, full.aabb);
+///
+///     // The vertices before the edit are untouched.
+///     let n = first_dirty_vertex.unwrap();
+///     assert_eq!(mesh.positions[..n], previous.positions[..n]);
+/// }
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn surface_nets_remesh_region<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    dirty_min: [u32; 3],
+    dirty_max: [u32; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    let needs_whole_extent = config.generate_boundary_faces
+        || config.skirt_depth.is_some()
+        || config.generate_surface_mask
+        || config.vertex_merge_distance.is_some()
+        || config.barycentric_wireframe
+        || config.voxel_size.is_some()
+        || config.axis_remap != AxisRemap::IDENTITY
+        || config.wrap.contains(&true)
+        || matches!(
+            config.normal_mode,
+            NormalMode::Smoothed | NormalMode::CentralDifference
+        );
+    let num_cubes = CubeIndexer::new(min, cube_max(max, config.wrap)).len;
+    if needs_whole_extent || output.cube_to_index.len() != num_cubes {
+        surface_nets_with_config(sdf, shape, min, max, config, output);
+        return;
+    }
+
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+
+    if config.iso_value != 0.0 {
+        let iso_samples = IsoSamples {
+            samples: sdf,
+            iso_value: config.iso_value,
+        };
+        remesh_region(
+            &iso_samples,
+            shape,
+            min,
+            max,
+            dirty_min,
+            dirty_max,
+            config,
+            output,
+        );
+    } else {
+        remesh_region(sdf, shape, min, max, dirty_min, dirty_max, config, output);
+    }
+}
+
+// The meshing passes of `surface_nets_remesh_region`, on the zero crossing of `sdf`. `max` is the exclusive max of the cubes.
+#[allow(clippy::too_many_arguments)]
+fn remesh_region<D, S>(
+    sdf: &D,
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    dirty_min: [u32; 3],
+    dirty_max: [u32; 3],
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    D: Samples + ?Sized,
+    S: Shape<3, Coord = u32>,
+{
+    // The cubes with a corner in the dirty region, up to the exclusive `dirty_cubes_max`.
+    let dirty_cubes_min = [0, 1, 2].map(|i| dirty_min[i].saturating_sub(1).max(min[i]));
+    let dirty_cubes_max = [0, 1, 2].map(|i| (dirty_max[i] + 1).min(max[i]));
+    let is_dirty =
+        |p: [u32; 3]| (0..3).all(|i| dirty_cubes_min[i] <= p[i] && p[i] < dirty_cubes_max[i]);
+
+    let cube_indexer = CubeIndexer::new(min, max);
+    let positions = std::mem::take(&mut output.positions);
+    let normals = std::mem::take(&mut output.normals);
+    let surface_points = std::mem::take(&mut output.surface_points);
+    let surface_strides = std::mem::take(&mut output.surface_strides);
+    output.reset(cube_indexer.len);
+    output.cube_to_index.fill(NULL_VERTEX);
+
+    let keep = |v: usize, output: &mut SurfaceNetsBuffer| {
+        output.num_surface_cubes += 1;
+        output.cube_to_index[cube_indexer.index(surface_points[v])] = output.positions.len() as u32;
+        output.positions.push(positions[v]);
+        if let Some(&normal) = normals.get(v) {
+            output.normals.push(normal);
+        }
+        output.surface_points.push(surface_points[v]);
+        output.surface_strides.push(surface_strides[v]);
+    };
+
+    // Interleave the kept surface points with the dirty cubes, in the same order as a full scan.
+    let mut kept = (0..positions.len())
+        .filter(|&v| !is_dirty(surface_points[v]))
+        .peekable();
+    for z in dirty_cubes_min[2]..dirty_cubes_max[2] {
+        for y in dirty_cubes_min[1]..dirty_cubes_max[1] {
+            for x in dirty_cubes_min[0]..dirty_cubes_max[0] {
+                let p = [x, y, z];
+                let cube = cube_indexer.index(p);
+                while let Some(v) = kept.next_if(|&v| cube_indexer.index(surface_points[v]) < cube)
+                {
+                    keep(v, output);
+                }
+                estimate_surface_at(
+                    sdf,
+                    shape,
+                    min,
+                    max,
+                    &config,
+                    cube_indexer,
+                    shape.linearize(p),
+                    p,
+                    output,
+                );
+            }
+        }
+    }
+    for v in kept {
+        keep(v, output);
+    }
+
+    make_all_quads(sdf, shape, min, max, [false; 6], &config, &[], None, output);
+    finish_normals(&config, output);
+    if config.generate_line_indices {
+        output.make_line_indices(config.quads);
+    }
+    if config.generate_face_normals {
+        output.make_face_normals();
+    }
+    if let Some(projection) = config.generate_uvs {
+        output.make_uvs(projection, config.quads);
+    }
+    output.update_aabb();
+}
+
+// Estimate the surface point of the single cube `p` (at `stride`), like a full scan would, and push it if it has one. `max` is
+// the exclusive max of the cubes.
+#[allow(clippy::too_many_arguments)]
+fn estimate_surface_at<D, S>(
+    sdf: &D,
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: &SurfaceNetsConfig,
+    cube_indexer: CubeIndexer,
+    stride: u32,
+    p: [u32; 3],
+    output: &mut SurfaceNetsBuffer,
+) where
+    D: Samples + ?Sized,
+    S: Shape<3, Coord = u32>,
+{
+    let [x, y, z] = p;
+    let face_strides = x_face_strides(
+        shape,
+        [y, z],
+        [min[1], min[2]],
+        [max[1], max[2]],
+        [config.wrap[1], config.wrap[2]],
+    );
+    let max_x = if config.wrap[0] && x + 1 == max[0] {
+        min[0]
+    } else {
+        x + 1
+    };
+    let min_face = sample_x_face(sdf, shape.linearize([x, 0, 0]), face_strides);
+    let max_face = sample_x_face(sdf, shape.linearize([max_x, 0, 0]), face_strides);
+    let p_vec = Vec3A::from(p.map(|c| c as f32));
+    if estimate_surface_in_cube(sdf, &min_face, &max_face, p_vec, config, output) {
+        output.num_surface_cubes += 1;
+        output.cube_to_index[cube_indexer.index(p)] = output.positions.len() as u32 - 1;
+        output.surface_points.push(p);
+        output.surface_strides.push(stride);
+    }
+}
+
+// The per-sample materials meshed by `surface_nets_with_materials`. `material` reads the material of the sample at a stride,
+// and `combine` blends the materials of the 8 corners of a cube with their weights.
+struct MaterialSamples<'a> {
+    material: &'a dyn Fn(usize) -> u32,
+    combine: &'a dyn Fn([u32; 8], [f32; 8]) -> u32,
+}
+
+// Resets `output` and runs every meshing pass, on the isosurface at `config.iso_value`. If there are `materials`, they are
+// meshed into `output.vertex_materials`.
+fn mesh_samples<D, S>(
+    sdf: &D,
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    materials: Option<&MaterialSamples>,
+    output: &mut SurfaceNetsBuffer,
+) where
+    D: Samples + ?Sized,
+    S: Shape<3, Coord = u32>,
+{
+    if config.iso_value != 0.0 {
+        let iso_samples = IsoSamples {
+            samples: sdf,
+            iso_value: config.iso_value,
+        };
+        mesh_zero_crossing(&iso_samples, shape, min, max, config, materials, output);
+    } else {
+        mesh_zero_crossing(sdf, shape, min, max, config, materials, output);
+    }
+}
+
+// Like `mesh_samples`, but the isosurface is where the samples cross zero.
+fn mesh_zero_crossing<D, S>(
+    sdf: &D,
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    materials: Option<&MaterialSamples>,
+    output: &mut SurfaceNetsBuffer,
+) where
+    D: Samples + ?Sized,
+    S: Shape<3, Coord = u32>,
+{
+    let max = cube_max(max, config.wrap);
+    output.reset(CubeIndexer::new(min, max).len);
+
+    let merged_cubes = estimate_surface(sdf, shape, min, max, &config, output, &mut |_, _| {});
+    if let Some(materials) = materials {
+        surface_materials(sdf, shape, min, max, config.wrap, materials, output);
+    }
+    if D::SMOOTH_POSITIONS {
+        smooth_surface_positions(min, max, output);
+    }
+    let closed_planes = closed_planes(min, max, &config, &output.surface_points);
+    make_all_quads(
+        sdf,
+        shape,
+        min,
+        max,
+        closed_planes,
+        &config,
+        &merged_cubes,
+        materials,
+        output,
+    );
+
+    if let Some(depth) = config.skirt_depth {
+        make_skirts(sdf, shape, min, max, config.wrap, depth, output);
+    }
+    if config.generate_boundary_faces {
+        output.boundary_indices_start = Some(if config.quads {
+            output.quad_indices.len()
+        } else {
+            output.indices.len()
+        });
+        make_boundary_faces(
+            sdf,
+            shape,
+            min,
+            max,
+            closed_planes,
+            &config,
+            materials,
+            output,
+        );
+    }
+    scale_to_voxel_size(config.voxel_size, output);
+    remap_axes(config.axis_remap, output);
+    finish_normals(&config, output);
+    if config.barycentric_wireframe {
+        output.unweld_triangles();
+    }
+    if config.generate_line_indices {
+        output.make_line_indices(config.quads);
+    }
+    if config.generate_face_normals {
+        output.make_face_normals();
+    }
+    if let Some(projection) = config.generate_uvs {
+        output.make_uvs(projection, config.quads);
+    }
+    output.update_aabb();
+}
+
+// The exclusive max of the cubes in the extent with max sample `max`. On wrapped axes, there is one more cube, between the max
+// and min samples.
+fn cube_max(max: [u32; 3], wrap: [bool; 3]) -> [u32; 3] {
+    [0, 1, 2].map(|i| max[i] + wrap[i] as u32)
+}
+
+// Scale the mesh from grid units to `voxel_size`. Normals are gradients, so they scale by the reciprocal.
+fn scale_to_voxel_size(voxel_size: Option<[f32; 3]>, output: &mut SurfaceNetsBuffer) {
+    let Some(voxel_size) = voxel_size else {
+        return;
+    };
+    let voxel_size = Vec3A::from(voxel_size);
+    for p in output.positions.iter_mut() {
+        *p = (Vec3A::from(*p) * voxel_size).into();
+    }
+    for n in output.normals.iter_mut() {
+        *n = (Vec3A::from(*n) / voxel_size).into();
+    }
+}
+
+// Apply the output axis remap, reversing the winding if it's a reflection.
+fn remap_axes(axis_remap: AxisRemap, output: &mut SurfaceNetsBuffer) {
+    if axis_remap == AxisRemap::IDENTITY {
+        return;
+    }
+    for p in output.positions.iter_mut() {
+        *p = axis_remap.apply(*p);
+    }
+    for n in output.normals.iter_mut() {
+        *n = axis_remap.apply(*n);
+    }
+    if axis_remap.is_reflection() {
+        output.flip_winding();
+    }
+}
+
+// Apply the normal modes that need the finished mesh, in output coordinates.
+fn finish_normals(config: &SurfaceNetsConfig, output: &mut SurfaceNetsBuffer) {
+    let is_unit = matches!(
+        config.normal_mode,
+        NormalMode::Normalized | NormalMode::AreaWeightedFaceAverage
+    );
+    // The normals were normalized when they were estimated, but scaling them to the voxel size changes their length.
+    if is_unit && config.voxel_size.is_some() {
+        for n in output.normals.iter_mut() {
+            *n = Vec3A::from(*n).normalize_or_zero().into();
+        }
+    }
+    if config.normal_mode == NormalMode::AreaWeightedFaceAverage {
+        output.make_area_weighted_normals(config.quads, config.normal_direction);
+    }
+}
+
+/// Errors detected by [`surface_nets_checked`] before meshing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceNetsError {
+    /// The sampled extent has fewer than 2 samples along `axis` (0 = X, 1 = Y, 2 = Z), so it doesn't contain a single cube.
+    ChunkTooSmall { axis: usize },
+    /// `min` is greater than `max` along `axis`.
+    InvertedExtent { axis: usize },
+    /// `max` is outside of the shape along `axis`.
+    OutOfBounds { axis: usize },
+    /// The SDF slice has `len` samples, fewer than the `required` size of the shape, e.g. when a buffer is reused for a larger
+    /// shape.
+    SdfTooShort { len: usize, required: usize },
+}
 
-        // Just make sure this buffer is big enough, whether or not we've used it before.
-        self.stride_to_index.resize(array_size, NULL_VERTEX);
+impl std::fmt::Display for SurfaceNetsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ChunkTooSmall { axis } => {
+                write!(
+                    f,
+                    "sampled extent has fewer than 2 samples along axis {axis}"
+                )
+            }
+            Self::InvertedExtent { axis } => {
+                write!(f, "min is greater than max along axis {axis}")
+            }
+            Self::OutOfBounds { axis } => {
+                write!(f, "max is outside of the shape along axis {axis}")
+            }
+            Self::SdfTooShort { len, required } => {
+                write!(f, "SDF has {len} samples, but the shape has {required}")
+            }
+        }
     }
 }
 
-/// This stride of the SDF array did not produce a vertex.
-pub const NULL_VERTEX: u32 = u32::MAX;
+impl std::error::Error for SurfaceNetsError {}
 
-/// The Naive Surface Nets smooth voxel meshing algorithm.
+/// Like [`surface_nets_with_config`], but validates the inputs first, returning an error instead of panicking (or silently
+/// producing nothing) when they are unusable.
 ///
-/// Extracts an isosurface mesh from the [signed distance field](https://en.wikipedia.org/wiki/Signed_distance_function) `sdf`.
-/// Each value in the field determines how close that point is to the isosurface. Negative values are considered "interior" of
-/// the surface volume, and positive values are considered "exterior." These lattice points will be considered corners of unit
-/// cubes. For each unit cube, at most one isosurface vertex will be estimated, as below, where `p` is a positive corner value,
-/// `n` is a negative corner value, `s` is an isosurface vertex, and `|` or `-` are mesh polygons connecting the vertices.
+/// The extent from `min` to `max` must be inside of `shape`, and `sdf` must have a sample for every point of `shape`. With
+/// these checked, the unchecked sampling in the hot loop can't read past the end of `sdf`.
 ///
-/// ```text
-/// p   p   p   p
-///   s---s
-/// p | n | p   p
-///   s   s---s
-/// p | n   n | p
-///   s---s---s
-/// p   p   p   p
 /// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets_checked, SurfaceNetsBuffer, SurfaceNetsConfig, SurfaceNetsError};
 ///
-/// The set of corners sampled is exactly the set of points in `[min, max]`. `sdf` must contain all of those points.
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
 ///
-/// Note that the scheme illustrated above implies that chunks must be padded with a 1-voxel border copied from neighboring
-/// voxels in order to connect seamlessly.
-pub fn surface_nets<T, S>(
+/// let sdf = [1.0; ChunkShape::USIZE];
+/// let mut buffer = SurfaceNetsBuffer::default();
+/// let config = SurfaceNetsConfig {
+///     generate_boundary_faces: true,
+///     ..Default::default()
+/// };
+///
+/// // Only a single sample along Y.
+/// let result = surface_nets_checked(&sdf, &ChunkShape {}, [0; 3], [17, 0, 17], config, &mut buffer);
+/// assert_eq!(result, Err(SurfaceNetsError::ChunkTooSmall { axis: 1 }));
+///
+/// // Min and max are the wrong way around along X.
+/// let result = surface_nets_checked(&sdf, &ChunkShape {}, [17, 0, 0], [0, 17, 17], config, &mut buffer);
+/// assert_eq!(result, Err(SurfaceNetsError::InvertedExtent { axis: 0 }));
+///
+/// // The shape ends at 17 along Z.
+/// let result = surface_nets_checked(&sdf, &ChunkShape {}, [0; 3], [17, 17, 18], config, &mut buffer);
+/// assert_eq!(result, Err(SurfaceNetsError::OutOfBounds { axis: 2 }));
+///
+/// // The slice is too short for the shape, even though the extent only covers part of it.
+/// let result = surface_nets_checked(&sdf[..1000], &ChunkShape {}, [0; 3], [8; 3], config, &mut buffer);
+/// assert_eq!(result, Err(SurfaceNetsError::SdfTooShort { len: 1000, required: ChunkShape::USIZE }));
+///
+/// assert!(surface_nets_checked(&sdf, &ChunkShape {}, [0; 3], [17; 3], config, &mut buffer).is_ok());
+/// ```
+pub fn surface_nets_checked<T, S>(
     sdf: &[T],
     shape: &S,
     min: [u32; 3],
     max: [u32; 3],
+    config: SurfaceNetsConfig,
     output: &mut SurfaceNetsBuffer,
-) where
+) -> Result<(), SurfaceNetsError>
+where
     T: SignedDistance,
     S: Shape<3, Coord = u32>,
 {
-    surface_nets_with_config(sdf, shape, min, max, SurfaceNetsConfig::default(), output);
+    if let Some(axis) = (0..3).find(|&axis| max[axis] < min[axis]) {
+        return Err(SurfaceNetsError::InvertedExtent { axis });
+    }
+    if let Some(axis) = (0..3).find(|&axis| max[axis] == min[axis]) {
+        return Err(SurfaceNetsError::ChunkTooSmall { axis });
+    }
+    let shape_max = shape.as_array();
+    if let Some(axis) = (0..3).find(|&axis| max[axis] >= shape_max[axis]) {
+        return Err(SurfaceNetsError::OutOfBounds { axis });
+    }
+    if sdf.len() < shape.usize() {
+        return Err(SurfaceNetsError::SdfTooShort {
+            len: sdf.len(),
+            required: shape.usize(),
+        });
+    }
+
+    surface_nets_with_config(sdf, shape, min, max, config, output);
+
+    Ok(())
 }
 
-/// The Naive Surface Nets smooth voxel meshing algorithm with configuration options.
+/// Like [`surface_nets_with_config`], but only estimates the surface points and their normals, skipping face generation
+/// entirely. This is useful when the output is consumed as a point cloud.
 ///
-/// Extracts an isosurface mesh from the [signed distance field](https://en.wikipedia.org/wiki/Signed_distance_function) `sdf`
-/// with additional configuration options for controlling mesh generation behavior.
+/// `output.indices` is always left empty. Since no faces are generated, `config.generate_boundary_faces` is ignored.
 ///
-/// When `config.generate_boundary_faces` is true, this function will generate faces on the boundaries of the sampling volume
-/// where the SDF is negative, creating watertight meshes.
-pub fn surface_nets_with_config<T, S>(
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, surface_nets_points, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = ((x * x + y * y + z * z) as f32).sqrt() - 15.0;
+/// }
+///
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// let mut points = SurfaceNetsBuffer::default();
+/// surface_nets_points(&sdf, &ChunkShape {}, [0; 3], [17; 3], SurfaceNetsConfig::default(), &mut points);
+///
+/// assert_eq!(points.positions, mesh.positions);
+/// assert_eq!(points.normals, mesh.normals);
+/// assert!(points.indices.is_empty());
+/// ```
+pub fn surface_nets_points<T, S>(
     sdf: &[T],
     shape: &S,
     min: [u32; 3],
@@ -185,87 +4065,662 @@ pub fn surface_nets_with_config<T, S>(
     assert!(shape.linearize(min) <= shape.linearize(max));
     assert!((shape.linearize(max) as usize) < sdf.len());
 
-    output.reset(sdf.len());
+    let max = cube_max(max, config.wrap);
+    output.reset(CubeIndexer::new(min, max).len);
 
-    estimate_surface(sdf, shape, min, max, output);
-    make_all_quads(sdf, shape, min, max, output);
-    
-    if config.generate_boundary_faces {
-        make_boundary_faces(sdf, shape, min, max, output);
+    if config.iso_value != 0.0 {
+        let iso_samples = IsoSamples {
+            samples: sdf,
+            iso_value: config.iso_value,
+        };
+        estimate_surface(
+            &iso_samples,
+            shape,
+            min,
+            max,
+            &config,
+            output,
+            &mut |_, _| {},
+        );
+    } else {
+        estimate_surface(sdf, shape, min, max, &config, output, &mut |_, _| {});
+    }
+    scale_to_voxel_size(config.voxel_size, output);
+    remap_axes(config.axis_remap, output);
+    finish_normals(&config, output);
+    output.update_aabb();
+}
+
+/// Returns `true` iff the cube with minimal corner at `min_corner_stride` intersects the isosurface, i.e. some but not all of its
+/// 8 corners are negative. This is exactly the test used by [`surface_nets`] to decide whether a cube gets a vertex, so it can be
+/// used to cheaply build occupancy masks without meshing.
+///
+/// Panics if any corner of the cube is out of bounds of `sdf`.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{cube_has_surface, surface_nets, SurfaceNetsBuffer, NULL_VERTEX};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = ((x * x + y * y + z * z) as f32).sqrt() - 15.0;
+/// }
+/// let mut buffer = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut buffer);
+///
+/// for z in 0..17 {
+///     for y in 0..17 {
+///         for x in 0..17 {
+///             let stride = ChunkShape::linearize([x, y, z]);
+///             let cube = ConstShape3u32::<17, 17, 17>::linearize([x, y, z]);
+///             let has_vertex = buffer.cube_to_index[cube as usize] != NULL_VERTEX;
+///             assert_eq!(cube_has_surface(&sdf, &ChunkShape {}, stride), has_vertex);
+///         }
+///     }
+/// }
+///
+/// // The same holds for an extent that doesn't start at the origin.
+/// let [min, max] = [[3, 1, 2], [14, 17, 9]];
+/// surface_nets(&sdf, &ChunkShape {}, min, max, &mut buffer);
+/// let size = [0, 1, 2].map(|i| max[i] - min[i]);
+/// for z in min[2]..max[2] {
+///     for y in min[1]..max[1] {
+///         for x in min[0]..max[0] {
+///             let stride = ChunkShape::linearize([x, y, z]);
+///             let cube = ((z - min[2]) * size[1] + (y - min[1])) * size[0] + (x - min[0]);
+///             let has_vertex = buffer.cube_to_index[cube as usize] != NULL_VERTEX;
+///             assert_eq!(cube_has_surface(&sdf, &ChunkShape {}, stride), has_vertex);
+///         }
+///     }
+/// }
+/// ```
+#[inline]
+pub fn cube_has_surface<T, S>(sdf: &[T], shape: &S, min_corner_stride: u32) -> bool
+where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    let mut num_negative = 0;
+    for corner in CUBE_CORNERS {
+        let corner_stride = min_corner_stride + shape.linearize(corner);
+        if sdf[corner_stride as usize].is_negative() {
+            num_negative += 1;
+        }
+    }
+    num_negative != 0 && num_negative != 8
+}
+
+/// Returns the number of cubes in the extent from `min` to `max` that intersect the isosurface, i.e. the number of surface
+/// points [`surface_nets`] would generate, e.g. to cheaply prioritize which chunks to mesh first.
+///
+/// This only tests the signs of the corners, and allocates nothing.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{count_surface_cubes, surface_nets, SurfaceNetsBuffer};
+/// use std::alloc::{GlobalAlloc, Layout, System};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// // Counts every allocation.
+/// struct CountingAllocator;
+/// static NUM_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+/// unsafe impl GlobalAlloc for CountingAllocator {
+///     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+///         NUM_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+///         unsafe { System.alloc(layout) }
+///     }
+///     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+///         unsafe { System.dealloc(ptr, layout) }
+///     }
+/// }
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// fn main() {
+///     let mut sdf = vec![1.0; ChunkShape::USIZE];
+///     for i in 0u32..ChunkShape::SIZE {
+///         let [x, y, z] = ChunkShape::delinearize(i);
+///         sdf[i as usize] = ((x * x + y * y + z * z) as f32).sqrt() - 15.0;
+///     }
+///     let mut buffer = SurfaceNetsBuffer::default();
+///     for [min, max] in [[[0; 3], [17; 3]], [[3, 1, 2], [14, 17, 9]]] {
+///         surface_nets(&sdf, &ChunkShape {}, min, max, &mut buffer);
+///
+///         let num_allocations = NUM_ALLOCATIONS.load(Ordering::SeqCst);
+///         let count = count_surface_cubes(&sdf, &ChunkShape {}, min, max);
+///         assert_eq!(NUM_ALLOCATIONS.load(Ordering::SeqCst), num_allocations);
+///
+///         assert_eq!(count as usize, buffer.surface_points.len());
+///     }
+/// }
+/// ```
+pub fn count_surface_cubes<T, S>(sdf: &[T], shape: &S, min: [u32; 3], max: [u32; 3]) -> u32
+where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    // SAFETY
+    // Make sure the slice matches the shape before we start using get_unchecked.
+    assert!(shape.linearize(min) <= shape.linearize(max));
+    assert!((shape.linearize(max) as usize) < sdf.len());
+
+    let x_stride = shape.linearize([1, 0, 0]);
+    let face_strides = [
+        0,
+        shape.linearize([0, 1, 0]),
+        shape.linearize([0, 0, 1]),
+        shape.linearize([0, 1, 1]),
+    ];
+    let mut count = 0;
+    for z in min[2]..max[2] {
+        for y in min[1]..max[1] {
+            let mut stride = shape.linearize([min[0], y, z]);
+            let mut min_face = sample_x_face(sdf, stride, face_strides);
+            for _ in min[0]..max[0] {
+                let max_face = sample_x_face(sdf, stride + x_stride, face_strides);
+                let num_negative = min_face.num_negative + max_face.num_negative;
+                if num_negative != 0 && num_negative != 8 {
+                    count += 1;
+                }
+                min_face = max_face;
+                stride += x_stride;
+            }
+        }
+    }
+    count
+}
+
+/// Returns the inclusive `(min, max)` corners of the voxels that meshing the extent from `min` to `max` with `config` will read,
+/// e.g. for prefetching samples or validating the padding around a chunk.
+///
+/// Every cube reads its own 8 corners (this covers the quads, boundary caps and skirts too), so this is the extent itself,
+/// widened by [`required_padding`] for options that sample neighboring voxels. Wrapped axes are never widened, since those reads
+/// wrap around the extent.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{
+///     read_extent, surface_nets_with_config, BoundaryCapMode, NormalMode, SurfaceNetsBuffer, SurfaceNetsConfig,
+/// };
+///
+/// type ChunkShape = ConstShape3u32<24, 24, 24>;
+///
+/// let [min, max] = [[3, 4, 5], [19, 18, 17]];
+/// let sphere = |[x, y, z]: [u32; 3]| {
+///     let [x, y, z] = [x as f32 - 11.0, y as f32 - 11.0, z as f32 - 11.0];
+///     (x * x + y * y + z * z).sqrt() - 7.5
+/// };
+///
+/// let configs = [
+///     SurfaceNetsConfig::default(),
+///     SurfaceNetsConfig {
+///         generate_boundary_faces: true,
+///         boundary_cap_mode: BoundaryCapMode::FaceCrossing,
+///         ..Default::default()
+///     },
+///     SurfaceNetsConfig {
+///         generate_boundary_faces: true,
+///         boundary_cap_mode: BoundaryCapMode::SnapToInterior,
+///         ..Default::default()
+///     },
+///     SurfaceNetsConfig {
+///         skirt_depth: Some(1.0),
+///         ..Default::default()
+///     },
+///     SurfaceNetsConfig {
+///         normal_mode: NormalMode::CentralDifference,
+///         normal_stencil_radius: 2,
+///         ..Default::default()
+///     },
+/// ];
+/// for config in configs {
+///     let (read_min, read_max) = read_extent(min, max, config);
+///     let in_extent = |p: [u32; 3]| (0..3).all(|i| read_min[i] <= p[i] && p[i] <= read_max[i]);
+///
+///     // Samples outside of the reported extent must not affect the mesh.
+///     let meshes = [-1.0, 1.0].map(|outside| {
+///         let mut sdf = vec![outside; ChunkShape::USIZE];
+///         for i in 0u32..ChunkShape::SIZE {
+///             let p = ChunkShape::delinearize(i);
+///             if in_extent(p) {
+///                 sdf[i as usize] = sphere(p);
+///             }
+///         }
+///         let mut mesh = SurfaceNetsBuffer::default();
+///         surface_nets_with_config(&sdf, &ChunkShape {}, min, max, config, &mut mesh);
+///         mesh
+///     });
+///     assert!(!meshes[0].indices.is_empty());
+///     assert_eq!(meshes[0].positions, meshes[1].positions);
+///     assert_eq!(meshes[0].normals, meshes[1].normals);
+///     assert_eq!(meshes[0].indices, meshes[1].indices);
+/// }
+/// ```
+pub fn read_extent(
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+) -> ([u32; 3], [u32; 3]) {
+    let padding = required_padding(config);
+    let padding = [0, 1, 2].map(|i| if config.wrap[i] { 0 } else { padding });
+    (
+        [0, 1, 2].map(|i| min[i].saturating_sub(padding[i])),
+        [0, 1, 2].map(|i| max[i] + padding[i]),
+    )
+}
+
+/// Returns how many voxels of padding `config` needs around the meshed extent (on every side), beyond the 1-voxel border that
+/// neighboring chunks always need to connect seamlessly. See [`read_extent`].
+///
+/// Only [`NormalMode::CentralDifference`] reads outside of the extent, by `normal_stencil_radius` voxels.
+pub fn required_padding(config: SurfaceNetsConfig) -> u32 {
+    match config.normal_mode {
+        NormalMode::RawGradient
+        | NormalMode::None
+        | NormalMode::Smoothed
+        | NormalMode::Normalized
+        | NormalMode::AreaWeightedFaceAverage => 0,
+        NormalMode::CentralDifference => config.normal_stencil_radius.max(1),
     }
 }
 
 // Find all vertex positions and normals. Also generate a map from grid position to vertex index to be used to look up vertices
 // when generating quads.
-fn estimate_surface<T, S>(
-    sdf: &[T],
+//
+// Neighboring cubes along X share a face, so the corners of each cube's max X face are kept for the next cube in the row. This
+// way, only 4 new corners are read per cube instead of 8.
+//
+// `[maxx, maxy, maxz]` is the exclusive max of the cubes (see `cube_max`), so on wrapped axes the corners past it are read from
+// the min of the extent.
+//
+// `on_vertex` is called with the cube of every new (unmerged) vertex as soon as it's been estimated, before the normal passes.
+fn estimate_surface<D, S>(
+    sdf: &D,
     shape: &S,
     [minx, miny, minz]: [u32; 3],
     [maxx, maxy, maxz]: [u32; 3],
+    config: &SurfaceNetsConfig,
     output: &mut SurfaceNetsBuffer,
-) where
-    T: SignedDistance,
+    on_vertex: &mut impl FnMut([u32; 3], &mut SurfaceNetsBuffer),
+) -> Vec<([u32; 3], u32)>
+where
+    D: Samples + ?Sized,
     S: Shape<3, Coord = u32>,
 {
+    let x_stride = shape.linearize([1, 0, 0]);
+    let min_x_offset = shape.linearize([minx, 0, 0]);
+    if config.generate_surface_mask {
+        let num_cubes = CubeIndexer::new([minx, miny, minz], [maxx, maxy, maxz]).len;
+        output.surface_mask.resize(num_cubes.div_ceil(64), 0);
+    }
+    let cube_strides = CubeIndexer::new([minx, miny, minz], [maxx, maxy, maxz]).strides;
+    let mut merged_cubes = Vec::new();
+    let mut cube = 0;
     for z in minz..maxz {
         for y in miny..maxy {
+            let face_strides = x_face_strides(
+                shape,
+                [y, z],
+                [miny, minz],
+                [maxy, maxz],
+                [config.wrap[1], config.wrap[2]],
+            );
+            let mut stride = shape.linearize([minx, y, z]);
+            let mut x_offset = min_x_offset;
+            let mut min_face = sample_x_face(sdf, x_offset, face_strides);
             for x in minx..maxx {
-                let stride = shape.linearize([x, y, z]);
+                let max_x_offset = if config.wrap[0] && x + 1 == maxx {
+                    min_x_offset
+                } else {
+                    x_offset + x_stride
+                };
+                let max_face = sample_x_face(sdf, max_x_offset, face_strides);
                 let p = Vec3A::from([x as f32, y as f32, z as f32]);
-                if estimate_surface_in_cube(sdf, shape, p, stride, output) {
-                    output.stride_to_index[stride as usize] = output.positions.len() as u32 - 1;
-                    output.surface_points.push([x, y, z]);
-                    output.surface_strides.push(stride);
+                if estimate_surface_in_cube(sdf, &min_face, &max_face, p, config, output) {
+                    output.num_surface_cubes += 1;
+                    let merged_into = config.vertex_merge_distance.and_then(|distance| {
+                        merge_with_neighbor(
+                            [x > minx, y > miny, z > minz],
+                            cube,
+                            cube_strides,
+                            distance,
+                            output,
+                        )
+                    });
+                    if let Some(neighbor) = merged_into {
+                        output.cube_to_index[cube] = neighbor;
+                        merged_cubes.push(([x, y, z], stride));
+                    } else {
+                        output.cube_to_index[cube] = output.positions.len() as u32 - 1;
+                        output.surface_points.push([x, y, z]);
+                        output.surface_strides.push(stride);
+                        on_vertex([x, y, z], output);
+                    }
+                    if config.generate_surface_mask {
+                        output.surface_mask[cube / 64] |= 1 << (cube % 64);
+                    }
                 } else {
-                    output.stride_to_index[stride as usize] = NULL_VERTEX;
+                    output.cube_to_index[cube] = NULL_VERTEX;
                 }
+                min_face = max_face;
+                stride += x_stride;
+                x_offset += x_stride;
+                cube += 1;
             }
         }
     }
+
+    match config.normal_mode {
+        NormalMode::Smoothed => smooth_normals([minx, miny, minz], [maxx, maxy, maxz], output),
+        NormalMode::CentralDifference => central_difference_normals(
+            sdf,
+            shape,
+            [minx, miny, minz],
+            [maxx, maxy, maxz],
+            config,
+            output,
+        ),
+        NormalMode::RawGradient
+        | NormalMode::None
+        | NormalMode::Normalized
+        | NormalMode::AreaWeightedFaceAverage => {}
+    }
+
+    merged_cubes
+}
+
+// If the vertex just estimated for `cube` is within `distance` of the vertex of its neighbor at -X, -Y or -Z (where
+// `has_neighbor`), removes it and returns the neighbor's vertex instead.
+fn merge_with_neighbor(
+    has_neighbor: [bool; 3],
+    cube: usize,
+    cube_strides: [usize; 3],
+    distance: f32,
+    output: &mut SurfaceNetsBuffer,
+) -> Option<u32> {
+    let position = Vec3A::from(*output.positions.last().unwrap());
+    let neighbor = (0..3)
+        .filter(|&axis| has_neighbor[axis])
+        .map(|axis| output.cube_to_index[cube - cube_strides[axis]])
+        .find(|&neighbor| {
+            neighbor != NULL_VERTEX
+                && position.distance(Vec3A::from(output.positions[neighbor as usize])) <= distance
+        })?;
+    output.positions.pop();
+    if output.normals.len() > output.positions.len() {
+        output.normals.pop();
+    }
+    Some(neighbor)
+}
+
+// Replace the normal of every surface point with the average normal of the surface points in the 3x3x3 block of cubes around
+// it.
+fn smooth_normals(min: [u32; 3], max: [u32; 3], output: &mut SurfaceNetsBuffer) {
+    let cube_indexer = CubeIndexer::new(min, max);
+    let mut smoothed = Vec::with_capacity(output.normals.len());
+    for &p in output.surface_points.iter() {
+        let lo = [0, 1, 2].map(|i| p[i].saturating_sub(1).max(min[i]));
+        let hi = [0, 1, 2].map(|i| (p[i] + 1).min(max[i] - 1));
+        let mut sum = Vec3A::ZERO;
+        let mut count = 0;
+        for z in lo[2]..=hi[2] {
+            for y in lo[1]..=hi[1] {
+                for x in lo[0]..=hi[0] {
+                    let index = output.cube_to_index[cube_indexer.index([x, y, z])];
+                    if index != NULL_VERTEX {
+                        sum += Vec3A::from(output.normals[index as usize]);
+                        count += 1;
+                    }
+                }
+            }
+        }
+        // The surface point itself is always counted.
+        smoothed.push((sum / count as f32).into());
+    }
+    output.normals.copy_from_slice(&smoothed);
+}
+
+// Replace the position of every surface point with the average position of the surface points in the 3x3x3 block of cubes
+// around it. This runs before any other vertices are made.
+fn smooth_surface_positions(min: [u32; 3], max: [u32; 3], output: &mut SurfaceNetsBuffer) {
+    let cube_indexer = CubeIndexer::new(min, max);
+    let mut smoothed = Vec::with_capacity(output.positions.len());
+    for &p in output.surface_points.iter() {
+        let lo = [0, 1, 2].map(|i| p[i].saturating_sub(1).max(min[i]));
+        let hi = [0, 1, 2].map(|i| (p[i] + 1).min(max[i] - 1));
+        let mut sum = Vec3A::ZERO;
+        let mut count = 0;
+        for z in lo[2]..=hi[2] {
+            for y in lo[1]..=hi[1] {
+                for x in lo[0]..=hi[0] {
+                    let index = output.cube_to_index[cube_indexer.index([x, y, z])];
+                    if index != NULL_VERTEX {
+                        sum += Vec3A::from(output.positions[index as usize]);
+                        count += 1;
+                    }
+                }
+            }
+        }
+        // The surface point itself is always counted.
+        smoothed.push((sum / count as f32).into());
+    }
+    output.positions.copy_from_slice(&smoothed);
 }
 
-// Consider the grid-aligned cube where `p` is the minimal corner. Find a point inside this cube that is approximately on the
-// isosurface.
+// Replace the normal of every surface point with the central-difference gradient of the SDF at the corners of its cube, with a
+// stencil of `config.normal_stencil_radius` voxels, trilinearly interpolated to the surface point.
 //
-// This is done by estimating, for each cube edge, where the isosurface crosses the edge (if it does at all). Then the estimated
-// surface point is the average of these edge crossings.
-fn estimate_surface_in_cube<T, S>(
-    sdf: &[T],
+// `max` is the exclusive max of the cubes. The stencil wraps around the extent on wrapped axes. On the others, the surface points
+// whose stencil leaves the array keep their normal.
+fn central_difference_normals<D, S>(
+    sdf: &D,
     shape: &S,
-    p: Vec3A,
-    min_corner_stride: u32,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: &SurfaceNetsConfig,
     output: &mut SurfaceNetsBuffer,
-) -> bool
+) where
+    D: Samples + ?Sized,
+    S: Shape<3, Coord = u32>,
+{
+    let radius = config.normal_stencil_radius.max(1) as i64;
+    let dims = shape.as_array();
+    let stride = |p: [i64; 3]| {
+        let p = [0, 1, 2].map(|i| {
+            if config.wrap[i] {
+                let period = (max[i] - min[i]) as i64;
+                (min[i] as i64 + (p[i] - min[i] as i64).rem_euclid(period)) as u32
+            } else {
+                p[i] as u32
+            }
+        });
+        shape.linearize(p) as usize
+    };
+    let read = |p: [i64; 3]| sdf.distance(stride(p));
+    // The points of the stencil of each corner of the cube `p`.
+    let stencil = |p: [u32; 3]| {
+        CUBE_CORNERS.into_iter().flat_map(move |corner| {
+            let c = [0, 1, 2].map(|i| (p[i] + corner[i]) as i64);
+            (0..6).map(move |j| {
+                let mut q = c;
+                q[j / 2] += if j % 2 == 0 { radius } else { -radius };
+                q
+            })
+        })
+    };
+    // Whether the stencils of all of the corners of the cube `p` are in the shape, and in the samples, which can be shorter.
+    let fits = |p: [u32; 3]| {
+        (0..3).all(|i| {
+            let p = p[i] as i64;
+            config.wrap[i] || (p >= radius && p + 1 + radius < dims[i] as i64)
+        }) && stencil(p).all(|q| stride(q) < sdf.len())
+    };
+
+    for (v, &p) in output.surface_points.iter().enumerate() {
+        if !fits(p) {
+            // Keep the `RawGradient` normal.
+            continue;
+        }
+        let t = Vec3A::from(output.positions[v]) - Vec3A::from(p.map(|c| c as f32));
+        let mut gradient = Vec3A::ZERO;
+        for corner in CUBE_CORNERS {
+            let c = [0, 1, 2].map(|i| (p[i] + corner[i]) as i64);
+            let mut corner_gradient = Vec3A::ZERO;
+            let mut weight = 1.0;
+            for axis in 0..3 {
+                let [mut forward, mut backward] = [c, c];
+                forward[axis] += radius;
+                backward[axis] -= radius;
+                corner_gradient[axis] = (read(forward) - read(backward)) / (2 * radius) as f32;
+                weight *= if corner[axis] == 1 {
+                    t[axis]
+                } else {
+                    1.0 - t[axis]
+                };
+            }
+            gradient += weight * corner_gradient;
+        }
+        output.normals[v] = match config.normal_direction {
+            NormalDirection::TowardExterior => gradient.into(),
+            NormalDirection::TowardInterior => (-gradient).into(),
+        };
+    }
+}
+
+// The 4 corners of a cube face perpendicular to the X axis, in `0b_zy` order.
+#[derive(Clone, Copy)]
+struct XFaceSamples {
+    dists: [f32; 4],
+    num_negative: u32,
+}
+
+// The strides of the corners of an X face of the cubes in row `[y, z]`, relative to the X coordinate of the face, in `0b_zy`
+// order. On wrapped axes, the corners at `max` are read from `min`.
+fn x_face_strides<S>(
+    shape: &S,
+    [y, z]: [u32; 2],
+    min: [u32; 2],
+    max: [u32; 2],
+    wrap: [bool; 2],
+) -> [u32; 4]
 where
-    T: SignedDistance,
     S: Shape<3, Coord = u32>,
 {
-    // Get the signed distance values at each corner of this cube.
-    let mut corner_dists = [0f32; 8];
+    let [y1, z1] = [0, 1].map(|i| {
+        let c = [y, z][i] + 1;
+        if wrap[i] && c == max[i] {
+            min[i]
+        } else {
+            c
+        }
+    });
+    [[y, z], [y1, z], [y, z1], [y1, z1]].map(|[y, z]| shape.linearize([0, y, z]))
+}
+
+// Reads the X face with corners at `base_stride + face_strides[i]`.
+#[inline]
+fn sample_x_face<D>(sdf: &D, base_stride: u32, face_strides: [u32; 4]) -> XFaceSamples
+where
+    D: Samples + ?Sized,
+{
+    let mut dists = [0f32; 4];
     let mut num_negative = 0;
-    for (i, dist) in corner_dists.iter_mut().enumerate() {
-        let corner_stride = min_corner_stride + shape.linearize(CUBE_CORNERS[i]);
-        let d = *unsafe { sdf.get_unchecked(corner_stride as usize) };
-        *dist = d.into();
-        if d.is_negative() {
+    for (dist, face_stride) in dists.iter_mut().zip(face_strides) {
+        let stride = (base_stride + face_stride) as usize;
+        *dist = sdf.distance(stride);
+        if sdf.is_negative(stride) {
             num_negative += 1;
         }
     }
+    XFaceSamples {
+        dists,
+        num_negative,
+    }
+}
 
+// Consider the grid-aligned cube where `p` is the minimal corner, with the given min and max X faces. Find a point inside this
+// cube that is approximately on the isosurface.
+//
+// This is done by estimating, for each cube edge, where the isosurface crosses the edge (if it does at all). Then the estimated
+// surface point is the average of these edge crossings (see `VertexPlacement` for the alternatives).
+fn estimate_surface_in_cube<D>(
+    sdf: &D,
+    min_face: &XFaceSamples,
+    max_face: &XFaceSamples,
+    p: Vec3A,
+    config: &SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) -> bool
+where
+    D: Samples + ?Sized,
+{
+    let num_negative = min_face.num_negative + max_face.num_negative;
     if num_negative == 0 || num_negative == 8 {
         // No crossings.
         return false;
     }
 
-    let c = centroid_of_edge_intersections(&corner_dists);
+    // Interleave the faces to get the signed distance values at each corner of this cube, in `0b_zyx` order.
+    let [n00, n10, n01, n11] = min_face.dists;
+    let [p00, p10, p01, p11] = max_face.dists;
+    let corner_dists = [n00, p00, n10, p10, n01, p01, n11, p11];
+
+    let crossing = |corner1, corner2, d1, d2| sdf.edge_crossing(p, corner1, corner2, d1, d2);
+    let c = match config.vertex_placement {
+        VertexPlacement::EdgeCentroid => centroid_of_edge_intersections(&corner_dists, &crossing),
+        VertexPlacement::CubeCenter => Vec3A::splat(0.5),
+        VertexPlacement::MassCenter => mass_center_of_edge_intersections(&corner_dists, &crossing),
+        VertexPlacement::GradientWeighted => {
+            gradient_weighted_edge_intersections(&corner_dists, &crossing)
+        }
+        VertexPlacement::QuadraticErrorFunction => {
+            let gradient = |c| sdf.crossing_gradient(p, &corner_dists, c);
+            qef_minimizer(&corner_dists, &crossing, &gradient)
+        }
+    };
+    let c = match config.relaxation {
+        Some(relaxation) => relax_in_cube(&corner_dists, c, relaxation),
+        None => c,
+    };
+    let c = match config.max_vertex_offset {
+        Some(max_offset) => {
+            Vec3A::splat(0.5) + (c - Vec3A::splat(0.5)).clamp_length_max(max_offset)
+        }
+        None => c,
+    };
+    let c = match config.clamp_vertices_to_cube {
+        Some(margin) => c.clamp(Vec3A::splat(margin), Vec3A::splat(1.0 - margin)),
+        None => c,
+    };
 
     output.positions.push((p + c).into());
-    output.normals.push(sdf_gradient(&corner_dists, c).into());
+    if config.normal_mode != NormalMode::None {
+        let normal = sdf_gradient(&corner_dists, c);
+        let normal = match config.normal_mode {
+            NormalMode::Normalized | NormalMode::AreaWeightedFaceAverage => {
+                normal.normalize_or_zero()
+            }
+            _ => normal,
+        };
+        output.normals.push(match config.normal_direction {
+            NormalDirection::TowardExterior => normal.into(),
+            NormalDirection::TowardInterior => (-normal).into(),
+        });
+    }
 
     true
 }
 
-fn centroid_of_edge_intersections(dists: &[f32; 8]) -> Vec3A {
+// `crossing` finds where the isosurface crosses an edge, like `estimate_surface_edge_intersection`.
+fn centroid_of_edge_intersections(
+    dists: &[f32; 8],
+    crossing: &impl Fn(u32, u32, f32, f32) -> Vec3A,
+) -> Vec3A {
     let mut count = 0;
     let mut sum = Vec3A::ZERO;
     for &[corner1, corner2] in CUBE_EDGES.iter() {
@@ -273,13 +4728,85 @@ fn centroid_of_edge_intersections(dists: &[f32; 8]) -> Vec3A {
         let d2 = dists[corner2 as usize];
         if (d1 < 0.0) != (d2 < 0.0) {
             count += 1;
-            sum += estimate_surface_edge_intersection(corner1, corner2, d1, d2);
+            sum += crossing(corner1, corner2, d1, d2);
         }
     }
 
     sum / count as f32
 }
 
+// Same as `centroid_of_edge_intersections`, but each crossing is weighted by how negative its interior corner is.
+fn mass_center_of_edge_intersections(
+    dists: &[f32; 8],
+    crossing: &impl Fn(u32, u32, f32, f32) -> Vec3A,
+) -> Vec3A {
+    let mut total_weight = 0.0;
+    let mut sum = Vec3A::ZERO;
+    for &[corner1, corner2] in CUBE_EDGES.iter() {
+        let d1 = dists[corner1 as usize];
+        let d2 = dists[corner2 as usize];
+        if (d1 < 0.0) != (d2 < 0.0) {
+            let weight = -d1.min(d2);
+            total_weight += weight;
+            sum += weight * crossing(corner1, corner2, d1, d2);
+        }
+    }
+
+    if total_weight > 0.0 {
+        sum / total_weight
+    } else {
+        // Every negative corner is only barely negative, so fall back to the plain centroid.
+        centroid_of_edge_intersections(dists, crossing)
+    }
+}
+
+// Same as `centroid_of_edge_intersections`, but each crossing is weighted by the change of the SDF along its edge.
+fn gradient_weighted_edge_intersections(
+    dists: &[f32; 8],
+    crossing: &impl Fn(u32, u32, f32, f32) -> Vec3A,
+) -> Vec3A {
+    let mut total_weight = 0.0;
+    let mut sum = Vec3A::ZERO;
+    for &[corner1, corner2] in CUBE_EDGES.iter() {
+        let d1 = dists[corner1 as usize];
+        let d2 = dists[corner2 as usize];
+        if (d1 < 0.0) != (d2 < 0.0) {
+            let weight = d1.abs() + d2.abs();
+            total_weight += weight;
+            sum += weight * crossing(corner1, corner2, d1, d2);
+        }
+    }
+
+    sum / total_weight
+}
+
+// Find the point that minimizes the sum of squared distances to the planes through the edge crossings, perpendicular to the
+// `gradient` at each crossing. The solution is relative to the centroid of the crossings, and slightly regularized toward it,
+// so that directions the planes don't constrain stay at the centroid.
+fn qef_minimizer(
+    dists: &[f32; 8],
+    crossing: &impl Fn(u32, u32, f32, f32) -> Vec3A,
+    gradient: &impl Fn(Vec3A) -> Vec3A,
+) -> Vec3A {
+    const REGULARIZATION: f32 = 1e-3;
+
+    let centroid = centroid_of_edge_intersections(dists, crossing);
+    let mut ata = Mat3A::IDENTITY * REGULARIZATION;
+    let mut atb = Vec3A::ZERO;
+    for &[corner1, corner2] in CUBE_EDGES.iter() {
+        let d1 = dists[corner1 as usize];
+        let d2 = dists[corner2 as usize];
+        if (d1 < 0.0) != (d2 < 0.0) {
+            let crossing = crossing(corner1, corner2, d1, d2);
+            let n = gradient(crossing).normalize_or_zero();
+            ata += Mat3A::from_cols(n * n.x, n * n.y, n * n.z);
+            atb += n * n.dot(crossing - centroid);
+        }
+    }
+
+    (centroid + ata.inverse() * atb).clamp(Vec3A::ZERO, Vec3A::ONE)
+}
+
 // Given two cube corners, find the point between them where the SDF is zero. (This might not exist).
 fn estimate_surface_edge_intersection(
     corner1: u32,
@@ -294,6 +4821,78 @@ fn estimate_surface_edge_intersection(
         + interp1 * CUBE_CORNER_VECTORS[corner2 as usize]
 }
 
+// Push the material of every surface vertex, combined from the materials of the corners of its cube. Each negative corner is
+// weighted by how much it contributes to the centroid of the edge crossings, i.e. with the same interpolation, and the positive
+// corners get no weight.
+fn surface_materials<D, S>(
+    sdf: &D,
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    wrap: [bool; 3],
+    materials: &MaterialSamples,
+    output: &mut SurfaceNetsBuffer,
+) where
+    D: Samples + ?Sized,
+    S: Shape<3, Coord = u32>,
+{
+    for &cube in output.surface_points.iter() {
+        let strides = CUBE_CORNERS
+            .map(|corner| wrapped_corner_stride(shape, cube, corner, min, max, wrap) as usize);
+        let dists = strides.map(|stride| sdf.distance(stride));
+        let mut weights = [0.0; 8];
+        let mut total = 0.0;
+        for &[corner1, corner2] in CUBE_EDGES.iter() {
+            let (d1, d2) = (dists[corner1 as usize], dists[corner2 as usize]);
+            if (d1 < 0.0) != (d2 < 0.0) {
+                let interp1 = d1 / (d1 - d2);
+                let (inside, share) = if d1 < 0.0 {
+                    (corner1, 1.0 - interp1)
+                } else {
+                    (corner2, interp1)
+                };
+                weights[inside as usize] += share;
+                total += share;
+            }
+        }
+        // If the crossings are all at the positive corners, the negative corners share the weight evenly.
+        let weights = if total > 0.0 {
+            weights.map(|w| w / total)
+        } else {
+            let num_negative = dists.iter().filter(|&&d| d < 0.0).count() as f32;
+            dists.map(|d| if d < 0.0 { 1.0 / num_negative } else { 0.0 })
+        };
+        output.vertex_materials.push((materials.combine)(
+            strides.map(materials.material),
+            weights,
+        ));
+    }
+}
+
+// Newton steps toward the zero of the trilinear interpolation of `dists`, starting from `c`. See `VertexRelaxation`.
+fn relax_in_cube(dists: &[f32; 8], mut c: Vec3A, relaxation: VertexRelaxation) -> Vec3A {
+    for _ in 0..relaxation.iterations {
+        let gradient = sdf_gradient(dists, c);
+        let length_squared = gradient.length_squared();
+        if length_squared <= f32::EPSILON {
+            break;
+        }
+        c -= relaxation.step * sdf_trilinear(dists, c) / length_squared * gradient;
+        if relaxation.clamp_to_cube {
+            c = c.clamp(Vec3A::ZERO, Vec3A::ONE);
+        }
+    }
+    c
+}
+
+// The trilinear interpolation of the corner values `dists`, in `0b_zyx` order, at `s`.
+fn sdf_trilinear(dists: &[f32; 8], s: Vec3A) -> f32 {
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let [x00, x10, x01, x11] =
+        [0b000, 0b010, 0b100, 0b110].map(|i| lerp(dists[i], dists[i | 1], s.x));
+    lerp(lerp(x00, x10, s.y), lerp(x01, x11, s.y), s.z)
+}
+
 /// Calculate the normal as the gradient of the distance field. Don't bother making it a unit vector, since we'll do that on the
 /// GPU.
 ///
@@ -330,14 +4929,21 @@ fn sdf_gradient(dists: &[f32; 8], s: Vec3A) -> Vec3A {
 // For every edge that crosses the isosurface, make a quad between the "centers" of the four cubes touching that surface. The
 // "centers" are actually the vertex positions found earlier. Also make sure the triangles are facing the right way. See the
 // comments on `maybe_make_quad` to help with understanding the indexing.
-fn make_all_quads<T, S>(
-    sdf: &[T],
+//
+// The `closed_planes` will be capped by `make_boundary_faces`, so they get no surface quads.
+#[allow(clippy::too_many_arguments)]
+fn make_all_quads<D, S>(
+    sdf: &D,
     shape: &S,
-    [minx, miny, minz]: [u32; 3],
-    [maxx, maxy, maxz]: [u32; 3],
+    min: [u32; 3],
+    max: [u32; 3],
+    closed_planes: [bool; 6],
+    config: &SurfaceNetsConfig,
+    merged_cubes: &[([u32; 3], u32)],
+    materials: Option<&MaterialSamples>,
     output: &mut SurfaceNetsBuffer,
 ) where
-    T: SignedDistance,
+    D: Samples + ?Sized,
     S: Shape<3, Coord = u32>,
 {
     let xyz_strides = [
@@ -346,52 +4952,123 @@ fn make_all_quads<T, S>(
         shape.linearize([0, 0, 1]) as usize,
     ];
 
-    for (&[x, y, z], &p_stride) in output
+    let cube_indexer = CubeIndexer::new(min, max);
+    let closed_max_planes = [1, 3, 5].map(|plane| closed_planes[plane]);
+    let surface_cubes = output
         .surface_points
         .iter()
         .zip(output.surface_strides.iter())
-    {
-        let p_stride = p_stride as usize;
-        let eval_max_plane = cfg!(feature = "eval-max-plane");
+        .map(|(&p, &p_stride)| (p, p_stride));
+    for (p, p_stride) in surface_cubes.chain(merged_cubes.iter().copied()) {
+        make_cube_quads(
+            sdf,
+            xyz_strides,
+            cube_indexer,
+            min,
+            max,
+            closed_max_planes,
+            config,
+            p,
+            p_stride as usize,
+            &output.cube_to_index,
+            &output.positions,
+            if config.quads {
+                &mut output.quad_indices
+            } else {
+                &mut output.indices
+            },
+            materials.map(|materials| (materials, &mut output.triangle_materials)),
+        );
+    }
+}
 
-        // Do edges parallel with the X axis
-        if y != miny && z != minz && (eval_max_plane || x != maxx - 1) {
-            maybe_make_quad(
-                sdf,
-                &output.stride_to_index,
-                &output.positions,
-                p_stride,
-                p_stride + xyz_strides[0],
-                xyz_strides[1],
-                xyz_strides[2],
-                &mut output.indices,
-            );
+// Make the quads for the edges at the minimal corner of the cube `p`, which has a surface point.
+//
+// On wrapped axes, the edges on the min plane are made too, with the cubes on the max plane as their neighbors. With the
+// `eval-max-plane` feature, so are the edges on the max plane, except on the `closed_max_planes` that get boundary faces
+// instead, which would overlap them.
+//
+// If there are `face_materials`, every face that's made gets the material of the negative sample of its edge.
+#[allow(clippy::too_many_arguments)]
+fn make_cube_quads<D>(
+    sdf: &D,
+    xyz_strides: [usize; 3],
+    cube_indexer: CubeIndexer,
+    min: [u32; 3],
+    max: [u32; 3],
+    closed_max_planes: [bool; 3],
+    config: &SurfaceNetsConfig,
+    p: [u32; 3],
+    p_stride: usize,
+    cube_to_index: &[u32],
+    positions: &[[f32; 3]],
+    indices: &mut Vec<u32>,
+    mut face_materials: Option<(&MaterialSamples, &mut Vec<u32>)>,
+) where
+    D: Samples + ?Sized,
+{
+    let eval_max_plane = cfg!(feature = "eval-max-plane");
+    let wrap = config.wrap;
+
+    // The stride (with the given axis strides) one step forward or backward from `p` along `axis`, wrapping around the extent
+    // on wrapped axes.
+    let span = |strides: [usize; 3], axis: usize| (max[axis] - min[axis]) as usize * strides[axis];
+    let forward = |stride: usize, strides: [usize; 3], axis: usize| {
+        if wrap[axis] && p[axis] + 1 == max[axis] {
+            stride + strides[axis] - span(strides, axis)
+        } else {
+            stride + strides[axis]
         }
-        // Do edges parallel with the Y axis
-        if x != minx && z != minz && (eval_max_plane || y != maxy - 1) {
-            maybe_make_quad(
-                sdf,
-                &output.stride_to_index,
-                &output.positions,
-                p_stride,
-                p_stride + xyz_strides[1],
-                xyz_strides[2],
-                xyz_strides[0],
-                &mut output.indices,
-            );
+    };
+    let backward = |stride: usize, strides: [usize; 3], axis: usize| {
+        if wrap[axis] && p[axis] == min[axis] {
+            stride + span(strides, axis) - strides[axis]
+        } else {
+            stride - strides[axis]
         }
-        // Do edges parallel with the Z axis
-        if x != minx && y != miny && (eval_max_plane || z != maxz - 1) {
+    };
+    let has_edge = |axis: usize| {
+        let [b, c] = [(axis + 1) % 3, (axis + 2) % 3];
+        (wrap[b] || p[b] != min[b])
+            && (wrap[c] || p[c] != min[c])
+            && ((eval_max_plane && !closed_max_planes[axis])
+                || wrap[axis]
+                || p[axis] != max[axis] - 1)
+    };
+
+    // Do edges parallel with the X, Y and Z axes, in that order.
+    let (p_index, index_strides) = (cube_indexer.index(p), cube_indexer.strides);
+    for axis in 0..3 {
+        if has_edge(axis) {
+            let [b, c] = [(axis + 1) % 3, (axis + 2) % 3];
+            let b_index = backward(p_index, index_strides, b);
+            let p2_stride = forward(p_stride, xyz_strides, axis);
+            let num_indices = indices.len();
             maybe_make_quad(
                 sdf,
-                &output.stride_to_index,
-                &output.positions,
+                cube_to_index,
+                positions,
+                config,
                 p_stride,
-                p_stride + xyz_strides[2],
-                xyz_strides[0],
-                xyz_strides[1],
-                &mut output.indices,
+                p2_stride,
+                [
+                    p_index,
+                    b_index,
+                    backward(p_index, index_strides, c),
+                    backward(b_index, index_strides, c),
+                ],
+                indices,
             );
+            if let Some((materials, face_materials)) = &mut face_materials {
+                let sides = if config.quads { 4 } else { 3 };
+                let inside = if sdf.is_negative(p_stride) {
+                    p_stride
+                } else {
+                    p2_stride
+                };
+                let num_faces = (indices.len() - num_indices) / sides;
+                face_materials.extend(std::iter::repeat_n((materials.material)(inside), num_faces));
+            }
         }
     }
 }
@@ -423,23 +5100,26 @@ fn make_all_quads<T, S>(
 //   p1 ---> p2
 //
 // then we must find the other 3 quad corners by moving along the other two axes (those orthogonal to A) in the negative
-// directions; these are axis B and axis C.
+// directions; these are axis B and axis C. `cubes` are the indices (in `cube_to_index`) of the cubes at p1, p1 - B, p1 - C and
+// p1 - B - C.
+//
+// If `config.safe_quads` is set, the quad is skipped unless all four cubes have a vertex (so `NULL_VERTEX` is never indexed). If
+// vertices may have been merged, the triangles that repeat a vertex are skipped, and so are degenerate triangles if
+// `config.skip_degenerate_triangles` is set.
 #[allow(clippy::too_many_arguments)]
-fn maybe_make_quad<T>(
-    sdf: &[T],
-    stride_to_index: &[u32],
+fn maybe_make_quad<D>(
+    sdf: &D,
+    cube_to_index: &[u32],
     positions: &[[f32; 3]],
+    config: &SurfaceNetsConfig,
     p1: usize,
     p2: usize,
-    axis_b_stride: usize,
-    axis_c_stride: usize,
+    cubes: [usize; 4],
     indices: &mut Vec<u32>,
 ) where
-    T: SignedDistance,
+    D: Samples + ?Sized,
 {
-    let d1 = unsafe { sdf.get_unchecked(p1) };
-    let d2 = unsafe { sdf.get_unchecked(p2) };
-    let negative_face = match (d1.is_negative(), d2.is_negative()) {
+    let negative_face = match (sdf.is_negative(p1), sdf.is_negative(p2)) {
         (true, false) => false,
         (false, true) => true,
         _ => return, // No face.
@@ -448,10 +5128,22 @@ fn maybe_make_quad<T>(
     // The triangle points, viewed face-front, look like this:
     // v1 v3
     // v2 v4
-    let v1 = stride_to_index[p1];
-    let v2 = stride_to_index[p1 - axis_b_stride];
-    let v3 = stride_to_index[p1 - axis_c_stride];
-    let v4 = stride_to_index[p1 - axis_b_stride - axis_c_stride];
+    let [v1, v2, v3, v4] = cubes.map(|cube| cube_to_index[cube]);
+    if config.safe_quads
+        && [v1, v2, v3, v4]
+            .iter()
+            .any(|&v| v as usize >= positions.len())
+    {
+        return;
+    }
+    if config.quads {
+        if negative_face {
+            indices.extend_from_slice(&[v1, v3, v4, v2]);
+        } else {
+            indices.extend_from_slice(&[v1, v2, v4, v3]);
+        }
+        return;
+    }
     let (pos1, pos2, pos3, pos4) = (
         Vec3A::from(positions[v1 as usize]),
         Vec3A::from(positions[v2 as usize]),
@@ -470,125 +5162,321 @@ fn maybe_make_quad<T>(
     } else {
         [v2, v4, v3, v2, v3, v1]
     };
-    indices.extend_from_slice(&quad);
+    if config.vertex_merge_distance.is_some() || config.skip_degenerate_triangles {
+        for tri in quad.chunks_exact(3) {
+            if tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0] {
+                push_triangle(config, positions, tri, indices);
+            }
+        }
+    } else {
+        indices.extend_from_slice(&quad);
+    }
+}
+
+// Push the triangle `tri`, unless it's degenerate and `config.skip_degenerate_triangles` is set.
+fn push_triangle(
+    config: &SurfaceNetsConfig,
+    positions: &[[f32; 3]],
+    tri: &[u32],
+    indices: &mut Vec<u32>,
+) {
+    if config.skip_degenerate_triangles {
+        let [a, b, c] = [0, 1, 2].map(|i| Vec3A::from(positions[tri[i] as usize]));
+        if 0.5 * (b - a).cross(c - a).length() < DEGENERATE_TRIANGLE_AREA {
+            return;
+        }
+    }
+    indices.extend_from_slice(tri);
+}
+
+// Decide which boundary planes to close, in the order min X, max X, min Y, max Y, min Z, max Z.
+fn closed_planes(
+    min: [u32; 3],
+    max: [u32; 3],
+    config: &SurfaceNetsConfig,
+    surface_points: &[[u32; 3]],
+) -> [bool; 6] {
+    if !config.generate_boundary_faces {
+        return [false; 6];
+    }
+    let closed_planes = match config.boundary_planes {
+        BoundaryPlanes::All => [true; 6],
+        BoundaryPlanes::Clipped => {
+            let mut closed_planes = [false; 6];
+            for p in surface_points.iter() {
+                for axis in 0..3 {
+                    closed_planes[2 * axis] |= p[axis] == min[axis];
+                    closed_planes[2 * axis + 1] |= p[axis] == max[axis] - 1;
+                }
+            }
+            closed_planes
+        }
+        BoundaryPlanes::Selected(closed_planes) => closed_planes,
+    };
+    // Wrapped axes have no boundary planes.
+    std::array::from_fn(|plane| closed_planes[plane] && !config.wrap[plane / 2])
 }
 
-// Generate faces on the boundaries of the sampling volume where the SDF is negative.
+// Generate faces on the `closed_planes` of the sampling volume where the SDF is negative.
 // This creates watertight meshes by closing holes at the boundaries.
-fn make_boundary_faces<T, S>(
-    sdf: &[T],
+#[allow(clippy::too_many_arguments)]
+fn make_boundary_faces<D, S>(
+    sdf: &D,
     shape: &S,
     [minx, miny, minz]: [u32; 3],
     [maxx, maxy, maxz]: [u32; 3],
+    closed_planes: [bool; 6],
+    config: &SurfaceNetsConfig,
+    materials: Option<&MaterialSamples>,
     output: &mut SurfaceNetsBuffer,
 ) where
-    T: SignedDistance,
+    D: Samples + ?Sized,
     S: Shape<3, Coord = u32>,
 {
+    let (min, max) = ([minx, miny, minz], [maxx, maxy, maxz]);
+
     // First, generate boundary vertices where needed
-    generate_boundary_vertices(sdf, shape, [minx, miny, minz], [maxx, maxy, maxz], output);
-    
-    // Then generate boundary faces
-    make_boundary_faces_x(sdf, shape, [minx, miny, minz], [maxx, maxy, maxz], minx, output);
-    make_boundary_faces_x(sdf, shape, [minx, miny, minz], [maxx, maxy, maxz], maxx - 1, output);
-    make_boundary_faces_y(sdf, shape, [minx, miny, minz], [maxx, maxy, maxz], miny, output);
-    make_boundary_faces_y(sdf, shape, [minx, miny, minz], [maxx, maxy, maxz], maxy - 1, output);
-    make_boundary_faces_z(sdf, shape, [minx, miny, minz], [maxx, maxy, maxz], minz, output);
-    make_boundary_faces_z(sdf, shape, [minx, miny, minz], [maxx, maxy, maxz], maxz - 1, output);
+    generate_boundary_vertices(
+        sdf,
+        shape,
+        min,
+        max,
+        closed_planes,
+        config,
+        materials,
+        output,
+    );
+
+    // Then generate boundary faces. The six planes are independent, but their faces are always emitted in the same order.
+    // Each cap quad surrounds the minimal corner of one of its cubes, so find out which of those are inside of the volume.
+    let cube_indexer = CubeIndexer::new(min, max);
+    let plane_masks: [PlaneMask; 6] = std::array::from_fn(|plane| {
+        if closed_planes[plane] {
+            PlaneMask::new(sdf, shape, min, max, plane)
+        } else {
+            PlaneMask::default()
+        }
+    });
+    let plane_masks = &plane_masks;
+    let cube_to_index = &output.cube_to_index;
+    let positions = &output.positions;
+    let quads = config.quads;
+    let make_plane_faces = |face: usize, indices: &mut Vec<u32>| match face {
+        0 => make_boundary_faces_x(
+            cube_indexer,
+            min,
+            max,
+            minx,
+            config,
+            &plane_masks[face],
+            cube_to_index,
+            positions,
+            indices,
+        ),
+        1 => make_boundary_faces_x(
+            cube_indexer,
+            min,
+            max,
+            maxx - 1,
+            config,
+            &plane_masks[face],
+            cube_to_index,
+            positions,
+            indices,
+        ),
+        2 => make_boundary_faces_y(
+            cube_indexer,
+            min,
+            max,
+            miny,
+            config,
+            &plane_masks[face],
+            cube_to_index,
+            positions,
+            indices,
+        ),
+        3 => make_boundary_faces_y(
+            cube_indexer,
+            min,
+            max,
+            maxy - 1,
+            config,
+            &plane_masks[face],
+            cube_to_index,
+            positions,
+            indices,
+        ),
+        4 => make_boundary_faces_z(
+            cube_indexer,
+            min,
+            max,
+            minz,
+            config,
+            &plane_masks[face],
+            cube_to_index,
+            positions,
+            indices,
+        ),
+        _ => make_boundary_faces_z(
+            cube_indexer,
+            min,
+            max,
+            maxz - 1,
+            config,
+            &plane_masks[face],
+            cube_to_index,
+            positions,
+            indices,
+        ),
+    };
+
+    #[cfg(feature = "rayon")]
+    if config.parallel_boundary_faces {
+        use rayon::prelude::*;
+
+        let plane_indices: Vec<(usize, Vec<u32>)> = (0..6)
+            .into_par_iter()
+            .filter(|&face| closed_planes[face])
+            .map(|face| {
+                let mut indices = Vec::new();
+                make_plane_faces(face, &mut indices);
+                (face, indices)
+            })
+            .collect();
+        for (face, indices) in plane_indices {
+            if quads {
+                output.quad_indices.extend_from_slice(&indices);
+            } else if config.separate_boundary_indices {
+                output.boundary_indices[face].extend_from_slice(&indices);
+            } else {
+                output.indices.extend_from_slice(&indices);
+            }
+        }
+        return;
+    }
+
+    for face in (0..6).filter(|&face| closed_planes[face]) {
+        if quads {
+            make_plane_faces(face, &mut output.quad_indices);
+        } else if config.separate_boundary_indices {
+            make_plane_faces(face, &mut output.boundary_indices[face]);
+        } else {
+            make_plane_faces(face, &mut output.indices);
+        }
+    }
 }
 
 // Generate boundary vertices for negative SDF values at the boundaries
-fn generate_boundary_vertices<T, S>(
-    sdf: &[T],
+#[allow(clippy::too_many_arguments)]
+fn generate_boundary_vertices<D, S>(
+    sdf: &D,
     shape: &S,
     [minx, miny, minz]: [u32; 3],
     [maxx, maxy, maxz]: [u32; 3],
+    closed_planes: [bool; 6],
+    config: &SurfaceNetsConfig,
+    materials: Option<&MaterialSamples>,
     output: &mut SurfaceNetsBuffer,
 ) where
-    T: SignedDistance,
+    D: Samples + ?Sized,
     S: Shape<3, Coord = u32>,
 {
-    // Use a map to track vertex positions and avoid duplicates
-    use std::collections::HashMap;
-    let mut position_to_index: HashMap<[u32; 3], u32> = HashMap::new();
-    
-    // First, map existing vertices to their positions
-    for (stride_idx, &vertex_idx) in output.stride_to_index.iter().enumerate() {
-        if vertex_idx != NULL_VERTEX {
-            // Find the 3D position from the stride
-            let coords = shape.delinearize(stride_idx as u32);
-            position_to_index.insert(coords, vertex_idx);
-        }
-    }
-    
+    // Track the existing vertex positions, quantized to a 0.001 grid, so caps that land on an existing vertex reuse it.
+    let quantize = |[x, y, z]: [f32; 3]| [x, y, z].map(|c| (c * 1000.0).round() as i32);
+    let mut position_to_index: std::collections::HashMap<[i32; 3], u32> = output
+        .positions
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (quantize(p), i as u32))
+        .collect();
+
+    let (min, max) = ([minx, miny, minz], [maxx, maxy, maxz]);
+    let cube_indexer = CubeIndexer::new(min, max);
+
+    // Vertices created below are caps, not surface points.
+    let num_surface_vertices = output.positions.len() as u32;
+
     // Check boundary voxels and create vertices for negative SDF values
     for z in minz..maxz {
         for y in miny..maxy {
             for x in minx..maxx {
-                let is_boundary = x == minx || x == maxx - 1 || y == miny || y == maxy - 1 || z == minz || z == maxz - 1;
-                
-                if is_boundary {
+                // A cube on an edge or corner of the extent is on several boundary faces. Its cap vertex is placed on all of
+                // the closed ones, with the normalized sum of their normals.
+                let p = [x, y, z];
+                let mut normal = Vec3A::ZERO;
+                let mut first_face = None;
+                for axis in 0..3 {
+                    let is_min = p[axis] == min[axis] && closed_planes[2 * axis];
+                    if is_min || p[axis] == max[axis] - 1 && closed_planes[2 * axis + 1] {
+                        normal[axis] = if is_min { -1.0 } else { 1.0 };
+                        first_face.get_or_insert((axis, is_min));
+                    }
+                }
+
+                if let Some((axis, is_min)) = first_face {
                     let stride = shape.linearize([x, y, z]);
-                    
-                    // Only create boundary vertex if not already created
-                    if output.stride_to_index[stride as usize] == NULL_VERTEX {
-                        let sdf_value = unsafe { sdf.get_unchecked(stride as usize) };
-                        
-                        if sdf_value.is_negative() {
-                            // Calculate the target boundary position
-                            let boundary_pos = if x == minx {
-                                [minx as f32, y as f32 + 0.5, z as f32 + 0.5]
-                            } else if x == maxx - 1 {
-                                [(maxx - 1) as f32 + 1.0, y as f32 + 0.5, z as f32 + 0.5]
-                            } else if y == miny {
-                                [x as f32 + 0.5, miny as f32, z as f32 + 0.5]
-                            } else if y == maxy - 1 {
-                                [x as f32 + 0.5, (maxy - 1) as f32 + 1.0, z as f32 + 0.5]
-                            } else if z == minz {
-                                [x as f32 + 0.5, y as f32 + 0.5, minz as f32]
-                            } else { // z == maxz - 1
-                                [x as f32 + 0.5, y as f32 + 0.5, (maxz - 1) as f32 + 1.0]
-                            };
-                            
-                            // Check if we already have a vertex at this exact position
-                            let mut existing_vertex_idx = None;
-                            for (i, &pos) in output.positions.iter().enumerate() {
-                                if (pos[0] - boundary_pos[0]).abs() < 0.001 
-                                    && (pos[1] - boundary_pos[1]).abs() < 0.001 
-                                    && (pos[2] - boundary_pos[2]).abs() < 0.001 {
-                                    existing_vertex_idx = Some(i as u32);
-                                    break;
-                                }
-                            }
-                            
-                            let vertex_idx = if let Some(idx) = existing_vertex_idx {
-                                // Reuse existing vertex
-                                idx
-                            } else {
-                                // Create new vertex
-                                let normal = if x == minx {
-                                    [-1.0, 0.0, 0.0]
-                                } else if x == maxx - 1 {
-                                    [1.0, 0.0, 0.0]
-                                } else if y == miny {
-                                    [0.0, -1.0, 0.0]
-                                } else if y == maxy - 1 {
-                                    [0.0, 1.0, 0.0]
-                                } else if z == minz {
-                                    [0.0, 0.0, -1.0]
+                    let cube = cube_indexer.index(p);
+
+                    // Only create boundary vertex if not already created, and only inside of the volume
+                    if output.cube_to_index[cube] == NULL_VERTEX && sdf.is_negative(stride as usize)
+                    {
+                        // Calculate the target boundary position
+                        let mut boundary_pos = boundary_cap_position(
+                            sdf,
+                            shape,
+                            p,
+                            axis,
+                            is_min,
+                            min,
+                            max,
+                            config.wrap,
+                            num_surface_vertices,
+                            config.boundary_cap_mode,
+                            &output.cube_to_index,
+                            &output.positions,
+                        );
+                        for other_axis in 0..3 {
+                            if other_axis != axis && normal[other_axis] != 0.0 {
+                                boundary_pos[other_axis] = if normal[other_axis] < 0.0 {
+                                    p[other_axis] as f32
                                 } else {
-                                    [0.0, 0.0, 1.0]
+                                    p[other_axis] as f32 + 1.0
                                 };
-                                
-                                output.positions.push(boundary_pos);
-                                output.normals.push(normal);
-                                output.surface_points.push([x, y, z]);
-                                output.surface_strides.push(stride);
-                                (output.positions.len() - 1) as u32
-                            };
-                            
-                            output.stride_to_index[stride as usize] = vertex_idx;
+                            }
                         }
+
+                        // Check if we already have a vertex at this exact position
+                        let existing_vertex_idx =
+                            position_to_index.get(&quantize(boundary_pos)).copied();
+
+                        let vertex_idx = if let Some(idx) = existing_vertex_idx {
+                            // Reuse existing vertex
+                            idx
+                        } else {
+                            // Create new vertex
+                            let normal: [f32; 3] = normal.normalize().into();
+
+                            output.positions.push(boundary_pos);
+                            if config.normal_mode != NormalMode::None {
+                                output.normals.push(match config.normal_direction {
+                                    NormalDirection::TowardExterior => normal,
+                                    NormalDirection::TowardInterior => normal.map(|c| -c),
+                                });
+                            }
+                            if let Some(materials) = materials {
+                                output
+                                    .vertex_materials
+                                    .push((materials.material)(stride as usize));
+                            }
+                            output.surface_points.push([x, y, z]);
+                            output.surface_strides.push(stride);
+                            output.num_boundary_vertices += 1;
+                            let new_idx = (output.positions.len() - 1) as u32;
+                            position_to_index.insert(quantize(boundary_pos), new_idx);
+                            new_idx
+                        };
+
+                        output.cube_to_index[cube] = vertex_idx;
                     }
                 }
             }
@@ -596,128 +5484,470 @@ fn generate_boundary_vertices<T, S>(
     }
 }
 
-// Generate boundary faces for X planes
-fn make_boundary_faces_x<T, S>(
-    sdf: &[T],
+// Place the cap vertex for the boundary cube at `p`, whose face on the `axis` plane (the min or max plane, according to
+// `is_min`) lies on the boundary of the sampling volume.
+#[allow(clippy::too_many_arguments)]
+fn boundary_cap_position<D, S>(
+    sdf: &D,
     shape: &S,
-    [minx, miny, minz]: [u32; 3],
-    [maxx, maxy, maxz]: [u32; 3],
-    x_plane: u32,
+    p: [u32; 3],
+    axis: usize,
+    is_min: bool,
+    min: [u32; 3],
+    max: [u32; 3],
+    wrap: [bool; 3],
+    num_surface_vertices: u32,
+    mode: BoundaryCapMode,
+    cube_to_index: &[u32],
+    positions: &[[f32; 3]],
+) -> [f32; 3]
+where
+    D: Samples + ?Sized,
+    S: Shape<3, Coord = u32>,
+{
+    // The in-plane axes of the face.
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let plane = if is_min { p[axis] } else { p[axis] + 1 };
+
+    let mut center = [p[0] as f32 + 0.5, p[1] as f32 + 0.5, p[2] as f32 + 0.5];
+    center[axis] = plane as f32;
+
+    match mode {
+        BoundaryCapMode::FaceCenter => center,
+        BoundaryCapMode::FaceCrossing => {
+            // Bilinearly interpolate the face corners, and take a single Newton step from the face center toward the zero
+            // crossing, staying inside of the face.
+            let face_dist = |du: u32, dv: u32| -> f32 {
+                let mut corner = [0; 3];
+                corner[axis] = plane - p[axis];
+                corner[u] = du;
+                corner[v] = dv;
+                sdf.distance(wrapped_corner_stride(shape, p, corner, min, max, wrap) as usize)
+            };
+            let [f00, f10, f01, f11] = [
+                face_dist(0, 0),
+                face_dist(1, 0),
+                face_dist(0, 1),
+                face_dist(1, 1),
+            ];
+            let value = 0.25 * (f00 + f10 + f01 + f11);
+            let gradient =
+                glam::Vec2::new(0.5 * (f10 - f00 + f11 - f01), 0.5 * (f01 - f00 + f11 - f10));
+            let step = (-value * gradient / gradient.length_squared().max(f32::EPSILON))
+                .clamp_length_max(0.5);
+            let mut position = center;
+            position[u] += step.x;
+            position[v] += step.y;
+            position
+        }
+        BoundaryCapMode::SnapToInterior => {
+            // Find the nearest surface vertex among the in-plane neighbors of this cube, and project it onto this cube's face.
+            let cube_indexer = CubeIndexer::new(min, max);
+            let mut nearest = None;
+            let mut nearest_distance = f32::INFINITY;
+            for dv in -1i32..=1 {
+                for du in -1i32..=1 {
+                    let mut neighbor = p;
+                    let nu = p[u] as i32 + du;
+                    let nv = p[v] as i32 + dv;
+                    if nu < min[u] as i32
+                        || nu >= max[u] as i32
+                        || nv < min[v] as i32
+                        || nv >= max[v] as i32
+                    {
+                        continue;
+                    }
+                    neighbor[u] = nu as u32;
+                    neighbor[v] = nv as u32;
+                    let index = cube_to_index[cube_indexer.index(neighbor)];
+                    if index == NULL_VERTEX || index >= num_surface_vertices {
+                        continue;
+                    }
+                    let position = positions[index as usize];
+                    let distance = Vec3A::from(position).distance_squared(Vec3A::from(center));
+                    if distance < nearest_distance {
+                        nearest_distance = distance;
+                        nearest = Some(position);
+                    }
+                }
+            }
+
+            match nearest {
+                Some(nearest) => {
+                    let mut position = center;
+                    position[u] = nearest[u].clamp(p[u] as f32, p[u] as f32 + 1.0);
+                    position[v] = nearest[v].clamp(p[v] as f32, p[v] as f32 + 1.0);
+                    position
+                }
+                None => center,
+            }
+        }
+    }
+}
+
+// Hang a skirt from every open edge of the surface whose vertices are both in the same boundary plane of the sampling volume.
+// Skirt vertices are shared between the skirt quads of the same plane, but not across planes, since they move in different
+// directions.
+fn make_skirts<D, S>(
+    sdf: &D,
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    wrap: [bool; 3],
+    depth: f32,
     output: &mut SurfaceNetsBuffer,
 ) where
-    T: SignedDistance,
+    D: Samples + ?Sized,
     S: Shape<3, Coord = u32>,
 {
-    let is_min_face = x_plane == minx;
-    
-    for z in minz..(maxz - 1) {
-        for y in miny..(maxy - 1) {
-            // Get the four corners of the quad
-            let stride_00 = shape.linearize([x_plane, y, z]);
-            let stride_01 = shape.linearize([x_plane, y, z + 1]);
-            let stride_10 = shape.linearize([x_plane, y + 1, z]);
-            let stride_11 = shape.linearize([x_plane, y + 1, z + 1]);
-            
-            let v00 = output.stride_to_index[stride_00 as usize];
-            let v01 = output.stride_to_index[stride_01 as usize];
-            let v10 = output.stride_to_index[stride_10 as usize];
-            let v11 = output.stride_to_index[stride_11 as usize];
-            
-            // Only create faces if all vertices exist
-            if v00 != NULL_VERTEX && v01 != NULL_VERTEX && v10 != NULL_VERTEX && v11 != NULL_VERTEX {
-                if is_min_face {
-                    // Winding for min face (facing outward)
-                    output.indices.extend_from_slice(&[v00, v01, v10]);
-                    output.indices.extend_from_slice(&[v01, v11, v10]);
+    let neighbors = topology::triangle_neighbors(&output.indices);
+    let mut skirt_vertices = std::collections::HashMap::<(u32, usize), u32>::new();
+    for (t, tri_neighbors) in neighbors.iter().enumerate() {
+        for (e, &neighbor) in tri_neighbors.iter().enumerate() {
+            if neighbor != topology::NO_NEIGHBOR {
+                continue;
+            }
+            let a = output.indices[3 * t + e];
+            let b = output.indices[3 * t + (e + 1) % 3];
+            let [pa, pb] = [a, b].map(|v| output.surface_points[v as usize]);
+            let Some(plane) = (0..6).filter(|&plane| !wrap[plane / 2]).find(|&plane| {
+                let axis = plane / 2;
+                let bound = if plane % 2 == 0 {
+                    min[axis]
                 } else {
-                    // Winding for max face (facing outward)
-                    output.indices.extend_from_slice(&[v00, v10, v01]);
-                    output.indices.extend_from_slice(&[v01, v10, v11]);
+                    max[axis] - 1
+                };
+                pa[axis] == bound && pb[axis] == bound
+            }) else {
+                continue;
+            };
+
+            let [sa, sb] = [a, b].map(|v| match skirt_vertices.get(&(v, plane)) {
+                Some(&skirt) => skirt,
+                None => {
+                    let skirt =
+                        make_skirt_vertex(sdf, shape, min, max, wrap, v, plane / 2, depth, output);
+                    skirt_vertices.insert((v, plane), skirt);
+                    skirt
                 }
-            }
+            });
+            // Wind the skirt quad to match the triangle, which has the edge (a, b).
+            output.indices.extend_from_slice(&[b, a, sa, b, sa, sb]);
         }
     }
 }
 
-// Generate boundary faces for Y planes
-fn make_boundary_faces_y<T, S>(
-    sdf: &[T],
+// Copy surface vertex `v`, moving it by `depth` toward the interior of the surface, within the boundary plane perpendicular to
+// `axis`. If the surface is parallel to the plane, the copy isn't moved at all.
+#[allow(clippy::too_many_arguments)]
+fn make_skirt_vertex<D, S>(
+    sdf: &D,
     shape: &S,
-    [minx, miny, minz]: [u32; 3],
-    [maxx, maxy, maxz]: [u32; 3],
-    y_plane: u32,
+    min: [u32; 3],
+    max: [u32; 3],
+    wrap: [bool; 3],
+    v: u32,
+    axis: usize,
+    depth: f32,
     output: &mut SurfaceNetsBuffer,
-) where
-    T: SignedDistance,
+) -> u32
+where
+    D: Samples + ?Sized,
     S: Shape<3, Coord = u32>,
 {
+    let cube = output.surface_points[v as usize];
+    let mut corner_dists = [0f32; 8];
+    for (dist, corner) in corner_dists.iter_mut().zip(CUBE_CORNERS) {
+        *dist = sdf.distance(wrapped_corner_stride(shape, cube, corner, min, max, wrap) as usize);
+    }
+    let p = Vec3A::from(output.positions[v as usize]);
+    let min_corner = Vec3A::from(cube.map(|c| c as f32));
+    let mut direction = -sdf_gradient(&corner_dists, p - min_corner);
+    direction[axis] = 0.0;
+
+    let skirt = output.duplicate_vertex(v);
+    output.positions[skirt as usize] = (p + depth * direction.normalize_or_zero()).into();
+    skirt
+}
+
+// Generate boundary faces for X planes
+#[allow(clippy::too_many_arguments)]
+fn make_boundary_faces_x(
+    cube_indexer: CubeIndexer,
+    [minx, miny, minz]: [u32; 3],
+    [_, maxy, maxz]: [u32; 3],
+    x_plane: u32,
+    config: &SurfaceNetsConfig,
+    plane_mask: &PlaneMask,
+    cube_to_index: &[u32],
+    positions: &[[f32; 3]],
+    indices: &mut Vec<u32>,
+) {
+    let is_min_face = x_plane == minx;
+
+    for z in minz..(maxz - 1) {
+        for y in miny..(maxy - 1) {
+            let corners = [
+                [x_plane, y, z],
+                [x_plane, y, z + 1],
+                [x_plane, y + 1, z],
+                [x_plane, y + 1, z + 1],
+            ];
+            push_boundary_quad(
+                cube_indexer,
+                corners,
+                config,
+                plane_mask,
+                cube_to_index,
+                positions,
+                !is_min_face,
+                indices,
+            );
+        }
+    }
+}
+
+// Generate boundary faces for Y planes
+#[allow(clippy::too_many_arguments)]
+fn make_boundary_faces_y(
+    cube_indexer: CubeIndexer,
+    [minx, miny, minz]: [u32; 3],
+    [maxx, _, maxz]: [u32; 3],
+    y_plane: u32,
+    config: &SurfaceNetsConfig,
+    plane_mask: &PlaneMask,
+    cube_to_index: &[u32],
+    positions: &[[f32; 3]],
+    indices: &mut Vec<u32>,
+) {
     let is_min_face = y_plane == miny;
-    
+
     for z in minz..(maxz - 1) {
         for x in minx..(maxx - 1) {
-            let stride_00 = shape.linearize([x, y_plane, z]);
-            let stride_01 = shape.linearize([x, y_plane, z + 1]);
-            let stride_10 = shape.linearize([x + 1, y_plane, z]);
-            let stride_11 = shape.linearize([x + 1, y_plane, z + 1]);
-            
-            let v00 = output.stride_to_index[stride_00 as usize];
-            let v01 = output.stride_to_index[stride_01 as usize];
-            let v10 = output.stride_to_index[stride_10 as usize];
-            let v11 = output.stride_to_index[stride_11 as usize];
-            
-            if v00 != NULL_VERTEX && v01 != NULL_VERTEX && v10 != NULL_VERTEX && v11 != NULL_VERTEX {
-                if is_min_face {
-                    output.indices.extend_from_slice(&[v00, v10, v01]);
-                    output.indices.extend_from_slice(&[v01, v10, v11]);
-                } else {
-                    output.indices.extend_from_slice(&[v00, v01, v10]);
-                    output.indices.extend_from_slice(&[v01, v11, v10]);
-                }
-            }
+            let corners = [
+                [x, y_plane, z],
+                [x, y_plane, z + 1],
+                [x + 1, y_plane, z],
+                [x + 1, y_plane, z + 1],
+            ];
+            push_boundary_quad(
+                cube_indexer,
+                corners,
+                config,
+                plane_mask,
+                cube_to_index,
+                positions,
+                is_min_face,
+                indices,
+            );
         }
     }
 }
 
 // Generate boundary faces for Z planes
-fn make_boundary_faces_z<T, S>(
-    sdf: &[T],
-    shape: &S,
+#[allow(clippy::too_many_arguments)]
+fn make_boundary_faces_z(
+    cube_indexer: CubeIndexer,
     [minx, miny, minz]: [u32; 3],
-    [maxx, maxy, maxz]: [u32; 3],
+    [maxx, maxy, _]: [u32; 3],
     z_plane: u32,
-    output: &mut SurfaceNetsBuffer,
-) where
-    T: SignedDistance,
-    S: Shape<3, Coord = u32>,
-{
+    config: &SurfaceNetsConfig,
+    plane_mask: &PlaneMask,
+    cube_to_index: &[u32],
+    positions: &[[f32; 3]],
+    indices: &mut Vec<u32>,
+) {
     let is_min_face = z_plane == minz;
-    
+
     for y in miny..(maxy - 1) {
         for x in minx..(maxx - 1) {
-            let stride_00 = shape.linearize([x, y, z_plane]);
-            let stride_01 = shape.linearize([x, y + 1, z_plane]);
-            let stride_10 = shape.linearize([x + 1, y, z_plane]);
-            let stride_11 = shape.linearize([x + 1, y + 1, z_plane]);
-            
-            let v00 = output.stride_to_index[stride_00 as usize];
-            let v01 = output.stride_to_index[stride_01 as usize];
-            let v10 = output.stride_to_index[stride_10 as usize];
-            let v11 = output.stride_to_index[stride_11 as usize];
-            
-            if v00 != NULL_VERTEX && v01 != NULL_VERTEX && v10 != NULL_VERTEX && v11 != NULL_VERTEX {
-                if is_min_face {
-                    output.indices.extend_from_slice(&[v00, v01, v10]);
-                    output.indices.extend_from_slice(&[v01, v11, v10]);
-                } else {
-                    output.indices.extend_from_slice(&[v00, v10, v01]);
-                    output.indices.extend_from_slice(&[v01, v10, v11]);
+            let corners = [
+                [x, y, z_plane],
+                [x, y + 1, z_plane],
+                [x + 1, y, z_plane],
+                [x + 1, y + 1, z_plane],
+            ];
+            push_boundary_quad(
+                cube_indexer,
+                corners,
+                config,
+                plane_mask,
+                cube_to_index,
+                positions,
+                !is_min_face,
+                indices,
+            );
+        }
+    }
+}
+
+// Close the boundary quad between the vertices of the cubes `[c00, c01, c10, c11]`, split along the `c01`-`c10` diagonal
+// (unless it's emitted as one of `quads`), and wound around `c00, c01, c11, c10`, or the other way around if `reverse`. The
+// quad surrounds the sample that the four cubes share on the plane, the minimal corner of `c11`, and is only part of the cap
+// if that sample is inside of the volume, like the dual faces of 2D surface nets. Then each of the cubes has a vertex: either
+// its surface vertex, or a cap vertex since all of its corners are inside. Four cubes around a sample outside of the volume
+// can all have surface vertices too, but their quad is already a surface quad.
+#[allow(clippy::too_many_arguments)]
+fn push_boundary_quad(
+    cube_indexer: CubeIndexer,
+    corners: [[u32; 3]; 4],
+    config: &SurfaceNetsConfig,
+    plane_mask: &PlaneMask,
+    cube_to_index: &[u32],
+    positions: &[[f32; 3]],
+    reverse: bool,
+    indices: &mut Vec<u32>,
+) {
+    if !plane_mask.is_inside(corners[3]) {
+        return;
+    }
+    let [v00, v01, v10, v11] = corners.map(|p| cube_to_index[cube_indexer.index(p)]);
+    if [v00, v01, v10, v11].contains(&NULL_VERTEX) {
+        return;
+    }
+    if config.quads {
+        if reverse {
+            indices.extend_from_slice(&[v00, v10, v11, v01]);
+        } else {
+            indices.extend_from_slice(&[v00, v01, v11, v10]);
+        }
+        return;
+    }
+    let quad = if reverse {
+        [v00, v10, v01, v01, v10, v11]
+    } else {
+        [v00, v01, v10, v01, v11, v10]
+    };
+    for tri in quad.chunks_exact(3) {
+        push_triangle(config, positions, tri, indices);
+    }
+}
+
+// Which samples on one closed boundary plane (numbered like in `make_boundary_faces`) are inside of the volume, so each cap only
+// needs a mask of its own plane rather than of the whole extent.
+#[derive(Default)]
+struct PlaneMask {
+    min: [u32; 3],
+    size: [u32; 3],
+    is_inside: Vec<bool>,
+}
+
+impl PlaneMask {
+    fn new<D, S>(sdf: &D, shape: &S, min: [u32; 3], max: [u32; 3], plane: usize) -> Self
+    where
+        D: Samples + ?Sized,
+        S: Shape<3, Coord = u32>,
+    {
+        let axis = plane / 2;
+        let mut min = min;
+        let mut size = [0, 1, 2].map(|i| max[i] - min[i]);
+        if plane % 2 == 1 {
+            min[axis] = max[axis] - 1;
+        }
+        size[axis] = 1;
+        let mut is_inside = Vec::with_capacity((size[0] * size[1] * size[2]) as usize);
+        for z in min[2]..min[2] + size[2] {
+            for y in min[1]..min[1] + size[1] {
+                for x in min[0]..min[0] + size[0] {
+                    is_inside.push(sdf.is_negative(shape.linearize([x, y, z]) as usize));
                 }
             }
         }
+        Self {
+            min,
+            size,
+            is_inside,
+        }
+    }
+
+    fn is_inside(&self, p: [u32; 3]) -> bool {
+        let [x, y, z] = [0, 1, 2].map(|i| p[i] - self.min[i]);
+        self.is_inside[((z * self.size[1] + y) * self.size[0] + x) as usize]
+    }
+}
+
+// The stride of `corner` of the cube `p`. On wrapped axes, the corners at `max` (the exclusive max of the cubes) are read from
+// `min`.
+fn wrapped_corner_stride<S>(
+    shape: &S,
+    p: [u32; 3],
+    corner: [u32; 3],
+    min: [u32; 3],
+    max: [u32; 3],
+    wrap: [bool; 3],
+) -> u32
+where
+    S: Shape<3, Coord = u32>,
+{
+    shape.linearize([0, 1, 2].map(|i| {
+        let c = p[i] + corner[i];
+        if wrap[i] && c == max[i] {
+            min[i]
+        } else {
+            c
+        }
+    }))
+}
+
+// Numbers the cubes of the extent from `min` to `max` (exclusive) like the voxels of an array of the extent's size, with X
+// varying fastest. This is the index of a cube in `SurfaceNetsBuffer::cube_to_index` and `surface_mask`.
+#[derive(Clone, Copy)]
+struct CubeIndexer {
+    min: [u32; 3],
+    strides: [usize; 3],
+    len: usize,
+}
+
+impl CubeIndexer {
+    fn new(min: [u32; 3], max: [u32; 3]) -> Self {
+        let [sx, sy, sz] = [0, 1, 2].map(|i| (max[i] - min[i]) as usize);
+        Self {
+            min,
+            strides: [1, sx, sx * sy],
+            len: sx * sy * sz,
+        }
+    }
+
+    #[inline]
+    fn index(&self, p: [u32; 3]) -> usize {
+        (p[0] - self.min[0]) as usize * self.strides[0]
+            + (p[1] - self.min[1]) as usize * self.strides[1]
+            + (p[2] - self.min[2]) as usize * self.strides[2]
     }
 }
 
-const CUBE_CORNERS: [[u32; 3]; 8] = [
+/// Returns the offset of corner `i` of a unit cube from its minimal corner.
+///
+/// Corners are numbered by the `0b_zyx` bit convention used throughout this crate: bit 0 is X, bit 1 is Y and bit 2 is Z. So
+/// corner `0b011` is `[1, 1, 0]`, and [`CUBE_CORNERS`]`[i]` is `corner_index_to_xyz(i)`. Only the lowest 3 bits of `i` are
+/// used.
+///
+/// ```
+/// use fast_surface_nets::{corner_index_to_xyz, xyz_to_corner_index, CUBE_CORNERS};
+///
+/// for i in 0..8u8 {
+///     let xyz = corner_index_to_xyz(i);
+///     assert_eq!(xyz, CUBE_CORNERS[i as usize]);
+///     assert_eq!(xyz_to_corner_index(xyz), i);
+/// }
+/// assert_eq!(corner_index_to_xyz(0b011), [1, 1, 0]);
+/// ```
+#[inline]
+pub const fn corner_index_to_xyz(i: u8) -> [u32; 3] {
+    [(i & 1) as u32, ((i >> 1) & 1) as u32, ((i >> 2) & 1) as u32]
+}
+
+/// The inverse of [`corner_index_to_xyz`]: returns the `0b_zyx` index of the unit cube corner at offset `[x, y, z]`, where
+/// each coordinate is 0 or 1.
+#[inline]
+pub const fn xyz_to_corner_index([x, y, z]: [u32; 3]) -> u8 {
+    debug_assert!(x <= 1 && y <= 1 && z <= 1);
+    (x | (y << 1) | (z << 2)) as u8
+}
+
+/// The offsets of the 8 corners of a unit cube from its minimal corner, indexed by the `0b_zyx` corner index (see
+/// [`corner_index_to_xyz`]).
+pub const CUBE_CORNERS: [[u32; 3]; 8] = [
     [0, 0, 0],
     [1, 0, 0],
     [0, 1, 0],
@@ -737,7 +5967,9 @@ const CUBE_CORNER_VECTORS: [Vec3A; 8] = [
     Vec3A::from_array([0.0, 1.0, 1.0]),
     Vec3A::from_array([1.0, 1.0, 1.0]),
 ];
-const CUBE_EDGES: [[u32; 2]; 12] = [
+/// The 12 edges of a unit cube, as pairs of `0b_zyx` corner indices (see [`corner_index_to_xyz`]). The first corner of each
+/// edge is the one closer to the cube's minimal corner.
+pub const CUBE_EDGES: [[u32; 2]; 12] = [
     [0b000, 0b001],
     [0b000, 0b010],
     [0b000, 0b100],
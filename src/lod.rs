@@ -0,0 +1,499 @@
+use crate::topology::{triangle_neighbors, NO_NEIGHBOR};
+use crate::{
+    surface_nets_with_config, AxisRemap, NormalMode, SignedDistance, SurfaceNetsBuffer,
+    SurfaceNetsConfig, NULL_VERTEX,
+};
+
+use glam::Vec3A;
+use ndshape::{RuntimeShape, Shape};
+use std::collections::{HashMap, HashSet};
+
+/// One side of the seam closed by [`stitch_lod_boundary`]: the mesh of a chunk, the extent it was meshed from, and where it is
+/// in the world.
+#[derive(Clone, Copy)]
+pub struct LodChunk<'a> {
+    /// The mesh of the chunk, meshed without boundary faces, so every vertex is the surface point of a cube.
+    pub mesh: &'a SurfaceNetsBuffer,
+    /// The minimal sample of the extent that was meshed.
+    pub min: [u32; 3],
+    /// The maximal sample of the extent that was meshed.
+    pub max: [u32; 3],
+    /// Added to every position (after scaling it to world units), moving the mesh from chunk-local coordinates into world
+    /// space.
+    pub world_offset: [f32; 3],
+}
+
+/// Closes the crack between a chunk and a neighboring chunk with `ratio` times coarser samples, e.g. at the border between two
+/// levels of detail, by appending transition triangles to `output`.
+///
+/// `face` is the face of the `fine` chunk that borders the `coarse` chunk, in the order min X, max X, min Y, max Y, min Z, max
+/// Z. The fine mesh is in units of its own samples, and the coarse mesh in units of `ratio` fine samples, so the world position
+/// of a vertex is `world_offset + position` in the fine chunk, and `world_offset + ratio * position` in the coarse chunk.
+///
+/// Like chunks of the same resolution, the two chunks overlap by one (coarse) sample, so the fine mesh ends at the surface
+/// points of its layer of cubes on `face`, and the coarse mesh at those of its layer of cubes on the opposite face. The cubes
+/// of each layer are found with `surface_points`. The open edges of each mesh along its layer are chained into polylines,
+/// and each fine polyline is zipped to the closest coarse polyline with a strip of triangles, which are wound like the meshes
+/// on either side of them.
+///
+/// The vertices of the strip are copies of the boundary vertices of both meshes, moved into world space (with their normals,
+/// if both meshes have them). So the strip fits the two meshes exactly, and welding all three together (e.g. with
+/// [`weld_chunks`](crate::weld_chunks)) gives a mesh without a crack.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{stitch_lod_boundary, surface_nets, weld_chunks, LodChunk, SurfaceNetsBuffer};
+/// use std::collections::{HashMap, HashSet};
+///
+/// type FineShape = ConstShape3u32<18, 18, 18>;
+/// type CoarseShape = ConstShape3u32<10, 10, 10>;
+///
+/// // A fine chunk, and a chunk with half the resolution at +X, both cut by a plane. They overlap by one coarse sample.
+/// let plane = |[_, _, z]: [f32; 3]| z - 8.3;
+/// let (fine_offset, coarse_offset) = ([0.0; 3], [16.0, 0.0, 0.0]);
+/// let mut fine_sdf = [1.0; FineShape::USIZE];
+/// for i in 0u32..FineShape::SIZE {
+///     fine_sdf[i as usize] = plane(FineShape::delinearize(i).map(|c| c as f32));
+/// }
+/// let mut coarse_sdf = [1.0; CoarseShape::USIZE];
+/// for i in 0u32..CoarseShape::SIZE {
+///     let p = CoarseShape::delinearize(i);
+///     coarse_sdf[i as usize] = plane([0, 1, 2].map(|a| coarse_offset[a] + 2.0 * p[a] as f32));
+/// }
+/// let (mut fine, mut coarse) = (SurfaceNetsBuffer::default(), SurfaceNetsBuffer::default());
+/// surface_nets(&fine_sdf, &FineShape {}, [0; 3], [17; 3], &mut fine);
+/// surface_nets(&coarse_sdf, &CoarseShape {}, [0; 3], [9; 3], &mut coarse);
+///
+/// let mut seam = SurfaceNetsBuffer::default();
+/// let fine_chunk = LodChunk { mesh: &fine, min: [0; 3], max: [17; 3], world_offset: fine_offset };
+/// let coarse_chunk = LodChunk { mesh: &coarse, min: [0; 3], max: [9; 3], world_offset: coarse_offset };
+/// stitch_lod_boundary(fine_chunk, coarse_chunk, 1, 2, &mut seam);
+/// assert!(!seam.indices.is_empty());
+///
+/// // Weld everything together in world space.
+/// let mut coarse_world = coarse.clone();
+/// for p in coarse_world.positions.iter_mut() {
+///     *p = p.map(|c| 2.0 * c);
+/// }
+/// let offsets = [fine_offset, coarse_offset, [0.0; 3]];
+/// let welded = weld_chunks(&[&fine, &coarse_world, &seam], &offsets, 0.001);
+///
+/// // Along the shared face, every edge is shared by two triangles with opposite windings, except at the ends of the seam.
+/// let mut directed_edges = HashMap::new();
+/// for tri in welded.indices.chunks(3) {
+///     for e in 0..3 {
+///         *directed_edges.entry([tri[e], tri[(e + 1) % 3]]).or_insert(0) += 1;
+///     }
+/// }
+/// assert!(directed_edges.values().all(|&count| count == 1));
+/// let in_seam = |v: u32| (16.0..18.0).contains(&welded.positions[v as usize][0]);
+/// let at_end = |v: u32| !(1.0..16.5).contains(&welded.positions[v as usize][1]);
+/// for &[a, b] in directed_edges.keys() {
+///     if in_seam(a) && in_seam(b) && !at_end(a) && !at_end(b) {
+///         assert!(directed_edges.contains_key(&[b, a]));
+///     }
+/// }
+/// ```
+pub fn stitch_lod_boundary(
+    fine: LodChunk,
+    coarse: LodChunk,
+    face: usize,
+    ratio: u32,
+    output: &mut SurfaceNetsBuffer,
+) {
+    let (axis, is_max) = (face / 2, face % 2 == 1);
+    let (fine_layer, coarse_layer) = if is_max {
+        (fine.max[axis] - 1, coarse.min[axis])
+    } else {
+        (fine.min[axis], coarse.max[axis] - 1)
+    };
+    let with_normals = [fine.mesh, coarse.mesh]
+        .iter()
+        .all(|mesh| mesh.normals.len() == mesh.positions.len());
+    let fine_chains = boundary_chains(fine, axis, fine_layer, 1.0, with_normals, output);
+    let coarse_chains = boundary_chains(
+        coarse,
+        axis,
+        coarse_layer,
+        ratio as f32,
+        with_normals,
+        output,
+    );
+
+    let position = |v: u32| Vec3A::from(output.positions[v as usize]);
+    let mut triangles = Vec::new();
+    zip_chain_sets(&fine_chains, &coarse_chains, &position, &mut triangles);
+    output.indices.extend_from_slice(&triangles);
+}
+
+/// Meshes the extent from `min` to `max` like [`surface_nets_with_config`], and closes the cracks towards coarser neighboring
+/// chunks with transition triangles, like the transition cells of Transvoxel.
+///
+/// `coarser_neighbors` has an entry for each face of the extent, in the order min X, max X, min Y, max Y, min Z, max Z. It is
+/// `Some(ratio)` if the chunk on that side is meshed from every `ratio`-th sample, and `None` if it has the same resolution
+/// (or there is no chunk). A coarse neighbor must overlap the layer of cubes of this chunk on that face with its own layer of
+/// cubes: on a max face, its first cube starts at `max - 1`, and on a min face, its last cube starts at `min`. On the other
+/// axes, its samples are aligned with `min`.
+///
+/// For each coarser face, the surface points of the neighbor's layer of cubes are computed from `sdf` itself, by sampling every
+/// `ratio`-th sample, so they are the same as in the neighbor's own mesh (if it was meshed with the same `config`). They are
+/// appended to `output` in the coordinates of this chunk, and a strip of triangles joins them to the open border of this
+/// chunk's mesh. Welding the result to the coarse neighbor (e.g. with [`weld_chunks`](crate::weld_chunks)) gives a mesh
+/// without a crack, and the neighbor doesn't need to know about this chunk at all.
+///
+/// Sampling the coarse layer reads `sdf` up to `ratio - 1` samples past `max`, so the array needs that much padding. The
+/// strips are added in array coordinates, after meshing, so `config` must not use `voxel_size`, `axis_remap`, `wrap`,
+/// `quads`, or `barycentric_wireframe`. The new vertices have positions, and normals if `config` generates them, but no other
+/// attributes.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{RuntimeShape, Shape};
+/// use fast_surface_nets::{
+///     surface_nets, surface_nets_with_lod_transitions, weld_chunks, SurfaceNetsBuffer, SurfaceNetsConfig,
+/// };
+/// use std::collections::HashMap;
+///
+/// // A sphere that crosses the max X face of a fine chunk, into a neighbor at +X with half the resolution. The fine array is
+/// // padded for the samples of the neighbor's first layer of cubes.
+/// let sphere = |[x, y, z]: [f32; 3]| ((x - 17.0).powi(2) + (y - 8.5).powi(2) + (z - 8.5).powi(2)).sqrt() - 6.0;
+/// let fine_shape = RuntimeShape::<u32, 3>::new([19, 19, 19]);
+/// let fine_sdf: Vec<f32> = (0..fine_shape.size())
+///     .map(|i| sphere(fine_shape.delinearize(i).map(|c| c as f32)))
+///     .collect();
+/// let mut fine = SurfaceNetsBuffer::default();
+/// let neighbors = [None, Some(2), None, None, None, None];
+/// let config = SurfaceNetsConfig::default();
+/// surface_nets_with_lod_transitions(&fine_sdf, &fine_shape, [0; 3], [17; 3], config, neighbors, &mut fine);
+///
+/// // The coarse neighbor, meshed on its own. It starts at the last layer of cubes of the fine chunk.
+/// let coarse_offset = [16.0, 0.0, 0.0];
+/// let coarse_shape = RuntimeShape::<u32, 3>::new([10, 10, 10]);
+/// let coarse_sdf: Vec<f32> = (0..coarse_shape.size())
+///     .map(|i| {
+///         let p = coarse_shape.delinearize(i);
+///         sphere([0, 1, 2].map(|a| coarse_offset[a] + 2.0 * p[a] as f32))
+///     })
+///     .collect();
+/// let mut coarse = SurfaceNetsBuffer::default();
+/// surface_nets(&coarse_sdf, &coarse_shape, [0; 3], [9; 3], &mut coarse);
+/// for p in coarse.positions.iter_mut() {
+///     *p = p.map(|c| 2.0 * c);
+/// }
+///
+/// // Welded together, the sphere is closed: every edge is shared by two triangles with opposite windings.
+/// let welded = weld_chunks(&[&fine, &coarse], &[[0.0; 3], coarse_offset], 0.001);
+/// let mut directed_edges = HashMap::new();
+/// for tri in welded.indices.chunks(3) {
+///     for e in 0..3 {
+///         *directed_edges.entry([tri[e], tri[(e + 1) % 3]]).or_insert(0) += 1;
+///     }
+/// }
+/// assert!(directed_edges.values().all(|&count| count == 1));
+/// assert!(directed_edges.keys().all(|&[a, b]| directed_edges.contains_key(&[b, a])));
+/// ```
+pub fn surface_nets_with_lod_transitions<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    coarser_neighbors: [Option<u32>; 6],
+    output: &mut SurfaceNetsBuffer,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    assert!(
+        config.voxel_size.is_none()
+            && config.axis_remap == AxisRemap::IDENTITY
+            && config.wrap == [false; 3]
+            && !config.quads
+            && !config.barycentric_wireframe,
+        "LOD transitions are added in array coordinates, to a welded triangle mesh"
+    );
+    surface_nets_with_config(sdf, shape, min, max, config, output);
+
+    // The surface points of the coarse layers are placed like the ones of this chunk, but there is nothing else to generate.
+    let coarse_config = SurfaceNetsConfig {
+        vertex_placement: config.vertex_placement,
+        normal_direction: config.normal_direction,
+        max_vertex_offset: config.max_vertex_offset,
+        relaxation: config.relaxation,
+        clamp_vertices_to_cube: config.clamp_vertices_to_cube,
+        iso_value: config.iso_value,
+        ..Default::default()
+    };
+    let with_normals = output.normals.len() == output.positions.len();
+    let size = [0, 1, 2].map(|i| max[i] - min[i]);
+    let mut triangles = Vec::new();
+    for (face, ratio) in coarser_neighbors.into_iter().enumerate() {
+        let Some(ratio) = ratio else {
+            continue;
+        };
+        let (axis, is_max) = (face / 2, face % 2 == 1);
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        // The open border of this chunk's mesh follows the contour on the inner plane of samples of its layer of cubes on the
+        // face, since the quads of the edges that cross the layer are left to the neighbor.
+        let fine_layer = if is_max { max[axis] - 1 } else { min[axis] };
+        let fine_segments = plane_segments(size[u], size[v], |iu, iv| {
+            let mut p = min;
+            p[axis] = fine_layer;
+            p[u] += iu;
+            p[v] += iv;
+            sdf[shape.linearize(p) as usize].is_negative()
+        });
+        let mut next = HashMap::new();
+        for [a, b] in fine_segments {
+            let [a, b] = [a, b].map(|[iu, iv]| {
+                let mut p = [0; 3];
+                p[axis] = fine_layer - min[axis];
+                p[u] = iu;
+                p[v] = iv;
+                output.cube_to_index[(p[0] + size[0] * (p[1] + size[1] * p[2])) as usize]
+            });
+            if a != NULL_VERTEX && b != NULL_VERTEX {
+                let [a, b] = if is_max { [b, a] } else { [a, b] };
+                next.insert(a, b);
+            }
+        }
+        let fine_chains = follow_chains(&next);
+
+        // The neighbor's first layer of cubes, which overlaps this chunk's last layer, meshed from every `ratio`-th sample.
+        let mut origin = min;
+        origin[axis] = fine_layer;
+        let mut dims = [2; 3];
+        dims[u] = size[u].div_ceil(ratio) + 1;
+        dims[v] = size[v].div_ceil(ratio) + 1;
+        let layer_shape = RuntimeShape::<u32, 3>::new(dims);
+        let layer_sdf: Vec<T> = (0..layer_shape.size())
+            .map(|i| {
+                let p = layer_shape.delinearize(i);
+                sdf[shape.linearize([0, 1, 2].map(|a| origin[a] + ratio * p[a])) as usize]
+            })
+            .collect();
+        let mut layer = SurfaceNetsBuffer::default();
+        let layer_max = dims.map(|d| d - 1);
+        surface_nets_with_config(
+            &layer_sdf,
+            &layer_shape,
+            [0; 3],
+            layer_max,
+            coarse_config,
+            &mut layer,
+        );
+        let coarse_segments = plane_segments(dims[u] - 1, dims[v] - 1, |iu, iv| {
+            let mut p = [0; 3];
+            p[u] = iu;
+            p[v] = iv;
+            layer_sdf[layer_shape.linearize(p) as usize].is_negative()
+        });
+        let mut copies = HashMap::new();
+        let mut next = HashMap::new();
+        for [a, b] in coarse_segments {
+            let [a, b] = [a, b].map(|[iu, iv]| {
+                let mut p = [0; 3];
+                p[u] = iu;
+                p[v] = iv;
+                let vertex = layer.cube_to_index
+                    [(p[0] + layer_max[0] * (p[1] + layer_max[1] * p[2])) as usize];
+                if vertex == NULL_VERTEX {
+                    return NULL_VERTEX;
+                }
+                *copies.entry(vertex).or_insert_with(|| {
+                    let p = Vec3A::from(layer.positions[vertex as usize]);
+                    output
+                        .positions
+                        .push((Vec3A::from(origin.map(|c| c as f32)) + ratio as f32 * p).into());
+                    if with_normals {
+                        let n = Vec3A::from(layer.normals[vertex as usize]);
+                        output.normals.push(match config.normal_mode {
+                            NormalMode::Normalized | NormalMode::AreaWeightedFaceAverage => {
+                                n.normalize_or_zero().into()
+                            }
+                            _ => (n / ratio as f32).into(),
+                        });
+                    }
+                    output.positions.len() as u32 - 1
+                })
+            });
+            if a != NULL_VERTEX && b != NULL_VERTEX {
+                let [a, b] = if is_max { [a, b] } else { [b, a] };
+                next.insert(a, b);
+            }
+        }
+        let coarse_chains = follow_chains(&next);
+
+        let position = |v: u32| Vec3A::from(output.positions[v as usize]);
+        zip_chain_sets(&fine_chains, &coarse_chains, &position, &mut triangles);
+    }
+    output.indices.extend_from_slice(&triangles);
+
+    if config.normal_mode == NormalMode::AreaWeightedFaceAverage {
+        output.make_area_weighted_normals(false, config.normal_direction);
+    }
+    if config.generate_line_indices {
+        output.make_line_indices(false);
+    }
+    if config.generate_face_normals {
+        output.make_face_normals();
+    }
+    output.update_aabb();
+}
+
+// The directed segments of the contour of the negative samples on a plane of `nu` by `nv` cubes, between the cubes on either
+// side of each crossed edge, wound counterclockwise around the negative region when `u` is to the right and `v` is up. Like
+// the quads of the mesh, the edges on the border of the plane and at the last cube of their axis are left out.
+fn plane_segments(nu: u32, nv: u32, is_negative: impl Fn(u32, u32) -> bool) -> Vec<[[u32; 2]; 2]> {
+    let mut segments = Vec::new();
+    for iv in 0..=nv {
+        for iu in 0..=nu {
+            let d0 = is_negative(iu, iv);
+            if iu + 1 < nu && 0 < iv && iv < nv && d0 != is_negative(iu + 1, iv) {
+                let [p, q] = [[iu, iv], [iu, iv - 1]];
+                segments.push(if d0 { [q, p] } else { [p, q] });
+            }
+            if iv + 1 < nv && 0 < iu && iu < nu && d0 != is_negative(iu, iv + 1) {
+                let [p, q] = [[iu, iv], [iu - 1, iv]];
+                segments.push(if d0 { [p, q] } else { [q, p] });
+            }
+        }
+    }
+    segments
+}
+
+// Chains the open edges of `chunk.mesh` whose vertices are both in the layer of cubes at `layer` along `axis` into polylines,
+// following the winding of their triangles, and returns them with whether each is a closed loop. Their vertices are copied to
+// `output` in world space.
+fn boundary_chains(
+    chunk: LodChunk,
+    axis: usize,
+    layer: u32,
+    scale: f32,
+    with_normals: bool,
+    output: &mut SurfaceNetsBuffer,
+) -> Vec<(Vec<u32>, bool)> {
+    let mesh = chunk.mesh;
+    let in_layer = |v: u32| mesh.surface_points[v as usize][axis] == layer;
+    let mut next = HashMap::<u32, u32>::new();
+    for (tri, neighbors) in mesh
+        .indices
+        .chunks_exact(3)
+        .zip(triangle_neighbors(&mesh.indices))
+    {
+        for e in 0..3 {
+            let [a, b] = [tri[e], tri[(e + 1) % 3]];
+            if neighbors[e] == NO_NEIGHBOR && in_layer(a) && in_layer(b) {
+                next.entry(a).or_insert(b);
+            }
+        }
+    }
+
+    let mut chains = follow_chains(&next);
+    for (chain, _) in chains.iter_mut() {
+        for v in chain.iter_mut() {
+            let p = Vec3A::from(mesh.positions[*v as usize]);
+            output
+                .positions
+                .push((Vec3A::from(chunk.world_offset) + scale * p).into());
+            if with_normals {
+                output.normals.push(mesh.normals[*v as usize]);
+            }
+            *v = output.positions.len() as u32 - 1;
+        }
+    }
+    chains
+}
+
+// Chains the directed edges `a -> next[a]` into polylines, and returns them with whether each is a closed loop. The open
+// chains are followed from their first vertices, then the closed loops from any vertex, in vertex order. Each vertex is in
+// at most one chain.
+fn follow_chains(next: &HashMap<u32, u32>) -> Vec<(Vec<u32>, bool)> {
+    let has_previous: HashSet<u32> = next.values().copied().collect();
+    let mut starts: Vec<u32> = next.keys().copied().collect();
+    starts.sort_unstable();
+    let open_starts = starts.iter().filter(|v| !has_previous.contains(v));
+    let mut visited = HashSet::new();
+    let mut chains = Vec::new();
+    for (&start, is_closed) in open_starts
+        .map(|v| (v, false))
+        .chain(starts.iter().map(|v| (v, true)))
+    {
+        if visited.contains(&start) {
+            continue;
+        }
+        let mut chain = Vec::new();
+        let mut v = Some(start);
+        while let Some(current) = v.filter(|v| !visited.contains(v)) {
+            visited.insert(current);
+            chain.push(current);
+            v = next.get(&current).copied();
+        }
+        chains.push((chain, is_closed));
+    }
+    chains
+}
+
+// Zips each fine chain to the closest coarse chain. The coarse chains run the other way around the surface, since they bound
+// the mesh on the other side of the seam.
+fn zip_chain_sets(
+    fine_chains: &[(Vec<u32>, bool)],
+    coarse_chains: &[(Vec<u32>, bool)],
+    position: &impl Fn(u32) -> Vec3A,
+    triangles: &mut Vec<u32>,
+) {
+    if coarse_chains.is_empty() {
+        return;
+    }
+    for (fine_chain, is_closed) in fine_chains.iter() {
+        let nearest = |chain: &[u32]| {
+            chain
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| (position(v).distance_squared(position(fine_chain[0])), i))
+                .fold((f32::INFINITY, 0), |a, b| if b.0 < a.0 { b } else { a })
+        };
+        let (coarse_chain, _) = coarse_chains
+            .iter()
+            .min_by(|a, b| nearest(&a.0).0.total_cmp(&nearest(&b.0).0))
+            .unwrap();
+        let mut coarse_chain: Vec<u32> = coarse_chain.iter().rev().copied().collect();
+        let mut fine_chain = fine_chain.clone();
+        if *is_closed {
+            // Start the loops at their closest vertices, and close them.
+            let start = nearest(&coarse_chain).1;
+            coarse_chain.rotate_left(start);
+            fine_chain.push(fine_chain[0]);
+            coarse_chain.push(coarse_chain[0]);
+        }
+        zip_chains(&fine_chain, &coarse_chain, position, triangles);
+    }
+}
+
+// Triangulates the strip between two polylines that run the same way, advancing along whichever one makes the shorter
+// diagonal. Each edge of `fine` is wound backward, and each edge of `coarse` forward.
+fn zip_chains(
+    fine: &[u32],
+    coarse: &[u32],
+    position: &impl Fn(u32) -> Vec3A,
+    triangles: &mut Vec<u32>,
+) {
+    let (mut i, mut j) = (0, 0);
+    while i + 1 < fine.len() || j + 1 < coarse.len() {
+        let advance_fine = if i + 1 == fine.len() {
+            false
+        } else if j + 1 == coarse.len() {
+            true
+        } else {
+            position(fine[i + 1]).distance_squared(position(coarse[j]))
+                < position(fine[i]).distance_squared(position(coarse[j + 1]))
+        };
+        if advance_fine {
+            triangles.extend_from_slice(&[fine[i + 1], fine[i], coarse[j]]);
+            i += 1;
+        } else {
+            triangles.extend_from_slice(&[fine[i], coarse[j], coarse[j + 1]]);
+            j += 1;
+        }
+    }
+}
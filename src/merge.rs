@@ -0,0 +1,295 @@
+use crate::topology::edge_key;
+use crate::SurfaceNetsBuffer;
+
+use glam::{Vec2, Vec3A};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Merges groups of adjacent coplanar quads into larger polygons and re-triangulates them, which cuts the triangle count of
+/// flat regions (e.g. the walls and floors of blocky SDFs) dramatically.
+///
+/// The index buffer is read as a list of quads, each split into 2 triangles, which is how [`surface_nets`](crate::surface_nets)
+/// lays out its output. Quads are grown into regions greedily, in order: a neighboring quad joins a region if its normal is
+/// within `normal_epsilon` (`1 - cos(angle)`) of the region's first quad, and its corners are within `planarity_epsilon` of
+/// that quad's plane. The boundary of each region is then simplified by dropping the vertices that are on a straight line and
+/// not used by any other triangle (so no cracks open up next to the region), and triangulated by ear clipping.
+///
+/// Regions that aren't a simple polygon (e.g. they have holes) are left alone. The output is a plain triangle list, not quads.
+/// Vertices that are no longer used are left in place, so the per-vertex buffers and `cube_to_index` stay valid. Boundary faces
+/// can be merged into their neighbors, so [`SurfaceNetsBuffer::boundary_indices_start`] is cleared.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{merge_coplanar, surface_nets, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A flat plane, which meshes to a grid of 16x16 quads.
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [_, _, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = z as f32 - 8.5;
+/// }
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// assert_eq!(mesh.indices.len() / 3, 2 * 16 * 16);
+///
+/// let area = |mesh: &SurfaceNetsBuffer| -> f32 {
+///     mesh.indices.chunks(3).map(|tri| {
+///         let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[tri[i] as usize]);
+///         // The signed area, viewed from above.
+///         0.5 * ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]))
+///     }).sum()
+/// };
+/// let area_before = area(&mesh);
+///
+/// // The whole plane collapses into a single square, with the same area and winding.
+/// merge_coplanar(&mut mesh, 1e-4, 1e-4);
+/// assert_eq!(mesh.indices.len() / 3, 2);
+/// assert!((area(&mesh) - area_before).abs() < 1e-3);
+/// ```
+pub fn merge_coplanar(mesh: &mut SurfaceNetsBuffer, normal_epsilon: f32, planarity_epsilon: f32) {
+    let quads: Vec<Option<Quad>> = mesh
+        .indices
+        .chunks_exact(6)
+        .map(|indices| Quad::new(indices, &mesh.positions))
+        .collect();
+
+    let mut edge_to_quads = HashMap::<[u32; 2], Vec<u32>>::new();
+    for (q, quad) in quads.iter().enumerate() {
+        if let Some(quad) = quad {
+            for [a, b] in quad.edges {
+                edge_to_quads
+                    .entry(edge_key(a, b))
+                    .or_default()
+                    .push(q as u32);
+            }
+        }
+    }
+    let mut num_uses = vec![0u32; mesh.positions.len()];
+    for &v in mesh.indices.iter() {
+        num_uses[v as usize] += 1;
+    }
+
+    let position = |v: u32| Vec3A::from(mesh.positions[v as usize]);
+    let mut merged = Vec::with_capacity(mesh.indices.len());
+    let mut state = vec![QuadState::Unvisited; quads.len()];
+    let mut queue = VecDeque::new();
+    for seed in 0..quads.len() {
+        let seed_quad = match (&quads[seed], state[seed]) {
+            (_, QuadState::Merged) => continue,
+            (Some(quad), QuadState::Unvisited) => quad,
+            _ => {
+                merged.extend_from_slice(&mesh.indices[6 * seed..6 * seed + 6]);
+                continue;
+            }
+        };
+
+        // Grow a region of coplanar quads from the seed.
+        let (normal, origin) = (seed_quad.normal, position(seed_quad.edges[0][0]));
+        let mut region = vec![seed];
+        state[seed] = QuadState::Merged;
+        queue.push_back(seed);
+        while let Some(q) = queue.pop_front() {
+            let Some(quad) = &quads[q] else { continue };
+            for [a, b] in quad.edges {
+                let neighbors = &edge_to_quads[&edge_key(a, b)];
+                if neighbors.len() != 2 {
+                    continue;
+                }
+                for &n in neighbors.iter() {
+                    let n = n as usize;
+                    let Some(neighbor) = &quads[n] else { continue };
+                    if state[n] != QuadState::Unvisited
+                        || 1.0 - neighbor.normal.dot(normal) > normal_epsilon
+                        || neighbor.edges.iter().any(|&[v, _]| {
+                            (position(v) - origin).dot(normal).abs() > planarity_epsilon
+                        })
+                    {
+                        continue;
+                    }
+                    state[n] = QuadState::Merged;
+                    region.push(n);
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        let triangles = if region.len() > 1 {
+            triangulate_region(mesh, &quads, &region, &num_uses, normal, planarity_epsilon)
+        } else {
+            None
+        };
+        match triangles {
+            Some(triangles) => merged.extend(triangles),
+            None => {
+                // Keep the original quads, each in its own place.
+                merged.extend_from_slice(&mesh.indices[6 * seed..6 * seed + 6]);
+                for &q in region[1..].iter() {
+                    state[q] = QuadState::Unmerged;
+                }
+            }
+        }
+    }
+
+    // A trailing triangle that isn't part of a quad is kept as is.
+    merged.extend_from_slice(&mesh.indices[6 * quads.len()..]);
+    mesh.indices = merged;
+    mesh.boundary_indices_start = None;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum QuadState {
+    Unvisited,
+    // In a region, whose triangles were emitted with its first quad.
+    Merged,
+    // In a region that couldn't be merged, so it's emitted as is.
+    Unmerged,
+}
+
+// A quad of 2 triangles, with its 4 outer edges (in winding order) and unit normal.
+struct Quad {
+    edges: [[u32; 2]; 4],
+    normal: Vec3A,
+}
+
+impl Quad {
+    fn new(indices: &[u32], positions: &[[f32; 3]]) -> Option<Self> {
+        let tri_edges = |tri: &[u32]| [0, 1, 2].map(|e| [tri[e], tri[(e + 1) % 3]]);
+        let (edges1, edges2) = (tri_edges(&indices[..3]), tri_edges(&indices[3..]));
+        // The diagonal is the only edge that appears in both triangles, in opposite directions.
+        let outer: Vec<[u32; 2]> = edges1
+            .iter()
+            .filter(|&&[a, b]| !edges2.contains(&[b, a]))
+            .chain(edges2.iter().filter(|&&[a, b]| !edges1.contains(&[b, a])))
+            .copied()
+            .collect();
+        let edges: [[u32; 2]; 4] = outer.try_into().ok()?;
+
+        let normal: Vec3A = [&indices[..3], &indices[3..]]
+            .iter()
+            .map(|tri| {
+                let [a, b, c] = [0, 1, 2].map(|i| Vec3A::from(positions[tri[i] as usize]));
+                (b - a).cross(c - a)
+            })
+            .sum();
+        let normal = normal.try_normalize()?;
+
+        Some(Self { edges, normal })
+    }
+}
+
+// Triangulates the boundary of `region`, or returns `None` if it isn't a simple polygon.
+fn triangulate_region(
+    mesh: &SurfaceNetsBuffer,
+    quads: &[Option<Quad>],
+    region: &[usize],
+    num_uses: &[u32],
+    normal: Vec3A,
+    planarity_epsilon: f32,
+) -> Option<Vec<u32>> {
+    // The boundary edges are the ones whose reverse isn't in the region.
+    let mut region_edges = HashSet::<[u32; 2]>::new();
+    let mut num_region_uses = HashMap::<u32, u32>::new();
+    for &q in region {
+        for [a, b] in quads[q].as_ref()?.edges {
+            region_edges.insert([a, b]);
+        }
+        for &v in mesh.indices[6 * q..6 * q + 6].iter() {
+            *num_region_uses.entry(v).or_insert(0) += 1;
+        }
+    }
+    let mut next = HashMap::<u32, u32>::new();
+    for &[a, b] in region_edges.iter() {
+        if !region_edges.contains(&[b, a]) && next.insert(a, b).is_some() {
+            return None;
+        }
+    }
+
+    // Walk the boundary, which must be a single loop.
+    let &start = next.keys().min()?;
+    let mut polygon = vec![start];
+    let mut v = next[&start];
+    while v != start {
+        if polygon.len() > next.len() {
+            return None;
+        }
+        polygon.push(v);
+        v = *next.get(&v)?;
+    }
+    if polygon.len() != next.len() {
+        return None;
+    }
+
+    // Interior vertices are dropped, so they must not be used by anything else.
+    let used_elsewhere = |v: u32| num_uses[v as usize] > num_region_uses[&v];
+    if num_region_uses
+        .keys()
+        .any(|&v| !next.contains_key(&v) && used_elsewhere(v))
+    {
+        return None;
+    }
+
+    // Project onto the plane, such that the polygon winds counterclockwise.
+    let u = normal.any_orthonormal_vector();
+    let w = normal.cross(u);
+    let project = |v: u32| {
+        let p = Vec3A::from(mesh.positions[v as usize]);
+        Vec2::new(p.dot(u), p.dot(w))
+    };
+
+    // Drop the boundary vertices on straight lines that nothing else uses.
+    let mut i = 0;
+    let mut num_unchanged = 0;
+    while polygon.len() > 3 && num_unchanged < polygon.len() {
+        let n = polygon.len();
+        let [prev, cur, next] = [(i + n - 1) % n, i, (i + 1) % n].map(|j| project(polygon[j]));
+        let (along, across) = (next - prev, cur - prev);
+        let distance = along.perp_dot(across).abs() / along.length().max(f32::EPSILON);
+        if !used_elsewhere(polygon[i])
+            && distance <= planarity_epsilon
+            && (cur - prev).dot(next - cur) > 0.0
+        {
+            polygon.remove(i);
+            num_unchanged = 0;
+            i %= polygon.len();
+        } else {
+            num_unchanged += 1;
+            i = (i + 1) % polygon.len();
+        }
+    }
+
+    ear_clip(
+        &polygon,
+        &polygon.iter().map(|&v| project(v)).collect::<Vec<_>>(),
+    )
+}
+
+// Triangulates a simple counterclockwise polygon by ear clipping, or returns `None` if it isn't one.
+fn ear_clip(vertices: &[u32], points: &[Vec2]) -> Option<Vec<u32>> {
+    let mut remaining: Vec<usize> = (0..vertices.len()).collect();
+    let mut triangles = Vec::with_capacity(3 * vertices.len());
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let [a, b, c] = [(i + n - 1) % n, i, (i + 1) % n].map(|j| remaining[j]);
+            let [pa, pb, pc] = [a, b, c].map(|j| points[j]);
+            (pb - pa).perp_dot(pc - pb) > 0.0
+                && remaining
+                    .iter()
+                    .filter(|&&j| j != a && j != b && j != c)
+                    .all(|&j| !in_triangle(points[j], pa, pb, pc))
+        })?;
+        let [a, b, c] = [(ear + n - 1) % n, ear, (ear + 1) % n].map(|j| remaining[j]);
+        triangles.extend_from_slice(&[vertices[a], vertices[b], vertices[c]]);
+        remaining.remove(ear);
+    }
+    triangles.extend(remaining.iter().map(|&j| vertices[j]));
+    Some(triangles)
+}
+
+// Whether `p` is inside of (or on) the counterclockwise triangle `abc`.
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    (b - a).perp_dot(p - a) >= 0.0
+        && (c - b).perp_dot(p - b) >= 0.0
+        && (a - c).perp_dot(p - c) >= 0.0
+}
@@ -0,0 +1,104 @@
+use crate::SignedDistance;
+
+use ndarray::ArrayView3;
+use ndshape::Shape;
+use std::borrow::Cow;
+
+/// A 3D [`Shape`] for arrays in C (row-major) order, like the standard layout of an `ndarray::Array3`, where the *last*
+/// coordinate varies fastest in memory.
+///
+/// Coordinates are used exactly as given, so the point `[x, y, z]` is the array element `array[[x, y, z]]`. Using an
+/// `ndshape` shape like `ConstShape3u32` instead would silently swap the X and Z axes, which mirrors the mesh.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct COrderShape3 {
+    dims: [u32; 3],
+}
+
+impl COrderShape3 {
+    /// `dims` are the array dimensions, in the same order as `ndarray::ArrayBase::dim`.
+    pub fn new(dims: [u32; 3]) -> Self {
+        Self { dims }
+    }
+}
+
+impl Shape<3> for COrderShape3 {
+    type Coord = u32;
+
+    #[inline]
+    fn size(&self) -> u32 {
+        self.dims[0] * self.dims[1] * self.dims[2]
+    }
+
+    #[inline]
+    fn usize(&self) -> usize {
+        self.size() as usize
+    }
+
+    #[inline]
+    fn as_array(&self) -> [u32; 3] {
+        self.dims
+    }
+
+    #[inline]
+    fn linearize(&self, [x, y, z]: [u32; 3]) -> u32 {
+        (x * self.dims[1] + y) * self.dims[2] + z
+    }
+
+    #[inline]
+    fn delinearize(&self, i: u32) -> [u32; 3] {
+        let z = i % self.dims[2];
+        let xy = i / self.dims[2];
+        [xy / self.dims[1], xy % self.dims[1], z]
+    }
+}
+
+/// Prepares an `ndarray` view for meshing, returning the sample slice and the matching [`COrderShape3`].
+///
+/// Views in standard (C-order, contiguous) layout are borrowed as-is. Any other view (e.g. Fortran order, transposed or
+/// strided views) is copied into C order first.
+///
+/// ```
+/// use fast_surface_nets::ndarray::{Array3, ShapeBuilder};
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{ndarray_sdf, surface_nets, SurfaceNetsBuffer};
+///
+/// fn sphere([x, y, z]: [usize; 3]) -> f32 {
+///     let [x, y, z] = [x as f32 - 6.0, y as f32 - 9.0, z as f32 - 12.0];
+///     (x * x + y * y + z * z).sqrt() - 5.0
+/// }
+///
+/// // The reference mesh, sampled in ndshape's X-fastest order.
+/// type NativeShape = ConstShape3u32<18, 18, 18>;
+/// let mut native_sdf = [1.0; NativeShape::USIZE];
+/// for i in 0u32..NativeShape::SIZE {
+///     let [x, y, z] = NativeShape::delinearize(i);
+///     native_sdf[i as usize] = sphere([x as usize, y as usize, z as usize]);
+/// }
+/// let mut native_mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&native_sdf, &NativeShape {}, [0; 3], [17; 3], &mut native_mesh);
+///
+/// // Both a C-order and a Fortran-order array produce the same (unmirrored) mesh.
+/// let c_array = Array3::from_shape_fn((18, 18, 18), |(x, y, z)| sphere([x, y, z]));
+/// let f_array = Array3::from_shape_fn((18, 18, 18).f(), |(x, y, z)| sphere([x, y, z]));
+/// for array in [c_array, f_array] {
+///     let (sdf, shape) = ndarray_sdf(array.view());
+///     let mut mesh = SurfaceNetsBuffer::default();
+///     surface_nets(&sdf, &shape, [0; 3], [17; 3], &mut mesh);
+///     assert_eq!(mesh.positions, native_mesh.positions);
+///     assert_eq!(mesh.normals, native_mesh.normals);
+///     assert_eq!(mesh.indices, native_mesh.indices);
+/// }
+/// ```
+pub fn ndarray_sdf<T>(view: ArrayView3<'_, T>) -> (Cow<'_, [T]>, COrderShape3)
+where
+    T: SignedDistance,
+{
+    let (d0, d1, d2) = view.dim();
+    let shape = COrderShape3::new([d0 as u32, d1 as u32, d2 as u32]);
+    let sdf = match view.to_slice() {
+        Some(slice) => Cow::Borrowed(slice),
+        // Logical iteration order is C order, regardless of the memory layout.
+        None => Cow::Owned(view.iter().copied().collect()),
+    };
+    (sdf, shape)
+}
@@ -0,0 +1,116 @@
+use crate::SurfaceNetsBuffer;
+
+use std::io::{self, Write};
+
+/// Writes meshes to a [Wavefront OBJ](https://en.wikipedia.org/wiki/Wavefront_.obj_file) stream as they are generated, e.g. to
+/// export a large volume chunk by chunk while reusing a single [`SurfaceNetsBuffer`].
+///
+/// Each call to [`write_mesh`](Self::write_mesh) writes the vertices of one mesh, followed by its faces. OBJ faces may refer
+/// to any vertex written before them, so nothing needs to be buffered between meshes: the writer only tracks how many vertices
+/// were written so far, to offset the (1-based) indices of the following meshes. Vertices shared by neighboring chunks are
+/// written once per chunk, since they aren't welded.
+///
+/// Binary formats like PLY are not supported, since their header must contain the total vertex and face counts before any
+/// data, which would require buffering the whole mesh (or a seekable output).
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, ObjWriter, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<10, 10, 10>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 4.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 3.0;
+/// }
+///
+/// // Stream two chunks (the same one, translated) through a single buffer.
+/// let mut writer = ObjWriter::new(Vec::new());
+/// let mut buffer = SurfaceNetsBuffer::default();
+/// for translation in [[0.0; 3], [9.0, 0.0, 0.0]] {
+///     surface_nets(&sdf, &ChunkShape {}, [0; 3], [9; 3], &mut buffer);
+///     writer.write_mesh(&buffer, translation)?;
+/// }
+/// let obj = String::from_utf8(writer.finish()?)?;
+///
+/// // Read it back.
+/// let mut positions = Vec::new();
+/// let mut indices = Vec::new();
+/// for line in obj.lines() {
+///     let mut words = line.split_whitespace();
+///     match words.next() {
+///         Some("v") => positions.push([0; 3].map(|_| words.next().unwrap().parse::<f32>().unwrap())),
+///         Some("f") => indices.extend(words.map(|w| w.split('/').next().unwrap().parse::<u32>().unwrap() - 1)),
+///         _ => {}
+///     }
+/// }
+///
+/// let num_vertices = buffer.positions.len();
+/// assert_eq!(positions.len(), 2 * num_vertices);
+/// assert_eq!(positions[..num_vertices], buffer.positions[..]);
+/// for (&[x, y, z], &p) in buffer.positions.iter().zip(positions[num_vertices..].iter()) {
+///     assert_eq!([x + 9.0, y, z], p);
+/// }
+/// let second_indices = buffer.indices.iter().map(|&i| i + num_vertices as u32);
+/// assert!(indices.iter().copied().eq(buffer.indices.iter().copied().chain(second_indices)));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ObjWriter<W: Write> {
+    writer: W,
+    num_vertices: u32,
+    num_normals: u32,
+}
+
+impl<W: Write> ObjWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            num_vertices: 0,
+            num_normals: 0,
+        }
+    }
+
+    /// Appends the triangles of `mesh` to the stream, with every position offset by `translation` (e.g. the chunk's world
+    /// offset). Normals are written too if `mesh` has them.
+    pub fn write_mesh(
+        &mut self,
+        mesh: &SurfaceNetsBuffer,
+        translation: [f32; 3],
+    ) -> io::Result<()> {
+        let [tx, ty, tz] = translation;
+        for &[x, y, z] in mesh.positions.iter() {
+            writeln!(self.writer, "v {} {} {}", x + tx, y + ty, z + tz)?;
+        }
+        let has_normals = mesh.normals.len() == mesh.positions.len();
+        if has_normals {
+            for &[x, y, z] in mesh.normals.iter() {
+                writeln!(self.writer, "vn {x} {y} {z}")?;
+            }
+        }
+
+        // OBJ indices are 1-based, and count every vertex (or normal) written so far.
+        let (offset, normal_offset) = (self.num_vertices + 1, self.num_normals + 1);
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0] + offset, tri[1] + offset, tri[2] + offset];
+            if has_normals {
+                let [na, nb, nc] = [0, 1, 2].map(|i| tri[i] + normal_offset);
+                writeln!(self.writer, "f {a}//{na} {b}//{nb} {c}//{nc}")?;
+            } else {
+                writeln!(self.writer, "f {a} {b} {c}")?;
+            }
+        }
+        self.num_vertices += mesh.positions.len() as u32;
+        if has_normals {
+            self.num_normals += mesh.normals.len() as u32;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the stream and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
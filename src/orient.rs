@@ -0,0 +1,125 @@
+use crate::topology::{triangle_neighbors, NO_NEIGHBOR};
+use crate::SurfaceNetsBuffer;
+
+use glam::Vec3A;
+use std::collections::VecDeque;
+
+/// Flips the winding of triangles in `mesh` so that neighboring triangles agree, e.g. to repair a mesh after welding or other
+/// post-processing.
+///
+/// Each connected component is traversed breadth-first over the triangle adjacency graph, flipping every triangle whose shared
+/// edge runs the same way as its already visited neighbor's. Then the component keeps whichever orientation most of its
+/// triangles had. If `orient_outward` is true, each component is instead flipped as a whole if its signed volume is negative,
+/// so that its triangles face outward; this is only meaningful for closed components.
+///
+/// Components are handled independently. A non-orientable component (like a Möbius strip) can't be made consistent, so some of
+/// its edges will still disagree. Normals are not changed.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{orient_consistently, surface_nets, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+/// }
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// let original = mesh.indices.clone();
+///
+/// // Mis-wind a minority of the triangles, including the first one.
+/// let flip = |indices: &mut [u32], t: usize| indices.swap(3 * t + 1, 3 * t + 2);
+/// for t in (0..mesh.indices.len() / 3).step_by(7) {
+///     flip(&mut mesh.indices, t);
+/// }
+/// orient_consistently(&mut mesh, false);
+/// assert_eq!(mesh.indices, original);
+///
+/// // A sphere wound inside out is flipped back to face outward.
+/// for t in 0..mesh.indices.len() / 3 {
+///     flip(&mut mesh.indices, t);
+/// }
+/// orient_consistently(&mut mesh, true);
+/// assert_eq!(mesh.indices, original);
+/// ```
+pub fn orient_consistently(mesh: &mut SurfaceNetsBuffer, orient_outward: bool) {
+    let neighbors = triangle_neighbors(&mesh.indices);
+    let num_triangles = neighbors.len();
+    let mut visited = vec![false; num_triangles];
+    let mut component = Vec::new();
+    let mut queue = VecDeque::new();
+
+    for seed in 0..num_triangles {
+        if visited[seed] {
+            continue;
+        }
+        visited[seed] = true;
+        queue.push_back(seed);
+        component.clear();
+        let mut num_flipped = 0;
+
+        while let Some(t) = queue.pop_front() {
+            component.push(t);
+            for &n in neighbors[t].iter().filter(|&&n| n != NO_NEIGHBOR) {
+                let n = n as usize;
+                if visited[n] {
+                    continue;
+                }
+                visited[n] = true;
+                if !windings_agree(&mesh.indices, t, n) {
+                    flip_triangle(&mut mesh.indices, n);
+                    num_flipped += 1;
+                }
+                queue.push_back(n);
+            }
+        }
+
+        let flip_component = if orient_outward {
+            signed_volume(mesh, &component) < 0.0
+        } else {
+            2 * num_flipped > component.len()
+        };
+        if flip_component {
+            for &t in component.iter() {
+                flip_triangle(&mut mesh.indices, t);
+            }
+        }
+    }
+}
+
+// Whether the edge shared by triangles `t` and `n` runs in opposite directions in each of them.
+fn windings_agree(indices: &[u32], t: usize, n: usize) -> bool {
+    let [tri, other] = [t, n].map(|t| &indices[3 * t..3 * t + 3]);
+    for e in 0..3 {
+        let [a, b] = [tri[e], tri[(e + 1) % 3]];
+        for f in 0..3 {
+            let [c, d] = [other[f], other[(f + 1) % 3]];
+            if [a, b] == [c, d] {
+                return false;
+            }
+            if [a, b] == [d, c] {
+                return true;
+            }
+        }
+    }
+    true
+}
+
+fn flip_triangle(indices: &mut [u32], t: usize) {
+    indices.swap(3 * t + 1, 3 * t + 2);
+}
+
+// The signed volume enclosed by the triangles of `component` (times 6), which is positive if they face outward.
+fn signed_volume(mesh: &SurfaceNetsBuffer, component: &[usize]) -> f32 {
+    component
+        .iter()
+        .map(|&t| {
+            let [a, b, c] =
+                [0, 1, 2].map(|i| Vec3A::from(mesh.positions[mesh.indices[3 * t + i] as usize]));
+            a.dot(b.cross(c))
+        })
+        .sum()
+}
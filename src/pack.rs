@@ -0,0 +1,227 @@
+use crate::SurfaceNetsBuffer;
+
+use glam::{Vec2, Vec3A};
+
+/// A per-vertex attribute of a [`SurfaceNetsBuffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VertexAttribute {
+    /// [`SurfaceNetsBuffer::positions`].
+    Position,
+    /// [`SurfaceNetsBuffer::normals`].
+    Normal,
+    /// [`SurfaceNetsBuffer::uvs`].
+    Uv,
+    /// [`SurfaceNetsBuffer::barycentrics`].
+    Barycentric,
+}
+
+impl VertexAttribute {
+    fn num_components(self) -> usize {
+        match self {
+            Self::Uv => 2,
+            Self::Position | Self::Normal | Self::Barycentric => 3,
+        }
+    }
+}
+
+/// How a [`VertexAttribute`] is encoded in the packed vertex buffer. Every format is a multiple of 4 bytes, so attributes stay
+/// aligned without padding. All values are little-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VertexFormat {
+    /// Two `f32`s, for 2-component attributes.
+    Float32x2,
+    /// Three `f32`s, for 3-component attributes.
+    Float32x3,
+    /// Four `i8`s, each mapping `[-1, 1]` to `[-127, 127]`, with the last one set to 0. Normals are normalized first, and
+    /// other attributes are clamped.
+    Snorm8x4,
+    /// A normalized vector in [octahedral encoding](https://jcgt.org/published/0003/02/01/), as two `i16`s each mapping
+    /// `[-1, 1]` to `[-32767, 32767]`.
+    OctahedralSnorm16x2,
+}
+
+impl VertexFormat {
+    /// The size of an attribute in this format, in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Self::Float32x2 => 8,
+            Self::Float32x3 => 12,
+            Self::Snorm8x4 | Self::OctahedralSnorm16x2 => 4,
+        }
+    }
+
+    fn num_components(self) -> usize {
+        match self {
+            Self::Float32x2 => 2,
+            Self::Float32x3 | Self::Snorm8x4 | Self::OctahedralSnorm16x2 => 3,
+        }
+    }
+}
+
+/// The attributes of an interleaved vertex, in order, with no padding between them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VertexLayout {
+    /// Each attribute with its format. An attribute can appear more than once, e.g. in two formats.
+    pub attributes: Vec<(VertexAttribute, VertexFormat)>,
+}
+
+impl VertexLayout {
+    /// The size of a vertex, in bytes.
+    pub fn stride(&self) -> usize {
+        self.attributes
+            .iter()
+            .map(|&(_, format)| format.size())
+            .sum()
+    }
+
+    /// The byte offset of each attribute within a vertex.
+    pub fn offsets(&self) -> Vec<usize> {
+        self.attributes
+            .iter()
+            .scan(0, |offset, &(_, format)| {
+                let attribute_offset = *offset;
+                *offset += format.size();
+                Some(attribute_offset)
+            })
+            .collect()
+    }
+}
+
+/// Packs the vertices of `mesh` into a single interleaved buffer following `layout`, ready to upload to the GPU, e.g. as a
+/// `wgpu` vertex buffer with [`VertexLayout::stride`] as its array stride. The index buffer can be uploaded as is.
+///
+/// Panics if an attribute in the layout isn't populated (i.e. not the same length as `positions`), or if its format has the
+/// wrong number of components for it.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{pack_vertices, surface_nets, SurfaceNetsBuffer, VertexAttribute, VertexFormat, VertexLayout};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+/// }
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// mesh.uvs = mesh.positions.iter().map(|p| [p[0] / 17.0, p[1] / 17.0]).collect();
+///
+/// let layout = VertexLayout {
+///     attributes: vec![
+///         (VertexAttribute::Position, VertexFormat::Float32x3),
+///         (VertexAttribute::Normal, VertexFormat::Snorm8x4),
+///         (VertexAttribute::Uv, VertexFormat::Float32x2),
+///         (VertexAttribute::Normal, VertexFormat::OctahedralSnorm16x2),
+///     ],
+/// };
+/// assert_eq!(layout.stride(), 12 + 4 + 8 + 4);
+/// assert_eq!(layout.offsets(), [0, 12, 16, 24]);
+/// let bytes = pack_vertices(&mesh, &layout);
+/// assert_eq!(bytes.len(), mesh.positions.len() * layout.stride());
+///
+/// // Decode every vertex, and compare it with the source attributes.
+/// let f32_at = |b: &[u8], i: usize| f32::from_le_bytes(b[4 * i..4 * i + 4].try_into().unwrap());
+/// let octahedral_decode = |[u, v]: [f32; 2]| {
+///     let z = 1.0 - u.abs() - v.abs();
+///     let (x, y) = if z < 0.0 {
+///         ((1.0 - v.abs()) * u.signum(), (1.0 - u.abs()) * v.signum())
+///     } else {
+///         (u, v)
+///     };
+///     let length = (x * x + y * y + z * z).sqrt();
+///     [x / length, y / length, z / length]
+/// };
+/// for (v, vertex) in bytes.chunks(layout.stride()).enumerate() {
+///     assert_eq!([0, 1, 2].map(|i| f32_at(vertex, i)), mesh.positions[v]);
+///     assert_eq!([0, 1].map(|i| f32_at(&vertex[16..], i)), mesh.uvs[v]);
+///
+///     let n = mesh.normals[v];
+///     let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+///     let unit = n.map(|c| c / length);
+///     let snorm8 = [0, 1, 2].map(|i| vertex[12 + i] as i8 as f32 / 127.0);
+///     assert_eq!(vertex[15], 0);
+///     let octahedral = octahedral_decode([0, 1].map(|i| {
+///         i16::from_le_bytes([vertex[24 + 2 * i], vertex[25 + 2 * i]]) as f32 / 32767.0
+///     }));
+///     for i in 0..3 {
+///         assert!((snorm8[i] - unit[i]).abs() <= 0.5 / 127.0 + 1e-6);
+///         assert!((octahedral[i] - unit[i]).abs() < 1e-3);
+///     }
+/// }
+/// ```
+pub fn pack_vertices(mesh: &SurfaceNetsBuffer, layout: &VertexLayout) -> Vec<u8> {
+    let num_vertices = mesh.positions.len();
+    for &(attribute, format) in layout.attributes.iter() {
+        assert_eq!(
+            attribute.num_components(),
+            format.num_components(),
+            "{format:?} doesn't fit {attribute:?}"
+        );
+        let len = match attribute {
+            VertexAttribute::Position => num_vertices,
+            VertexAttribute::Normal => mesh.normals.len(),
+            VertexAttribute::Uv => mesh.uvs.len(),
+            VertexAttribute::Barycentric => mesh.barycentrics.len(),
+        };
+        assert_eq!(len, num_vertices, "{attribute:?} isn't populated");
+    }
+
+    let mut bytes = Vec::with_capacity(num_vertices * layout.stride());
+    for v in 0..num_vertices {
+        for &(attribute, format) in layout.attributes.iter() {
+            let value = match attribute {
+                VertexAttribute::Position => mesh.positions[v],
+                VertexAttribute::Normal => mesh.normals[v],
+                VertexAttribute::Uv => {
+                    let [u, v] = mesh.uvs[v];
+                    [u, v, 0.0]
+                }
+                VertexAttribute::Barycentric => mesh.barycentrics[v],
+            };
+            let unit = || {
+                if attribute == VertexAttribute::Normal {
+                    Vec3A::from(value).normalize_or_zero()
+                } else {
+                    Vec3A::from(value).clamp(Vec3A::splat(-1.0), Vec3A::ONE)
+                }
+            };
+            match format {
+                VertexFormat::Float32x2 => {
+                    for c in &value[..2] {
+                        bytes.extend_from_slice(&c.to_le_bytes());
+                    }
+                }
+                VertexFormat::Float32x3 => {
+                    for c in value {
+                        bytes.extend_from_slice(&c.to_le_bytes());
+                    }
+                }
+                VertexFormat::Snorm8x4 => {
+                    for c in unit().to_array() {
+                        bytes.push((c * 127.0).round() as i8 as u8);
+                    }
+                    bytes.push(0);
+                }
+                VertexFormat::OctahedralSnorm16x2 => {
+                    for c in octahedral_encode(unit()).to_array() {
+                        bytes.extend_from_slice(&((c * 32767.0).round() as i16).to_le_bytes());
+                    }
+                }
+            }
+        }
+    }
+    bytes
+}
+
+// Maps a unit vector onto the octahedron, unfolded into the [-1, 1] square.
+fn octahedral_encode(n: Vec3A) -> Vec2 {
+    let n = n / n.abs().element_sum().max(f32::EPSILON);
+    if n.z >= 0.0 {
+        Vec2::new(n.x, n.y)
+    } else {
+        let sign = |c: f32| if c >= 0.0 { 1.0 } else { -1.0 };
+        Vec2::new((1.0 - n.y.abs()) * sign(n.x), (1.0 - n.x.abs()) * sign(n.y))
+    }
+}
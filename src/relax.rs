@@ -0,0 +1,264 @@
+use crate::{SignedDistance, SurfaceNetsBuffer};
+
+use glam::Vec3A;
+use ndshape::Shape;
+
+/// Moves every vertex of `mesh` closer to the isosurface of `sdf`, which reduces shimmering when the SDF animates slightly and
+/// makes the surface more accurate.
+///
+/// Each iteration takes a damped Newton step `p -= step * d(p) * ∇d(p) / |∇d(p)|²` along the gradient, where `d` is the
+/// trilinear interpolation of `sdf`, so `step = 1.0` projects straight onto the interpolated surface. Every vertex is
+/// constrained to its cube in `surface_points` (if populated), so the mesh can't fold over. Normals are not updated.
+///
+/// This is meant for meshes without boundary caps or skirts, since those vertices would be pulled toward the surface too.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{relax_vertices, surface_nets, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let sphere = |[x, y, z]: [f32; 3]| {
+///     let [x, y, z] = [x - 8.5, y - 8.5, z - 8.5];
+///     (x * x + y * y + z * z).sqrt() - 6.0
+/// };
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     sdf[i as usize] = sphere(ChunkShape::delinearize(i).map(|c| c as f32));
+/// }
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+///
+/// let error = |mesh: &SurfaceNetsBuffer| {
+///     mesh.positions.iter().map(|&p| sphere(p).abs()).sum::<f32>() / mesh.positions.len() as f32
+/// };
+/// let error_before = error(&mesh);
+/// relax_vertices(&mut mesh, &sdf, &ChunkShape {}, 4, 0.5);
+/// assert!(error(&mesh) < error_before);
+/// ```
+pub fn relax_vertices<T, S>(
+    mesh: &mut SurfaceNetsBuffer,
+    sdf: &[T],
+    shape: &S,
+    iterations: usize,
+    step: f32,
+) where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    assert!(shape.usize() <= sdf.len());
+
+    let has_cubes = mesh.surface_points.len() == mesh.positions.len();
+    for (v, position) in mesh.positions.iter_mut().enumerate() {
+        let (cube_min, cube_max) = if has_cubes {
+            let cube_min = Vec3A::from(mesh.surface_points[v].map(|c| c as f32));
+            (cube_min, cube_min + 1.0)
+        } else {
+            (Vec3A::splat(f32::NEG_INFINITY), Vec3A::splat(f32::INFINITY))
+        };
+
+        let mut p = Vec3A::from(*position);
+        for _ in 0..iterations {
+            let (d, gradient) = sample_trilinear(sdf, shape, p);
+            let length_squared = gradient.length_squared();
+            if length_squared <= f32::EPSILON {
+                break;
+            }
+            p = (p - step * d * gradient / length_squared).clamp(cube_min, cube_max);
+        }
+        *position = p.into();
+    }
+}
+
+/// Returns the trilinear interpolation of `sdf` at `point`, from the 8 samples around it. Points outside of the array are clamped
+/// to it.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::sample_sdf_at;
+///
+/// type ChunkShape = ConstShape3u32<4, 4, 4>;
+///
+/// let mut sdf = [0.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+///     sdf[i as usize] = x + 2.0 * y - z;
+/// }
+///
+/// // Trilinear interpolation is exact for linear fields.
+/// assert_eq!(sample_sdf_at(&sdf, &ChunkShape {}, [1.5, 0.25, 2.0]), 1.5 + 0.5 - 2.0);
+/// assert_eq!(sample_sdf_at(&sdf, &ChunkShape {}, [3.0, 3.0, 0.0]), 9.0);
+/// ```
+pub fn sample_sdf_at<T, S>(sdf: &[T], shape: &S, point: [f32; 3]) -> f32
+where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    assert!(shape.usize() <= sdf.len());
+
+    sample_trilinear(sdf, shape, Vec3A::from(point)).0
+}
+
+/// Returns the largest distance from the isosurface of any vertex of `mesh`, i.e. the maximum absolute value of
+/// [`sample_sdf_at`] over the vertex positions, or 0 if there are none. This is a single accuracy number to assert in tests,
+/// which is ideally near zero.
+///
+/// The vertices of boundary caps aren't placed on the isosurface, so with `exclude_boundary_vertices`, the vertices whose cube
+/// in `surface_points` doesn't intersect the isosurface are skipped. This needs `surface_points` to be populated, and otherwise
+/// every vertex is checked. Skirt vertices share the cube of the surface vertex they hang from, so they're always checked.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{max_surface_error, surface_nets, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let sphere = |center: [f32; 3]| {
+///     let mut sdf = [1.0; ChunkShape::USIZE];
+///     for i in 0u32..ChunkShape::SIZE {
+///         let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+///         let [x, y, z] = [x - center[0], y - center[1], z - center[2]];
+///         sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 6.0;
+///     }
+///     sdf
+/// };
+///
+/// let sdf = sphere([8.5; 3]);
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// assert!(max_surface_error(&mesh, &sdf, &ChunkShape {}, false) < 0.02);
+///
+/// // The caps of a clipped sphere are off of the surface, unless they're excluded.
+/// let clipped = sphere([2.0, 8.5, 8.5]);
+/// let config = SurfaceNetsConfig {
+///     generate_boundary_faces: true,
+///     ..Default::default()
+/// };
+/// surface_nets_with_config(&clipped, &ChunkShape {}, [0; 3], [17; 3], config, &mut mesh);
+/// assert!(max_surface_error(&mesh, &clipped, &ChunkShape {}, false) > 1.0);
+/// assert!(max_surface_error(&mesh, &clipped, &ChunkShape {}, true) < 0.02);
+/// ```
+pub fn max_surface_error<T, S>(
+    mesh: &SurfaceNetsBuffer,
+    sdf: &[T],
+    shape: &S,
+    exclude_boundary_vertices: bool,
+) -> f32
+where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    assert!(shape.usize() <= sdf.len());
+
+    let has_cubes = mesh.surface_points.len() == mesh.positions.len();
+    mesh.positions
+        .iter()
+        .enumerate()
+        .filter(|&(v, _)| {
+            !(exclude_boundary_vertices
+                && has_cubes
+                && !cube_intersects_surface(sdf, shape, mesh.surface_points[v]))
+        })
+        .map(|(_, &p)| sample_trilinear(sdf, shape, Vec3A::from(p)).0.abs())
+        .fold(0.0, f32::max)
+}
+
+// Whether some but not all of the corners of the cube with minimal corner `cube` are negative.
+fn cube_intersects_surface<T, S>(sdf: &[T], shape: &S, cube: [u32; 3]) -> bool
+where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    let dims = shape.as_array();
+    let num_negative = (0..8)
+        .filter(|&i| {
+            let corner = [0, 1, 2].map(|axis| (cube[axis] + ((i >> axis) & 1)).min(dims[axis] - 1));
+            sdf[shape.linearize(corner) as usize].is_negative()
+        })
+        .count();
+    num_negative != 0 && num_negative != 8
+}
+
+impl SurfaceNetsBuffer {
+    /// Returns the SDF sampled at every vertex position with [`sample_sdf_at`], i.e. how far each vertex is from the isosurface
+    /// of the interpolated field. These are ideally near zero, so this measures the accuracy of the mesh (e.g. before and after
+    /// [`relax_vertices`]).
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets, SurfaceNetsBuffer};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// // A tilted plane.
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+    ///     sdf[i as usize] = 0.3 * x + 0.2 * y + z - 8.7;
+    /// }
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+    ///
+    /// // Every vertex is on the plane.
+    /// let errors = mesh.vertex_sdf_errors(&sdf, &ChunkShape {});
+    /// assert_eq!(errors.len(), mesh.positions.len());
+    /// assert!(errors.iter().all(|e| e.abs() < 1e-4));
+    /// ```
+    pub fn vertex_sdf_errors<T, S>(&self, sdf: &[T], shape: &S) -> Vec<f32>
+    where
+        T: SignedDistance,
+        S: Shape<3, Coord = u32>,
+    {
+        assert!(shape.usize() <= sdf.len());
+
+        self.positions
+            .iter()
+            .map(|&p| sample_trilinear(sdf, shape, Vec3A::from(p)).0)
+            .collect()
+    }
+}
+
+// Returns the trilinearly interpolated value of `sdf` at `p`, and its gradient. Points outside of the array are clamped to it.
+fn sample_trilinear<T, S>(sdf: &[T], shape: &S, p: Vec3A) -> (f32, Vec3A)
+where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    let dims = shape.as_array();
+    let mut min_corner = [0; 3];
+    let mut t = Vec3A::ZERO;
+    for axis in 0..3 {
+        let max_cell = dims[axis].saturating_sub(2) as f32;
+        let cell = p[axis].floor().clamp(0.0, max_cell);
+        min_corner[axis] = cell as u32;
+        t[axis] = (p[axis] - cell).clamp(0.0, 1.0);
+    }
+
+    let mut dists = [0.0; 8];
+    for (i, dist) in dists.iter_mut().enumerate() {
+        let corner =
+            [0, 1, 2].map(|axis| (min_corner[axis] + ((i as u32 >> axis) & 1)).min(dims[axis] - 1));
+        let d = sdf[shape.linearize(corner) as usize];
+        *dist = d.into() - d.surface_offset();
+    }
+
+    // Interpolate along X, then Y, then Z, in the `0b_zyx` corner order.
+    let lerp = |a: f32, b: f32, t: f32| a + t * (b - a);
+    let x00 = lerp(dists[0b000], dists[0b001], t.x);
+    let x10 = lerp(dists[0b010], dists[0b011], t.x);
+    let x01 = lerp(dists[0b100], dists[0b101], t.x);
+    let x11 = lerp(dists[0b110], dists[0b111], t.x);
+    let y0 = lerp(x00, x10, t.y);
+    let y1 = lerp(x01, x11, t.y);
+    let value = lerp(y0, y1, t.z);
+
+    let dx00 = dists[0b001] - dists[0b000];
+    let dx10 = dists[0b011] - dists[0b010];
+    let dx01 = dists[0b101] - dists[0b100];
+    let dx11 = dists[0b111] - dists[0b110];
+    let dx = lerp(lerp(dx00, dx10, t.y), lerp(dx01, dx11, t.y), t.z);
+    let dy = lerp(x10 - x00, x11 - x01, t.z);
+    let dz = y1 - y0;
+
+    (value, Vec3A::new(dx, dy, dz))
+}
@@ -0,0 +1,68 @@
+use crate::SurfaceNetsBuffer;
+
+use rerun::{Arrows3D, Mesh3D, Points3D, RecordingStream, RecordingStreamResult, Transform3D};
+
+/// Logs the contents of `buffer` to a [rerun](https://rerun.io) recording, for visually debugging a chunk's output.
+///
+/// Everything is logged under `entity_path`, which is placed at `translation` (e.g. the chunk's world offset):
+///
+/// - `{entity_path}/mesh`: the triangle mesh, with vertex normals if there are any
+/// - `{entity_path}/normals`: the vertex normals as arrows, only if `log_normals` is true
+/// - `{entity_path}/surface_points`: the array coordinates of every voxel that intersects the isosurface, as a point cloud
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{log_to_rerun, rerun, surface_nets, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = ((x * x + y * y + z * z) as f32).sqrt() - 15.0;
+/// }
+/// let mut buffer = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut buffer);
+///
+/// let rec = rerun::RecordingStreamBuilder::new("surface_nets_debug").buffered()?;
+/// log_to_rerun(&rec, "chunks/0_0_0", &buffer, [0.0; 3], true)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn log_to_rerun(
+    rec: &RecordingStream,
+    entity_path: &str,
+    buffer: &SurfaceNetsBuffer,
+    translation: [f32; 3],
+    log_normals: bool,
+) -> RecordingStreamResult<()> {
+    rec.log(entity_path, &Transform3D::from_translation(translation))?;
+
+    let mut mesh = Mesh3D::new(buffer.positions.iter().copied()).with_triangle_indices(
+        buffer
+            .indices
+            .chunks_exact(3)
+            .map(|tri| [tri[0], tri[1], tri[2]]),
+    );
+    if buffer.normals.len() == buffer.positions.len() {
+        mesh = mesh.with_vertex_normals(buffer.normals.iter().copied());
+    }
+    rec.log(format!("{entity_path}/mesh"), &mesh)?;
+
+    if log_normals && buffer.normals.len() == buffer.positions.len() {
+        rec.log(
+            format!("{entity_path}/normals"),
+            &Arrows3D::from_vectors(buffer.normals.iter().copied())
+                .with_origins(buffer.positions.iter().copied()),
+        )?;
+    }
+
+    rec.log(
+        format!("{entity_path}/surface_points"),
+        &Points3D::new(
+            buffer
+                .surface_points
+                .iter()
+                .map(|&[x, y, z]| [x as f32, y as f32, z as f32]),
+        ),
+    )
+}
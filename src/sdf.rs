@@ -0,0 +1,124 @@
+//! Signed distance functions of common primitives, and combinators for building fields out of them, e.g. for tests and
+//! procedural content. The formulas follow [Inigo Quilez](https://iquilezles.org/articles/distfunctions/).
+//!
+//! Every primitive is centered at the origin (translate the point to move it), and is exact, except where noted.
+//!
+//! ```
+//! use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+//! use fast_surface_nets::{sdf, surface_nets, SurfaceNetsBuffer};
+//!
+//! let inside = [0.1, -0.2, 0.05];
+//! let outside = [3.0, 0.5, -2.0];
+//! let primitives: [&dyn Fn([f32; 3]) -> f32; 5] = [
+//!     &|p| sdf::sphere(p, 1.0),
+//!     &|p| sdf::cuboid(p, [1.0, 0.5, 2.0]),
+//!     &|p| sdf::torus([p[0] - 1.0, p[1], p[2]], 1.0, 0.3),
+//!     &|p| sdf::capsule(p, [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0], 0.5),
+//!     &|p| sdf::plane(p, [0.0, 1.0, 0.0], 0.0),
+//! ];
+//! for primitive in primitives {
+//!     assert!(primitive(inside) < 0.0);
+//!     assert!(primitive(outside) > 0.0);
+//! }
+//!
+//! // Distances are exact.
+//! assert_eq!(sdf::sphere([0.0, 3.0, 4.0], 1.0), 4.0);
+//! assert_eq!(sdf::cuboid([3.0, 0.0, 0.0], [1.0, 1.0, 1.0]), 2.0);
+//! assert_eq!(sdf::cuboid([0.0, 0.0, 0.0], [1.0, 0.5, 2.0]), -0.5);
+//! assert_eq!(sdf::torus([2.0, 0.0, 0.0], 1.0, 0.25), 0.75);
+//! assert_eq!(sdf::capsule([0.0, 2.0, 0.0], [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0], 0.5), 1.5);
+//! assert_eq!(sdf::plane([0.0, 2.0, 0.0], [0.0, 1.0, 0.0], 1.0), 1.0);
+//!
+//! // Build a field from a few primitives and mesh it.
+//! type ChunkShape = ConstShape3u32<18, 18, 18>;
+//! let mut field = [1.0; ChunkShape::USIZE];
+//! for i in 0u32..ChunkShape::SIZE {
+//!     let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32 - 8.5);
+//!     let body = sdf::smooth_union(sdf::sphere([x, y, z], 5.0), sdf::cuboid([x, y + 4.0, z], [6.0, 1.0, 6.0]), 1.0);
+//!     field[i as usize] = sdf::subtraction(body, sdf::capsule([x, y, z], [0.0, -8.0, 0.0], [0.0, 8.0, 0.0], 1.5));
+//! }
+//! let mut mesh = SurfaceNetsBuffer::default();
+//! surface_nets(&field, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+//! assert!(!mesh.indices.is_empty());
+//! ```
+
+use glam::{Vec2, Vec3A};
+
+/// A sphere.
+pub fn sphere(p: [f32; 3], radius: f32) -> f32 {
+    Vec3A::from(p).length() - radius
+}
+
+/// An axis-aligned box, with the given half of its size along each axis.
+pub fn cuboid(p: [f32; 3], half_extents: [f32; 3]) -> f32 {
+    let q = Vec3A::from(p).abs() - Vec3A::from(half_extents);
+    q.max(Vec3A::ZERO).length() + q.max_element().min(0.0)
+}
+
+/// A torus around the Y axis. `major_radius` is the distance from the center to the middle of the tube, and `minor_radius` is
+/// the radius of the tube.
+pub fn torus(p: [f32; 3], major_radius: f32, minor_radius: f32) -> f32 {
+    let [x, y, z] = p;
+    Vec2::new(Vec2::new(x, z).length() - major_radius, y).length() - minor_radius
+}
+
+/// A capsule, i.e. the points within `radius` of the segment from `a` to `b`.
+pub fn capsule(p: [f32; 3], a: [f32; 3], b: [f32; 3], radius: f32) -> f32 {
+    let (pa, ba) = (
+        Vec3A::from(p) - Vec3A::from(a),
+        Vec3A::from(b) - Vec3A::from(a),
+    );
+    let h = (pa.dot(ba) / ba.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    (pa - h * ba).length() - radius
+}
+
+/// The half-space below the plane with the given unit `normal`, offset by `height` along the normal from the origin. Only
+/// exact if `normal` is normalized.
+pub fn plane(p: [f32; 3], normal: [f32; 3], height: f32) -> f32 {
+    Vec3A::from(p).dot(Vec3A::from(normal)) - height
+}
+
+/// The union of two shapes. The distance is only a bound inside of both.
+pub fn union(a: f32, b: f32) -> f32 {
+    a.min(b)
+}
+
+/// The intersection of two shapes. The distance is only a bound outside of both.
+pub fn intersection(a: f32, b: f32) -> f32 {
+    a.max(b)
+}
+
+/// Shape `a` with shape `b` cut out of it. The distance is only a bound.
+pub fn subtraction(a: f32, b: f32) -> f32 {
+    a.max(-b)
+}
+
+/// The union of two shapes, blended together within a distance of about `k`. The result is C1-continuous (as long as `a` and
+/// `b` are), unlike [`union`].
+///
+/// ```
+/// use fast_surface_nets::sdf::{smooth_union, sphere, union};
+///
+/// // Far from the seam, it's the same as a union.
+/// assert_eq!(smooth_union(-1.0, 2.0, 0.5), union(-1.0, 2.0));
+///
+/// // Along a path across the seam between two spheres, the derivative changes smoothly.
+/// let f = |t: f32| {
+///     let p = [t, 0.5, 0.0];
+///     smooth_union(sphere([p[0] + 1.0, p[1], p[2]], 1.2), sphere([p[0] - 1.0, p[1], p[2]], 1.2), 0.5)
+/// };
+/// let h = 1e-3;
+/// let derivative = |t: f32| (f(t + h) - f(t - h)) / (2.0 * h);
+/// for i in -100..100 {
+///     let t = i as f32 * 0.01;
+///     assert!((derivative(t + 0.01) - derivative(t)).abs() < 0.05);
+/// }
+///
+/// // Whereas the derivative of the plain union jumps at the seam.
+/// let g = |t: f32| union(sphere([t + 1.0, 0.5, 0.0], 1.2), sphere([t - 1.0, 0.5, 0.0], 1.2));
+/// assert!(((g(0.01) - g(0.0)) - (g(0.0) - g(-0.01))).abs() / 0.01 > 1.0);
+/// ```
+pub fn smooth_union(a: f32, b: f32, k: f32) -> f32 {
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+    b + h * (a - b) - k * h * (1.0 - h)
+}
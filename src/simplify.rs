@@ -0,0 +1,399 @@
+use crate::topology::{triangle_neighbors, unique_edges, NO_NEIGHBOR};
+use crate::SurfaceNetsBuffer;
+
+use glam::{DVec3, Vec3A};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use std::ops::AddAssign;
+
+/// Decimates the triangles of `mesh` by collapsing edges in order of their quadric error (Garland and Heckbert), until at most
+/// `target_triangles` are left, or collapsing any more edges would move the surface by more than `max_error`. This removes the
+/// over-tessellation of flat and gently curved regions, without a round trip through another mesh library.
+///
+/// The error of a vertex is the root of the summed squared distances to the planes of the original triangles around it. At open
+/// edges, e.g. where a chunk is cut off, planes perpendicular to the triangles keep the border in place. With `lock_boundary`,
+/// the vertices on open edges don't move at all, so the seams of neighboring chunks still match after each is simplified on its
+/// own.
+///
+/// Each collapse moves the remaining vertex to the best of the two ends of the edge and its midpoint, and sums their normals (if
+/// any). Collapses that would fold a triangle over or pinch the surface into a non-manifold shape are skipped.
+///
+/// The index buffer is read as a triangle list, so [`SurfaceNetsConfig::quads`](crate::SurfaceNetsConfig::quads) must be off.
+/// Vertices that are no longer used are left in place, so the per-vertex buffers stay valid, although the remaining vertices
+/// may have left their cubes in `surface_points`. The triangles keep their order, and so do their
+/// [`triangle_materials`](SurfaceNetsBuffer::triangle_materials), while [`face_normals`](SurfaceNetsBuffer::face_normals) and
+/// [`line_indices`](SurfaceNetsBuffer::line_indices) are made again if they're populated.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{simplify, surface_nets, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// let area = |mesh: &SurfaceNetsBuffer| -> f32 {
+///     mesh.triangles()
+///         .map(|[a, b, c]| {
+///             // The signed area, viewed from above.
+///             0.5 * ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]))
+///         })
+///         .sum()
+/// };
+///
+/// // A flat plane, which meshes to a grid of 16x16 quads, collapses to a single square with the same area.
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [_, _, z] = ChunkShape::delinearize(i);
+///     sdf[i as usize] = z as f32 - 8.5;
+/// }
+/// let mut plane = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut plane);
+/// let area_before = area(&plane);
+/// simplify(&mut plane, 0, 1e-3, false);
+/// assert_eq!(plane.indices.len() / 3, 2);
+/// assert!((area(&plane) - area_before).abs() < 1e-2);
+///
+/// // A sphere that is cut open by the chunk, simplified to a quarter of its triangles, with its open border locked.
+/// let sphere = |[x, y, z]: [f32; 3]| ((x - 8.5).powi(2) + (y - 8.5).powi(2) + (z - 14.0).powi(2)).sqrt() - 7.0;
+/// for i in 0u32..ChunkShape::SIZE {
+///     sdf[i as usize] = sphere(ChunkShape::delinearize(i).map(|c| c as f32));
+/// }
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// let before = mesh.clone();
+/// let target = mesh.indices.len() / 3 / 4;
+/// simplify(&mut mesh, target, 1.0, true);
+/// assert!(mesh.indices.len() / 3 <= target);
+/// assert!(mesh.indices.iter().all(|&v| sphere(mesh.positions[v as usize]).abs() < 0.1));
+///
+/// // The open border of the mesh is exactly where it was.
+/// let open_vertices = |mesh: &SurfaceNetsBuffer| {
+///     let mut open = std::collections::BTreeSet::new();
+///     for tri in mesh.indices.chunks(3) {
+///         for e in 0..3 {
+///             let [a, b] = [tri[e], tri[(e + 1) % 3]];
+///             if !mesh.indices.chunks(3).any(|t| (0..3).any(|f| [t[f], t[(f + 1) % 3]] == [b, a])) {
+///                 open.insert((a, mesh.positions[a as usize].map(f32::to_bits)));
+///             }
+///         }
+///     }
+///     open
+/// };
+/// assert!(!open_vertices(&before).is_empty());
+/// assert_eq!(open_vertices(&mesh), open_vertices(&before));
+/// ```
+pub fn simplify(
+    mesh: &mut SurfaceNetsBuffer,
+    target_triangles: usize,
+    max_error: f32,
+    lock_boundary: bool,
+) {
+    let num_vertices = mesh.positions.len();
+    let mut triangles: Vec<[u32; 3]> = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|tri| [tri[0], tri[1], tri[2]])
+        .collect();
+    let mut alive = vec![true; triangles.len()];
+    let mut num_alive = triangles.len();
+
+    let position = |v: u32| mesh.positions[v as usize];
+    let mut quadrics = vec![Quadric::default(); num_vertices];
+    let mut is_boundary = vec![false; num_vertices];
+    let mut vertex_triangles = vec![Vec::new(); num_vertices];
+    for (t, (tri, neighbors)) in triangles
+        .iter()
+        .zip(triangle_neighbors(&mesh.indices))
+        .enumerate()
+    {
+        for &v in tri {
+            vertex_triangles[v as usize].push(t as u32);
+        }
+        let [a, b, c] = tri.map(|v| Vec3A::from(position(v)));
+        let Some(normal) = (b - a).cross(c - a).try_normalize() else {
+            continue;
+        };
+        let plane = Quadric::plane(normal, a);
+        for &v in tri {
+            quadrics[v as usize] += plane;
+        }
+        for e in (0..3).filter(|&e| neighbors[e] == NO_NEIGHBOR) {
+            let [u, w] = [tri[e], tri[(e + 1) % 3]];
+            is_boundary[u as usize] = true;
+            is_boundary[w as usize] = true;
+            let [pu, pw] = [u, w].map(|v| Vec3A::from(position(v)));
+            if let Some(border_normal) = (pw - pu).cross(normal).try_normalize() {
+                let border = Quadric::plane(border_normal, pu);
+                quadrics[u as usize] += border;
+                quadrics[w as usize] += border;
+            }
+        }
+    }
+
+    let mut versions = vec![0u32; num_vertices];
+    let mut heap = BinaryHeap::new();
+    let max_cost = (max_error as f64).powi(2);
+    let push_collapse = |heap: &mut BinaryHeap<Collapse>,
+                         quadrics: &[Quadric],
+                         is_boundary: &[bool],
+                         versions: &[u32],
+                         positions: &[[f32; 3]],
+                         a: u32,
+                         b: u32| {
+        let (keep, remove) = match (is_boundary[a as usize], is_boundary[b as usize]) {
+            (true, true) if lock_boundary => return,
+            (false, true) if lock_boundary => (b, a),
+            _ => (a, b),
+        };
+        let quadric = quadrics[a as usize].sum(&quadrics[b as usize]);
+        let [pa, pb] = [keep, remove].map(|v| Vec3A::from(positions[v as usize]));
+        let candidates: &[Vec3A] = if lock_boundary && is_boundary[keep as usize] {
+            &[pa]
+        } else {
+            &[pa, pb, 0.5 * (pa + pb)]
+        };
+        let (cost, target) = candidates.iter().map(|&p| (quadric.error(p), p)).fold(
+            (f64::INFINITY, pa),
+            |best, c| if c.0 < best.0 { c } else { best },
+        );
+        heap.push(Collapse {
+            cost,
+            keep,
+            remove,
+            target: target.into(),
+            versions: [versions[keep as usize], versions[remove as usize]],
+        });
+    };
+    for [a, b] in unique_edges(&mesh.indices, 3)
+        .chunks_exact(2)
+        .map(|e| [e[0], e[1]])
+    {
+        push_collapse(
+            &mut heap,
+            &quadrics,
+            &is_boundary,
+            &versions,
+            &mesh.positions,
+            a,
+            b,
+        );
+    }
+
+    let has_normals = mesh.normals.len() == num_vertices;
+    while num_alive > target_triangles {
+        let Some(collapse) = heap.pop() else { break };
+        if collapse.cost > max_cost {
+            break;
+        }
+        let Collapse {
+            keep,
+            remove,
+            target,
+            ..
+        } = collapse;
+        if collapse.versions != [versions[keep as usize], versions[remove as usize]] {
+            // One of the ends changed after this collapse was queued.
+            continue;
+        }
+
+        let live = |v: u32| {
+            vertex_triangles[v as usize]
+                .iter()
+                .copied()
+                .filter(|&t| alive[t as usize])
+        };
+        let (shared, moved): (Vec<u32>, Vec<u32>) = live(keep)
+            .chain(live(remove))
+            .collect::<HashSet<u32>>()
+            .into_iter()
+            .partition(|&t| {
+                let tri = triangles[t as usize];
+                tri.contains(&keep) && tri.contains(&remove)
+            });
+        if shared.is_empty() {
+            continue;
+        }
+
+        // The only vertices next to both ends are across the triangles of the edge, or the surface pinches.
+        let neighbors = |v: u32| -> HashSet<u32> {
+            live(v)
+                .flat_map(|t| triangles[t as usize])
+                .filter(|&n| n != keep && n != remove)
+                .collect()
+        };
+        let num_opposite = shared.len();
+        if neighbors(keep).intersection(&neighbors(remove)).count() != num_opposite {
+            continue;
+        }
+
+        // No triangle may fold over.
+        let folds = moved.iter().any(|&t| {
+            let tri = triangles[t as usize];
+            let before = tri.map(|v| Vec3A::from(mesh.positions[v as usize]));
+            let after = tri.map(|v| {
+                if v == keep || v == remove {
+                    Vec3A::from(target)
+                } else {
+                    Vec3A::from(mesh.positions[v as usize])
+                }
+            });
+            let normal = |[a, b, c]: [Vec3A; 3]| (b - a).cross(c - a);
+            normal(after).dot(normal(before)) <= 0.0
+        });
+        if folds {
+            continue;
+        }
+
+        for &t in shared.iter() {
+            alive[t as usize] = false;
+            num_alive -= 1;
+        }
+        for &t in moved.iter() {
+            let tri = &mut triangles[t as usize];
+            if let Some(v) = tri.iter_mut().find(|v| **v == remove) {
+                *v = keep;
+                vertex_triangles[keep as usize].push(t);
+            }
+        }
+        vertex_triangles[keep as usize].retain(|&t| alive[t as usize]);
+        vertex_triangles[remove as usize].clear();
+        mesh.positions[keep as usize] = target;
+        if has_normals {
+            let normal = Vec3A::from(mesh.normals[keep as usize])
+                + Vec3A::from(mesh.normals[remove as usize]);
+            mesh.normals[keep as usize] = normal.into();
+        }
+        quadrics[keep as usize] = quadrics[keep as usize].sum(&quadrics[remove as usize]);
+        is_boundary[keep as usize] |= is_boundary[remove as usize];
+        versions[keep as usize] += 1;
+        versions[remove as usize] += 1;
+
+        let mut next: Vec<u32> = vertex_triangles[keep as usize]
+            .iter()
+            .flat_map(|&t| triangles[t as usize])
+            .filter(|&v| v != keep)
+            .collect();
+        next.sort_unstable();
+        next.dedup();
+        for v in next {
+            push_collapse(
+                &mut heap,
+                &quadrics,
+                &is_boundary,
+                &versions,
+                &mesh.positions,
+                keep,
+                v,
+            );
+        }
+    }
+
+    if mesh.triangle_materials.len() == triangles.len() {
+        let mut t = 0;
+        mesh.triangle_materials.retain(|_| {
+            t += 1;
+            alive[t - 1]
+        });
+    }
+    mesh.indices = triangles
+        .iter()
+        .zip(alive.iter())
+        .filter(|(_, &alive)| alive)
+        .flat_map(|(tri, _)| *tri)
+        .collect();
+    mesh.boundary_indices_start = None;
+    if !mesh.face_normals.is_empty() {
+        mesh.make_face_normals();
+    }
+    if !mesh.line_indices.is_empty() {
+        mesh.line_indices = unique_edges(&mesh.indices, 3);
+    }
+}
+
+// A candidate edge collapse, ordered so the cheapest comes out of a `BinaryHeap` first. It's stale unless the versions of its
+// vertices still match.
+struct Collapse {
+    cost: f64,
+    keep: u32,
+    remove: u32,
+    target: [f32; 3],
+    versions: [u32; 2],
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| [other.keep, other.remove].cmp(&[self.keep, self.remove]))
+    }
+}
+
+// The sum of squared distances to a set of planes, as the symmetric 4x4 matrix `[A b; b^T c]` of `p^T A p + 2 b^T p + c`, in
+// double precision since the errors of large quadrics are small differences.
+#[derive(Clone, Copy, Default)]
+struct Quadric {
+    a: [f64; 6],
+    b: DVec3,
+    c: f64,
+}
+
+impl Quadric {
+    // The squared distance to the plane through `point` with unit `normal`.
+    fn plane(normal: Vec3A, point: Vec3A) -> Self {
+        let n = normal.as_dvec3();
+        let d = -n.dot(point.as_dvec3());
+        Self {
+            a: [
+                n.x * n.x,
+                n.x * n.y,
+                n.x * n.z,
+                n.y * n.y,
+                n.y * n.z,
+                n.z * n.z,
+            ],
+            b: n * d,
+            c: d * d,
+        }
+    }
+
+    fn sum(&self, other: &Self) -> Self {
+        let mut sum = *self;
+        sum += *other;
+        sum
+    }
+
+    fn error(&self, p: Vec3A) -> f64 {
+        let p = p.as_dvec3();
+        let [xx, xy, xz, yy, yz, zz] = self.a;
+        let ap = DVec3::new(
+            xx * p.x + xy * p.y + xz * p.z,
+            xy * p.x + yy * p.y + yz * p.z,
+            xz * p.x + yz * p.y + zz * p.z,
+        );
+        (p.dot(ap) + 2.0 * self.b.dot(p) + self.c).max(0.0)
+    }
+}
+
+impl AddAssign for Quadric {
+    fn add_assign(&mut self, other: Self) {
+        for (a, other_a) in self.a.iter_mut().zip(other.a) {
+            *a += other_a;
+        }
+        self.b += other.b;
+        self.c += other.c;
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{surface_nets_with_config, SignedDistance, SurfaceNetsBuffer, SurfaceNetsConfig};
+
+use ndshape::Shape;
+use smallvec::SmallVec;
+
+/// The number of vertices that a [`SmallSurfaceNetsBuffer`] stores inline. This fits a small sphere in an 8³ chunk.
+pub const SMALL_BUFFER_VERTICES: usize = 128;
+
+/// The number of indices that a [`SmallSurfaceNetsBuffer`] stores inline.
+pub const SMALL_BUFFER_INDICES: usize = 6 * SMALL_BUFFER_VERTICES;
+
+/// The mesh of a tiny chunk (around 4³ to 8³ samples), stored inline so that keeping many of them around doesn't cost a heap
+/// allocation each. Meshes with more than [`SMALL_BUFFER_VERTICES`] vertices or [`SMALL_BUFFER_INDICES`] indices spill to the
+/// heap, just like a `Vec`.
+///
+/// Only the final mesh is stored. See [`surface_nets_small`].
+#[derive(Clone, Debug, Default)]
+pub struct SmallSurfaceNetsBuffer {
+    /// The triangle mesh positions, like [`SurfaceNetsBuffer::positions`].
+    pub positions: SmallVec<[[f32; 3]; SMALL_BUFFER_VERTICES]>,
+    /// The triangle mesh normals, like [`SurfaceNetsBuffer::normals`].
+    pub normals: SmallVec<[[f32; 3]; SMALL_BUFFER_VERTICES]>,
+    /// The triangle mesh indices, like [`SurfaceNetsBuffer::indices`].
+    pub indices: SmallVec<[u32; SMALL_BUFFER_INDICES]>,
+}
+
+impl SmallSurfaceNetsBuffer {
+    /// Whether any of the buffers spilled to the heap.
+    pub fn spilled(&self) -> bool {
+        self.positions.spilled() || self.normals.spilled() || self.indices.spilled()
+    }
+}
+
+impl From<&SurfaceNetsBuffer> for SmallSurfaceNetsBuffer {
+    fn from(buffer: &SurfaceNetsBuffer) -> Self {
+        Self {
+            positions: SmallVec::from_slice(&buffer.positions),
+            normals: SmallVec::from_slice(&buffer.normals),
+            indices: SmallVec::from_slice(&buffer.indices),
+        }
+    }
+}
+
+/// Like [`surface_nets_with_config`], but returns the mesh in a [`SmallSurfaceNetsBuffer`], for meshing huge numbers of tiny
+/// chunks whose meshes are kept around.
+///
+/// Meshing runs in `scratch`, which should be reused across calls, so its allocations are amortized. The result is then copied
+/// into the inline storage of the returned buffer, which only allocates if it spills.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{sdf, surface_nets, surface_nets_small, SurfaceNetsBuffer, SurfaceNetsConfig};
+///
+/// fn sphere<S: ConstShape<3, Coord = u32>>(center: f32, radius: f32) -> Vec<f32> {
+///     (0..S::SIZE).map(|i| sdf::sphere(S::delinearize(i).map(|c| c as f32 - center), radius)).collect()
+/// }
+///
+/// let mut scratch = SurfaceNetsBuffer::default();
+///
+/// // A tiny chunk's mesh is stored inline.
+/// type TinyShape = ConstShape3u32<8, 8, 8>;
+/// let tiny = sphere::<TinyShape>(3.5, 2.5);
+/// let small = surface_nets_small(&tiny, &TinyShape {}, [0; 3], [7; 3], SurfaceNetsConfig::default(), &mut scratch);
+/// assert!(!small.indices.is_empty());
+/// assert!(!small.spilled());
+///
+/// // A bigger one spills to the heap, but is still the same mesh.
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+/// let big = sphere::<ChunkShape>(8.5, 6.0);
+/// let spilled = surface_nets_small(&big, &ChunkShape {}, [0; 3], [17; 3], SurfaceNetsConfig::default(), &mut scratch);
+/// assert!(spilled.spilled());
+///
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&big, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+/// assert_eq!(&spilled.positions[..], &mesh.positions[..]);
+/// assert_eq!(&spilled.normals[..], &mesh.normals[..]);
+/// assert_eq!(&spilled.indices[..], &mesh.indices[..]);
+/// ```
+pub fn surface_nets_small<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+    scratch: &mut SurfaceNetsBuffer,
+) -> SmallSurfaceNetsBuffer
+where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    surface_nets_with_config(sdf, shape, min, max, config, scratch);
+    SmallSurfaceNetsBuffer::from(&*scratch)
+}
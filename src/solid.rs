@@ -0,0 +1,111 @@
+use crate::{SignedDistance, SurfaceNetsBuffer};
+
+use ndshape::Shape;
+
+/// Detects a fully interior chunk, i.e. one whose samples in the extent from `min` to `max` are all negative, and closes it off
+/// from its empty neighbors. Surface Nets alone produces nothing for such a chunk, which leaves a hole in a chunked volume
+/// wherever it borders a chunk with nothing in it.
+///
+/// `neighbor_signs` summarizes the neighbor across each boundary plane, in the order min X, max X, min Y, max Y, min Z, max Z:
+/// `Some(true)` if it's solid, `Some(false)` if it's empty, and `None` if it has a surface of its own (or there's no
+/// neighbor), which meshes that boundary. Each plane with an empty neighbor gets a single quad covering the extent, facing the
+/// neighbor, with 4 vertices of its own and flat unit normals.
+///
+/// Returns `true` if the chunk is fully interior, in which case `output` holds the caps (if any). Otherwise `output` is left
+/// empty, and the chunk should be meshed as usual.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{cap_solid_boundaries, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+///
+/// // A solid chunk, with an empty chunk at +X and solid chunks everywhere else.
+/// let solid = [-1.0; ChunkShape::USIZE];
+/// let neighbor_signs = [Some(true), Some(false), Some(true), Some(true), Some(true), Some(true)];
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// assert!(cap_solid_boundaries(&solid, &ChunkShape {}, [0; 3], [17; 3], neighbor_signs, &mut mesh));
+///
+/// // The only faces are the wall between the two chunks, which faces the empty one.
+/// assert_eq!(mesh.indices.len(), 6);
+/// assert!(mesh.positions.iter().all(|p| p[0] == 17.0));
+/// assert!(mesh.normals.iter().all(|&n| n == [1.0, 0.0, 0.0]));
+/// let area: f32 = mesh
+///     .indices
+///     .chunks(3)
+///     .map(|tri| {
+///         let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[tri[i] as usize]);
+///         // The signed area, viewed from +X.
+///         0.5 * ((b[1] - a[1]) * (c[2] - a[2]) - (c[1] - a[1]) * (b[2] - a[2]))
+///     })
+///     .sum();
+/// assert_eq!(area, 17.0 * 17.0);
+///
+/// // A chunk with a surface in it isn't capped.
+/// let mut surface = solid;
+/// surface[0] = 1.0;
+/// assert!(!cap_solid_boundaries(&surface, &ChunkShape {}, [0; 3], [17; 3], neighbor_signs, &mut mesh));
+/// assert!(mesh.positions.is_empty() && mesh.indices.is_empty());
+/// ```
+pub fn cap_solid_boundaries<T, S>(
+    sdf: &[T],
+    shape: &S,
+    min: [u32; 3],
+    max: [u32; 3],
+    neighbor_signs: [Option<bool>; 6],
+    output: &mut SurfaceNetsBuffer,
+) -> bool
+where
+    T: SignedDistance,
+    S: Shape<3, Coord = u32>,
+{
+    assert!((shape.linearize(max) as usize) < sdf.len());
+
+    // The caps don't belong to any cube.
+    output.reset(0);
+
+    let is_solid = (min[2]..=max[2]).all(|z| {
+        (min[1]..=max[1]).all(|y| {
+            (min[0]..=max[0]).all(|x| sdf[shape.linearize([x, y, z]) as usize].is_negative())
+        })
+    });
+    if !is_solid {
+        return false;
+    }
+
+    for (face, &neighbor_sign) in neighbor_signs.iter().enumerate() {
+        if neighbor_sign != Some(false) {
+            continue;
+        }
+        let (axis, is_max) = (face / 2, face % 2 == 1);
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        // Counterclockwise around +axis, which is reversed on the min plane so the cap faces the neighbor.
+        let mut corners = [
+            [min[u], min[v]],
+            [max[u], min[v]],
+            [max[u], max[v]],
+            [min[u], max[v]],
+        ];
+        if !is_max {
+            corners.reverse();
+        }
+        let mut normal = [0.0; 3];
+        normal[axis] = if is_max { 1.0 } else { -1.0 };
+
+        let first = output.positions.len() as u32;
+        for [cu, cv] in corners {
+            let mut p = [0.0; 3];
+            p[axis] = if is_max { max[axis] } else { min[axis] } as f32;
+            p[u] = cu as f32;
+            p[v] = cv as f32;
+            output.positions.push(p);
+            output.normals.push(normal);
+        }
+        output
+            .indices
+            .extend_from_slice(&[0, 1, 2, 0, 2, 3].map(|i| first + i));
+    }
+    output.update_aabb();
+    true
+}
@@ -0,0 +1,277 @@
+use crate::SurfaceNetsBuffer;
+
+use std::collections::{HashMap, HashSet};
+
+impl SurfaceNetsBuffer {
+    /// Returns a line list containing every unique edge of the triangle mesh exactly once, e.g. for wireframe rendering.
+    ///
+    /// Edges are deduplicated by sorting, so this is `O(T log T)` for `T` triangles.
+    ///
+    /// ```
+    /// use fast_surface_nets::SurfaceNetsBuffer;
+    ///
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// mesh.indices = vec![0, 1, 2];
+    /// assert_eq!(mesh.wireframe_indices().len(), 6);
+    ///
+    /// // Two triangles sharing the edge (1, 2).
+    /// mesh.indices = vec![0, 1, 2, 2, 1, 3];
+    /// assert_eq!(mesh.wireframe_indices().len(), 10);
+    /// ```
+    pub fn wireframe_indices(&self) -> Vec<u32> {
+        unique_edges(&self.indices, 3)
+    }
+
+    /// Returns the Euler characteristic `V - E + F` of the triangle mesh, e.g. for topological validation. Only the vertices
+    /// used by a triangle are counted, and every unique edge is counted once.
+    ///
+    /// A closed surface has `2 - 2 * genus` per connected component, so a sphere has 2 and a torus has 0.
+    ///
+    /// ```
+    /// use fast_surface_nets::SurfaceNetsBuffer;
+    ///
+    /// // A tetrahedron.
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// mesh.positions = vec![[0.0; 3]; 4];
+    /// mesh.indices = vec![0, 1, 2, 0, 3, 1, 1, 3, 2, 2, 3, 0];
+    /// assert_eq!(mesh.euler_characteristic(), 4 - 6 + 4);
+    /// ```
+    pub fn euler_characteristic(&self) -> i64 {
+        let mut edges = sorted_triangle_edges(&self.indices);
+        edges.dedup();
+        let mut used = vec![false; self.positions.len()];
+        for &v in self.indices.iter() {
+            used[v as usize] = true;
+        }
+        let num_vertices = used.iter().filter(|&&used| used).count();
+        num_vertices as i64 - edges.len() as i64 + (self.indices.len() / 3) as i64
+    }
+
+    /// Returns the genus (the number of handles) of the triangle mesh, summed over its connected components, or `None` unless
+    /// it's closed, i.e. every edge is shared by exactly two triangles.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{sdf, surface_nets, SurfaceNetsBuffer};
+    ///
+    /// type ChunkShape = ConstShape3u32<26, 26, 26>;
+    ///
+    /// let mesh = |field: &dyn Fn([f32; 3]) -> f32| {
+    ///     let mut samples = [1.0; ChunkShape::USIZE];
+    ///     for i in 0u32..ChunkShape::SIZE {
+    ///         samples[i as usize] = field(ChunkShape::delinearize(i).map(|c| c as f32 - 12.5));
+    ///     }
+    ///     let mut mesh = SurfaceNetsBuffer::default();
+    ///     surface_nets(&samples, &ChunkShape {}, [0; 3], [25; 3], &mut mesh);
+    ///     mesh
+    /// };
+    ///
+    /// let sphere = mesh(&|p| sdf::sphere(p, 8.0));
+    /// assert_eq!(sphere.euler_characteristic(), 2);
+    /// assert_eq!(sphere.genus(), Some(0));
+    ///
+    /// let torus = mesh(&|p| sdf::torus(p, 7.0, 3.0));
+    /// assert_eq!(torus.euler_characteristic(), 0);
+    /// assert_eq!(torus.genus(), Some(1));
+    ///
+    /// // Two separate tori.
+    /// let tori = mesh(&|[x, y, z]| {
+    ///     sdf::union(sdf::torus([x, y - 5.0, z], 7.0, 2.0), sdf::torus([x, y + 5.0, z], 7.0, 2.0))
+    /// });
+    /// assert_eq!(tori.euler_characteristic(), 0);
+    /// assert_eq!(tori.genus(), Some(2));
+    ///
+    /// // A sphere cut open by the boundary of the chunk isn't closed.
+    /// let open = mesh(&|[x, y, z]| sdf::sphere([x - 10.0, y, z], 8.0));
+    /// assert_eq!(open.genus(), None);
+    /// ```
+    pub fn genus(&self) -> Option<i64> {
+        let edges = sorted_triangle_edges(&self.indices);
+        if edges.is_empty() || edges.chunk_by(|a, b| a == b).any(|run| run.len() != 2) {
+            return None;
+        }
+
+        // Count the connected components.
+        let mut roots: Vec<u32> = (0..self.positions.len() as u32).collect();
+        fn find(roots: &mut [u32], mut v: u32) -> u32 {
+            while roots[v as usize] != v {
+                roots[v as usize] = roots[roots[v as usize] as usize];
+                v = roots[v as usize];
+            }
+            v
+        }
+        for &[a, b] in edges.iter().step_by(2) {
+            let (a, b) = (find(&mut roots, a), find(&mut roots, b));
+            roots[a.max(b) as usize] = a.min(b);
+        }
+        let num_components = self
+            .indices
+            .iter()
+            .filter(|&&v| find(&mut roots, v) == v)
+            .collect::<HashSet<_>>()
+            .len() as i64;
+
+        Some(num_components - self.euler_characteristic() / 2)
+    }
+
+    /// Returns the valence (the number of incident edges) of every vertex, for diagnosing mesh quality.
+    ///
+    /// The index buffer is read as a list of quads, each split into 2 triangles, which is how
+    /// [`surface_nets`](crate::surface_nets) lays out its output. The diagonal that splits a quad is not counted, so an ideal
+    /// Surface Nets mesh has valence 4 everywhere, and high-valence vertices point at problematic cube configurations.
+    /// Vertices on an open boundary have lower valence, which is expected. A trailing triangle that isn't part of a quad has
+    /// all 3 of its edges counted.
+    ///
+    /// Valences saturate at `u8::MAX`.
+    ///
+    /// ```
+    /// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+    /// use fast_surface_nets::{surface_nets, SurfaceNetsBuffer};
+    ///
+    /// type ChunkShape = ConstShape3u32<18, 18, 18>;
+    ///
+    /// // A flat plane, which meshes to a grid of 17x17 vertices.
+    /// let mut sdf = [1.0; ChunkShape::USIZE];
+    /// for i in 0u32..ChunkShape::SIZE {
+    ///     let [_, _, z] = ChunkShape::delinearize(i);
+    ///     sdf[i as usize] = z as f32 - 8.5;
+    /// }
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+    ///
+    /// let valences = mesh.vertex_valences();
+    /// assert_eq!(valences.len(), mesh.positions.len());
+    /// for (&valence, &[x, y, _]) in valences.iter().zip(mesh.surface_points.iter()) {
+    ///     let num_boundary_axes = [x, y].iter().filter(|&&c| c == 0 || c == 16).count();
+    ///     assert_eq!(valence as usize, 4 - num_boundary_axes);
+    /// }
+    /// ```
+    pub fn vertex_valences(&self) -> Vec<u8> {
+        let mut edges = Vec::with_capacity(self.indices.len());
+        let mut quads = self.indices.chunks_exact(6);
+        for quad in &mut quads {
+            let tri_edges = |tri: &[u32]| [0, 1, 2].map(|e| edge_key(tri[e], tri[(e + 1) % 3]));
+            let (edges1, edges2) = (tri_edges(&quad[..3]), tri_edges(&quad[3..]));
+            // Skip the diagonal, which is the only edge shared by both triangles.
+            edges.extend(edges1.iter().filter(|e| !edges2.contains(e)));
+            edges.extend(edges2.iter().filter(|e| !edges1.contains(e)));
+        }
+        for tri in quads.remainder().chunks_exact(3) {
+            edges.extend([0, 1, 2].map(|e| edge_key(tri[e], tri[(e + 1) % 3])));
+        }
+        edges.sort_unstable();
+        edges.dedup();
+
+        let mut valences = vec![0u8; self.positions.len()];
+        for v in edges.into_iter().flatten() {
+            valences[v as usize] = valences[v as usize].saturating_add(1);
+        }
+        valences
+    }
+
+    /// Returns an index buffer in the triangles-with-adjacency layout (e.g. `GL_TRIANGLES_ADJACENCY`) for geometry shaders.
+    ///
+    /// Every triangle `[v0, v1, v2]` becomes 6 indices `[v0, a01, v1, a12, v2, a20]`, where `aij` is the vertex opposite of the
+    /// edge `(vi, vj)` in the neighboring triangle across that edge.
+    ///
+    /// Boundary edges have no neighboring triangle, so their adjacent vertex falls back to the triangle's *own* opposite vertex,
+    /// e.g. `a01 = v2`. This is always a valid index, and shaders can detect boundary edges by checking for it.
+    ///
+    /// ```
+    /// use fast_surface_nets::SurfaceNetsBuffer;
+    ///
+    /// // Two triangles sharing the edge (1, 2).
+    /// let mut mesh = SurfaceNetsBuffer::default();
+    /// mesh.indices = vec![0, 1, 2, 2, 1, 3];
+    /// assert_eq!(
+    ///     mesh.adjacency_indices(),
+    ///     vec![
+    ///         0, 2, 1, 3, 2, 1, // The edge (1, 2) is opposite of vertex 3.
+    ///         2, 0, 1, 2, 3, 1, // The edge (2, 1) is opposite of vertex 0.
+    ///     ]
+    /// );
+    /// ```
+    pub fn adjacency_indices(&self) -> Vec<u32> {
+        let neighbors = triangle_neighbors(&self.indices);
+        let mut adjacency = Vec::with_capacity(2 * self.indices.len());
+        for (tri, tri_neighbors) in self.indices.chunks_exact(3).zip(neighbors.iter()) {
+            for e in 0..3 {
+                let [a, b] = [tri[e], tri[(e + 1) % 3]];
+                let own_opposite = tri[(e + 2) % 3];
+                let opposite = match tri_neighbors[e] {
+                    NO_NEIGHBOR => own_opposite,
+                    n => {
+                        let n = n as usize;
+                        self.indices[3 * n..3 * n + 3]
+                            .iter()
+                            .copied()
+                            .find(|&v| v != a && v != b)
+                            .unwrap_or(own_opposite)
+                    }
+                };
+                adjacency.push(a);
+                adjacency.push(opposite);
+            }
+        }
+        adjacency
+    }
+}
+
+/// Marks a triangle edge that isn't shared with any other triangle.
+pub(crate) const NO_NEIGHBOR: u32 = u32::MAX;
+
+/// For every triangle in `indices`, finds the triangle across each of its three edges. Edge `i` of a triangle connects its
+/// corners `i` and `(i + 1) % 3`.
+///
+/// Edges without a neighbor get [`NO_NEIGHBOR`]. If more than two triangles share an edge (non-manifold), only the first two
+/// are linked.
+pub(crate) fn triangle_neighbors(indices: &[u32]) -> Vec<[u32; 3]> {
+    let num_triangles = indices.len() / 3;
+    let mut neighbors = vec![[NO_NEIGHBOR; 3]; num_triangles];
+    let mut open_edges = HashMap::<[u32; 2], (u32, usize)>::with_capacity(indices.len());
+    for (t, tri) in indices.chunks_exact(3).enumerate() {
+        for e in 0..3 {
+            let key = edge_key(tri[e], tri[(e + 1) % 3]);
+            match open_edges.get(&key) {
+                Some(&(other_t, other_e))
+                    if neighbors[other_t as usize][other_e] == NO_NEIGHBOR =>
+                {
+                    neighbors[other_t as usize][other_e] = t as u32;
+                    neighbors[t][e] = other_t;
+                }
+                Some(_) => {}
+                None => {
+                    open_edges.insert(key, (t as u32, e));
+                }
+            }
+        }
+    }
+    neighbors
+}
+
+// The keys of the edges of every triangle, sorted, so an edge shared by several triangles appears once per triangle.
+fn sorted_triangle_edges(indices: &[u32]) -> Vec<[u32; 2]> {
+    sorted_polygon_edges(indices, 3)
+}
+
+// Like `sorted_triangle_edges`, for polygons with `sides` indices each, e.g. quads.
+fn sorted_polygon_edges(indices: &[u32], sides: usize) -> Vec<[u32; 2]> {
+    let mut edges: Vec<[u32; 2]> = indices
+        .chunks_exact(sides)
+        .flat_map(|polygon| (0..sides).map(move |e| edge_key(polygon[e], polygon[(e + 1) % sides])))
+        .collect();
+    edges.sort_unstable();
+    edges
+}
+
+/// A line list of every unique edge of the polygons in `indices`, which have `sides` indices each.
+pub(crate) fn unique_edges(indices: &[u32], sides: usize) -> Vec<u32> {
+    let mut edges = sorted_polygon_edges(indices, sides);
+    edges.dedup();
+    edges.into_iter().flatten().collect()
+}
+
+/// An order-independent key for the edge between vertices `a` and `b`.
+pub(crate) fn edge_key(a: u32, b: u32) -> [u32; 2] {
+    [a.min(b), a.max(b)]
+}
@@ -0,0 +1,342 @@
+use crate::SurfaceNetsBuffer;
+
+use glam::Vec3A;
+use std::collections::{HashMap, HashSet};
+
+/// Merges vertices of `mesh` whose positions fall into the same cell of a quantization grid with cell size `precision`.
+///
+/// Ties are broken deterministically: every group of merged vertices is represented by its member with the lowest original
+/// index, which provides the welded position, and the welded vertices keep the relative order of their representatives. The
+/// welded normal is the sum of the group's normals (accumulated in original index order), normalized afterward. This makes the
+/// output independent of how the work is scheduled, so [`par_weld_vertices`] produces exactly the same mesh.
+///
+/// Only `positions`, `normals` and `indices` are filled in the returned buffer (`normals` only if `mesh` has them). The
+/// per-voxel fields no longer make sense once vertices have been merged, so they are left empty.
+pub fn weld_vertices(mesh: &SurfaceNetsBuffer, precision: f32) -> SurfaceNetsBuffer {
+    let mut welded = SurfaceNetsBuffer::default();
+    let mut key_to_index = HashMap::<[i32; 3], u32>::with_capacity(mesh.positions.len());
+    let mut remap = Vec::with_capacity(mesh.positions.len());
+    let mut normal_sums = Vec::new();
+    for (i, &position) in mesh.positions.iter().enumerate() {
+        let new_index = *key_to_index
+            .entry(quantize(position, precision))
+            .or_insert_with(|| {
+                welded.positions.push(position);
+                normal_sums.push(Vec3A::ZERO);
+                welded.positions.len() as u32 - 1
+            });
+        if let Some(&normal) = mesh.normals.get(i) {
+            normal_sums[new_index as usize] += Vec3A::from(normal);
+        }
+        remap.push(new_index);
+    }
+
+    if mesh.normals.len() == mesh.positions.len() {
+        welded.normals = normal_sums
+            .into_iter()
+            .map(|n| n.normalize_or_zero().into())
+            .collect();
+    }
+    welded.indices = mesh.indices.iter().map(|&i| remap[i as usize]).collect();
+    welded
+}
+
+/// Merges the meshes of several chunks into one, moving each of them by its offset in `offsets` (e.g. from chunk-local
+/// coordinates into world space), and welds their vertices like [`weld_vertices`] with the given `precision`.
+///
+/// Neighboring chunks overlap by one sample, so the cubes on their shared boundary are meshed by both, and their vertices
+/// coincide once moved into place. Welding them closes the seams, and their normals are averaged. Other vertices are only
+/// merged if they're within `precision` of each other, so a `precision` far below the voxel size (e.g. `0.001`) keeps the
+/// interior of every chunk as it is.
+///
+/// Panics if `buffers` and `offsets` have different lengths.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{surface_nets, weld_chunks, weld_vertices, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+/// type WorldShape = ConstShape3u32<34, 18, 18>;
+///
+/// let sphere = |[x, y, z]: [f32; 3]| ((x - 16.3).powi(2) + (y - 8.5).powi(2) + (z - 8.5).powi(2)).sqrt() - 6.0;
+///
+/// // The sphere, split across two chunks along X. They overlap by one sample.
+/// let offsets = [[0.0; 3], [16.0, 0.0, 0.0]];
+/// let chunks = offsets.map(|offset| {
+///     let mut sdf = [1.0; ChunkShape::USIZE];
+///     for i in 0u32..ChunkShape::SIZE {
+///         let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+///         sdf[i as usize] = sphere([x + offset[0], y + offset[1], z + offset[2]]);
+///     }
+///     let mut mesh = SurfaceNetsBuffer::default();
+///     surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut mesh);
+///     mesh
+/// });
+/// let welded = weld_chunks(&[&chunks[0], &chunks[1]], &offsets, 0.001);
+///
+/// // Each surface cube on the seam was meshed by both chunks, and is welded into a single vertex.
+/// let num_seam_cubes = chunks[0].surface_points.iter().filter(|p| p[0] == 16).count();
+/// assert!(num_seam_cubes > 0);
+/// assert_eq!(chunks[1].surface_points.iter().filter(|p| p[0] == 0).count(), num_seam_cubes);
+/// assert_eq!(welded.positions.len(), chunks[0].positions.len() + chunks[1].positions.len() - num_seam_cubes);
+/// assert_eq!(welded.indices.len(), chunks[0].indices.len() + chunks[1].indices.len());
+///
+/// // So it has the same vertices as the whole sphere meshed in one chunk (up to rounding).
+/// let mut sdf = [1.0; WorldShape::USIZE];
+/// for i in 0u32..WorldShape::SIZE {
+///     sdf[i as usize] = sphere(WorldShape::delinearize(i).map(|c| c as f32));
+/// }
+/// let mut whole = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &WorldShape {}, [0; 3], [33, 17, 17], &mut whole);
+/// let whole = weld_vertices(&whole, 0.001);
+/// assert_eq!(welded.positions.len(), whole.positions.len());
+/// for p in welded.positions.iter() {
+///     assert!(whole.positions.iter().any(|q| (0..3).all(|i| (p[i] - q[i]).abs() < 1e-4)));
+/// }
+/// ```
+pub fn weld_chunks(
+    buffers: &[&SurfaceNetsBuffer],
+    offsets: &[[f32; 3]],
+    precision: f32,
+) -> SurfaceNetsBuffer {
+    assert_eq!(
+        buffers.len(),
+        offsets.len(),
+        "every chunk needs its own offset"
+    );
+
+    let mut merged = SurfaceNetsBuffer::default();
+    let has_normals = buffers
+        .iter()
+        .all(|mesh| mesh.normals.len() == mesh.positions.len());
+    for (mesh, &offset) in buffers.iter().zip(offsets.iter()) {
+        let first = merged.positions.len() as u32;
+        merged.positions.extend(
+            mesh.positions
+                .iter()
+                .map(|p| [0, 1, 2].map(|i| p[i] + offset[i])),
+        );
+        if has_normals {
+            merged.normals.extend_from_slice(&mesh.normals);
+        }
+        merged
+            .indices
+            .extend(mesh.indices.iter().map(|&i| first + i));
+    }
+    weld_vertices(&merged, precision)
+}
+
+/// Merges the meshes of several chunks into one like [`weld_chunks`], including their boundary faces, and cleans up what's
+/// left where they meet, e.g. for a physics engine that needs a single mesh without duplicate faces.
+///
+/// To close a group of chunks with boundary faces, mesh each chunk with [`BoundaryPlanes::Selected`](crate::BoundaryPlanes),
+/// leaving open the planes it shares with other chunks of the group. Then the caps of neighboring chunks meet where the caps
+/// of a single big chunk would be. Any face that ends up in the merged mesh more than once (with the same winding) is only
+/// kept once, the triangles that collapsed while welding are removed, and so are the vertices that no triangle uses anymore.
+/// The boundary faces are taken from `indices` and `boundary_indices`, so it doesn't matter whether they were separated.
+///
+/// Panics if `buffers` and `offsets` have different lengths.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{
+///     stitch_chunks, surface_nets_with_config, BoundaryPlanes, SurfaceNetsBuffer, SurfaceNetsConfig,
+/// };
+/// use std::collections::HashSet;
+///
+/// type ChunkShape = ConstShape3u32<18, 18, 18>;
+/// type WorldShape = ConstShape3u32<34, 18, 18>;
+///
+/// // A sphere that is too big for two chunks along X, so it's capped on every side but the one they share.
+/// let sphere = |[x, y, z]: [f32; 3]| ((x - 16.3).powi(2) + (y - 8.5).powi(2) + (z - 8.5).powi(2)).sqrt() - 10.0;
+/// let capped = |planes| SurfaceNetsConfig {
+///     generate_boundary_faces: true,
+///     boundary_planes: BoundaryPlanes::Selected(planes),
+///     ..Default::default()
+/// };
+/// let offsets = [[0.0; 3], [16.0, 0.0, 0.0]];
+/// let shared_faces = [1, 0];
+/// let chunks = [0, 1].map(|c| {
+///     let mut sdf = [1.0; ChunkShape::USIZE];
+///     for i in 0u32..ChunkShape::SIZE {
+///         let [x, y, z] = ChunkShape::delinearize(i).map(|c| c as f32);
+///         sdf[i as usize] = sphere([x + offsets[c][0], y + offsets[c][1], z + offsets[c][2]]);
+///     }
+///     let mut planes = [true; 6];
+///     planes[shared_faces[c]] = false;
+///     let mut mesh = SurfaceNetsBuffer::default();
+///     surface_nets_with_config(&sdf, &ChunkShape {}, [0; 3], [17; 3], capped(planes), &mut mesh);
+///     mesh
+/// });
+/// let stitched = stitch_chunks(&[&chunks[0], &chunks[1]], &offsets, 0.001);
+///
+/// // The same sphere, capped in a single chunk.
+/// let mut sdf = [1.0; WorldShape::USIZE];
+/// for i in 0u32..WorldShape::SIZE {
+///     sdf[i as usize] = sphere(WorldShape::delinearize(i).map(|c| c as f32));
+/// }
+/// let mut whole = SurfaceNetsBuffer::default();
+/// surface_nets_with_config(&sdf, &WorldShape {}, [0; 3], [33, 17, 17], capped([true; 6]), &mut whole);
+///
+/// // The stitched mesh has the same vertices and faces, each just once.
+/// let triangles = |mesh: &SurfaceNetsBuffer| {
+///     let corner = |v: u32| mesh.positions[v as usize].map(|c| (c * 1000.0).round() as i32);
+///     let rotate_to_first_corner = |tri: &[u32]| {
+///         let first = (0..3).min_by_key(|&i| corner(tri[i])).unwrap();
+///         [0, 1, 2].map(|i| corner(tri[(first + i) % 3]))
+///     };
+///     mesh.indices.chunks(3).map(rotate_to_first_corner).collect::<Vec<_>>()
+/// };
+/// let stitched_triangles = triangles(&stitched);
+/// let unique_triangles: HashSet<_> = stitched_triangles.iter().copied().collect();
+/// assert_eq!(unique_triangles.len(), stitched_triangles.len());
+/// assert_eq!(unique_triangles, triangles(&whole).into_iter().collect());
+/// assert_eq!(stitched.positions.len(), whole.positions.len());
+/// ```
+pub fn stitch_chunks(
+    buffers: &[&SurfaceNetsBuffer],
+    offsets: &[[f32; 3]],
+    precision: f32,
+) -> SurfaceNetsBuffer {
+    let with_boundary_faces: Vec<SurfaceNetsBuffer> = buffers
+        .iter()
+        .map(|mesh| SurfaceNetsBuffer {
+            positions: mesh.positions.clone(),
+            normals: mesh.normals.clone(),
+            indices: [&mesh.indices[..], &mesh.boundary_indices.concat()].concat(),
+            ..Default::default()
+        })
+        .collect();
+    let buffers: Vec<&SurfaceNetsBuffer> = with_boundary_faces.iter().collect();
+    let mut stitched = weld_chunks(&buffers, offsets, precision);
+
+    // Drop the collapsed triangles, and the repeated ones, whichever corner they start at.
+    let mut seen = HashSet::new();
+    let mut indices = Vec::with_capacity(stitched.indices.len());
+    for tri in stitched.indices.chunks_exact(3) {
+        if tri[0] == tri[1] || tri[1] == tri[2] || tri[2] == tri[0] {
+            continue;
+        }
+        let first = (0..3).min_by_key(|&i| tri[i]).unwrap();
+        if seen.insert([0, 1, 2].map(|i| tri[(first + i) % 3])) {
+            indices.extend_from_slice(tri);
+        }
+    }
+    stitched.indices = indices;
+
+    // Drop the vertices that are no longer used, keeping the others in order.
+    let mut is_used = vec![false; stitched.positions.len()];
+    for &v in stitched.indices.iter() {
+        is_used[v as usize] = true;
+    }
+    let mut new_index = Vec::with_capacity(is_used.len());
+    let mut num_used = 0;
+    for &used in is_used.iter() {
+        new_index.push(num_used);
+        num_used += used as u32;
+    }
+    let keep = |attribute: &mut Vec<[f32; 3]>| {
+        let mut used = is_used.iter();
+        attribute.retain(|_| *used.next().unwrap());
+    };
+    keep(&mut stitched.positions);
+    if !stitched.normals.is_empty() {
+        keep(&mut stitched.normals);
+    }
+    for v in stitched.indices.iter_mut() {
+        *v = new_index[*v as usize];
+    }
+    stitched
+}
+
+/// A parallel version of [`weld_vertices`] that produces an identical mesh.
+///
+/// Instead of a shared hash map, vertices are grouped by sorting them on their quantized position (then original index), which
+/// keeps the grouping independent of thread scheduling.
+///
+/// ```
+/// use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+/// use fast_surface_nets::{par_weld_vertices, surface_nets, weld_vertices, SurfaceNetsBuffer};
+///
+/// type ChunkShape = ConstShape3u32<34, 34, 34>;
+///
+/// let mut sdf = [1.0; ChunkShape::USIZE];
+/// for i in 0u32..ChunkShape::SIZE {
+///     let [x, y, z] = ChunkShape::delinearize(i);
+///     let [x, y, z] = [x as f32 - 16.0, y as f32 - 16.0, z as f32 - 16.0];
+///     sdf[i as usize] = (x * x + y * y + z * z).sqrt() - 12.0;
+/// }
+/// let mut mesh = SurfaceNetsBuffer::default();
+/// surface_nets(&sdf, &ChunkShape {}, [0; 3], [33; 3], &mut mesh);
+///
+/// // Duplicate every vertex so there is plenty to weld.
+/// let num_vertices = mesh.positions.len() as u32;
+/// mesh.positions.extend_from_within(..);
+/// mesh.normals.extend_from_within(..);
+/// let shifted: Vec<u32> = mesh.indices.iter().map(|i| i + num_vertices).collect();
+/// mesh.indices.extend(shifted);
+///
+/// let serial = weld_vertices(&mesh, 0.001);
+/// let parallel = par_weld_vertices(&mesh, 0.001);
+/// assert_eq!(serial.positions.len(), num_vertices as usize);
+/// assert_eq!(parallel.positions, serial.positions);
+/// assert_eq!(parallel.normals, serial.normals);
+/// assert_eq!(parallel.indices, serial.indices);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn par_weld_vertices(mesh: &SurfaceNetsBuffer, precision: f32) -> SurfaceNetsBuffer {
+    use rayon::prelude::*;
+
+    let mut keyed: Vec<([i32; 3], u32)> = mesh
+        .positions
+        .par_iter()
+        .enumerate()
+        .map(|(i, &p)| (quantize(p, precision), i as u32))
+        .collect();
+    keyed.par_sort_unstable();
+
+    // Each group is sorted by original index, so the first member is the representative.
+    let mut groups: Vec<&[([i32; 3], u32)]> = keyed.par_chunk_by(|a, b| a.0 == b.0).collect();
+    groups.par_sort_unstable_by_key(|group| group[0].1);
+
+    let mut remap = vec![0; mesh.positions.len()];
+    for (new_index, group) in groups.iter().enumerate() {
+        for &(_, old_index) in group.iter() {
+            remap[old_index as usize] = new_index as u32;
+        }
+    }
+
+    let positions = groups
+        .par_iter()
+        .map(|group| mesh.positions[group[0].1 as usize])
+        .collect();
+    let normals = if mesh.normals.len() == mesh.positions.len() {
+        groups
+            .par_iter()
+            .map(|group| {
+                let normal_sum = group.iter().fold(Vec3A::ZERO, |sum, &(_, i)| {
+                    sum + Vec3A::from(mesh.normals[i as usize])
+                });
+                normal_sum.normalize_or_zero().into()
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let indices = mesh
+        .indices
+        .par_iter()
+        .map(|&i| remap[i as usize])
+        .collect();
+
+    SurfaceNetsBuffer {
+        positions,
+        normals,
+        indices,
+        ..Default::default()
+    }
+}
+
+fn quantize(p: [f32; 3], precision: f32) -> [i32; 3] {
+    (Vec3A::from(p) / precision).round().as_ivec3().into()
+}
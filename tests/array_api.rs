@@ -0,0 +1,70 @@
+//! Every public item takes and returns plain arrays rather than `glam` types (see the crate docs). Since every module is
+//! re-exported from the crate root, this checks the signatures of all of the public items in `src`, so a new helper can't leak
+//! `Vec3A` into the API by accident.
+
+use std::fs;
+use std::path::Path;
+
+const GLAM_TYPES: &[&str] = &[
+    "Vec2", "Vec3", "Vec3A", "Vec4", "Mat3", "Mat4", "Quat", "Affine3A",
+];
+
+// The signatures of the public items in `source`: each runs from `pub` to the start of the body (or the end of the item), and
+// a field is just its line.
+fn public_signatures(source: &str) -> Vec<String> {
+    let mut signatures = Vec::new();
+    let mut lines = source.lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim_start();
+        if !line.starts_with("pub ") || line.starts_with("pub use ") || line.starts_with("pub mod ")
+        {
+            continue;
+        }
+        let mut signature = line.to_string();
+        let is_field = signature.ends_with(',');
+        while !is_field && !signature.contains('{') && !signature.ends_with(';') {
+            match lines.next() {
+                Some(line) => signature.push_str(line.trim()),
+                None => break,
+            }
+        }
+        signatures.push(signature);
+    }
+    signatures
+}
+
+#[test]
+fn public_items_only_use_arrays() {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let mut leaks = Vec::new();
+    for entry in fs::read_dir(&src).unwrap() {
+        let path = entry.unwrap().path();
+        let source = fs::read_to_string(&path).unwrap();
+        for signature in public_signatures(&source) {
+            let uses_glam = signature
+                .split(|c: char| !c.is_alphanumeric() && c != ':')
+                .any(|token| token.starts_with("glam::") || GLAM_TYPES.contains(&token));
+            if uses_glam {
+                leaks.push(format!("{}: {signature}", path.display()));
+            }
+        }
+    }
+    assert!(
+        leaks.is_empty(),
+        "glam types in the public API:\n{}",
+        leaks.join("\n")
+    );
+}
+
+#[test]
+fn finds_glam_types_in_signatures() {
+    let source = concat!(
+        "pub fn leaky(\n    p: [f32; 3],\n) -> glam::Vec3A {\n}\n",
+        "pub(crate) fn private(p: Vec3A) {}\n",
+        "pub struct S {\n    pub v: Vec3A,\n}\n",
+    );
+    let signatures = public_signatures(source);
+    assert_eq!(signatures.len(), 3);
+    assert!(signatures[0].contains("glam::Vec3A"));
+    assert_eq!(signatures[2], "pub v: Vec3A,");
+}
@@ -0,0 +1,798 @@
+//! Behaviour checks for the `SurfaceNetsConfig` options, on a few shared fixtures.
+
+use std::collections::{HashMap, HashSet};
+
+use fast_surface_nets::glam::Vec3A;
+use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+use fast_surface_nets::{
+    required_padding, sdf, surface_nets_f64, surface_nets_from_cubes, surface_nets_occupancy,
+    surface_nets_with_config, BoundaryCapMode, NormalMode, SurfaceNetsBuffer, SurfaceNetsConfig,
+    UvProjection, VertexPlacement, VertexRelaxation, DEGENERATE_TRIANGLE_AREA,
+};
+
+type ChunkShape = ConstShape3u32<18, 18, 18>;
+
+// Samples `f` at every point of a chunk.
+fn sample(f: impl Fn([f32; 3]) -> f32) -> Vec<f32> {
+    (0..ChunkShape::SIZE)
+        .map(|i| f(ChunkShape::delinearize(i).map(|c| c as f32)))
+        .collect()
+}
+
+// A sphere of `radius` around `center`.
+fn sphere(center: [f32; 3], radius: f32) -> Vec<f32> {
+    sample(|p| {
+        (0..3)
+            .map(|i| (p[i] - center[i]).powi(2))
+            .sum::<f32>()
+            .sqrt()
+            - radius
+    })
+}
+
+// Meshes the extent from `min` to `max` of a chunk.
+fn mesh_extent(
+    sdf: &[f32],
+    min: [u32; 3],
+    max: [u32; 3],
+    config: SurfaceNetsConfig,
+) -> SurfaceNetsBuffer {
+    let mut buffer = SurfaceNetsBuffer::default();
+    surface_nets_with_config(sdf, &ChunkShape {}, min, max, config, &mut buffer);
+    buffer
+}
+
+// Meshes all of a chunk.
+fn mesh(sdf: &[f32], config: SurfaceNetsConfig) -> SurfaceNetsBuffer {
+    mesh_extent(sdf, [0; 3], [17; 3], config)
+}
+
+// An FNV-1a hash of the positions, normals and indices, to compare a mesh against one from an older version.
+fn mesh_hash(mesh: &SurfaceNetsBuffer) -> u64 {
+    let words = mesh
+        .positions
+        .iter()
+        .chain(mesh.normals.iter())
+        .flatten()
+        .map(|c| c.to_bits())
+        .chain(mesh.indices.iter().copied());
+    words.fold(0xcbf29ce484222325, |hash, word| {
+        (hash ^ word as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[test]
+fn default_placement_matches_baseline() {
+    // The octant of a sphere around the origin, meshed before the placement was configurable.
+    let mesh = mesh(&sphere([0.0; 3], 15.0), SurfaceNetsConfig::default());
+    assert_eq!(
+        SurfaceNetsConfig::default().vertex_placement,
+        VertexPlacement::EdgeCentroid
+    );
+    assert_eq!((mesh.positions.len(), mesh.indices.len()), (523, 2880));
+    assert_eq!(mesh_hash(&mesh), 0xe8973ba9e0ee95e7);
+}
+
+#[test]
+fn cube_center_placement() {
+    let config = SurfaceNetsConfig {
+        vertex_placement: VertexPlacement::CubeCenter,
+        ..Default::default()
+    };
+    let mesh = mesh(&sphere([0.0; 3], 15.0), config);
+    assert!(!mesh.positions.is_empty());
+    for (p, v) in mesh.positions.iter().zip(mesh.surface_points.iter()) {
+        assert_eq!(*p, v.map(|c| c as f32 + 0.5));
+    }
+}
+
+// A sphere that gets clipped by the chunk boundaries, meshed with boundary caps.
+fn clipped_sphere(cap_mode: BoundaryCapMode) -> SurfaceNetsBuffer {
+    let config = SurfaceNetsConfig {
+        generate_boundary_faces: true,
+        boundary_cap_mode: cap_mode,
+        ..Default::default()
+    };
+    mesh(&sphere([9.0; 3], 10.0), config)
+}
+
+// The cap vertices come after the surface vertices.
+fn cap_vertices(mesh: &SurfaceNetsBuffer) -> std::ops::Range<usize> {
+    mesh.positions.len() - mesh.num_boundary_vertices as usize..mesh.positions.len()
+}
+
+#[test]
+fn default_cap_vertices_are_face_centers() {
+    let mesh = clipped_sphere(BoundaryCapMode::default());
+    let mut num_checked = 0;
+    for v in cap_vertices(&mesh) {
+        let cube = mesh.surface_points[v];
+        let planes: Vec<usize> = (0..3).filter(|&a| cube[a] == 0 || cube[a] == 16).collect();
+        if let [axis] = planes[..] {
+            let mut center = cube.map(|c| c as f32 + 0.5);
+            center[axis] = if cube[axis] == 0 { 0.0 } else { 17.0 };
+            assert_eq!(mesh.positions[v], center);
+            num_checked += 1;
+        }
+    }
+    assert!(num_checked > 0);
+}
+
+#[test]
+fn face_crossing_caps_are_closer_to_the_surface() {
+    let cap_error = |mesh: &SurfaceNetsBuffer| -> f32 {
+        let sdf = |p: [f32; 3]| (0..3).map(|i| (p[i] - 9.0).powi(2)).sum::<f32>().sqrt() - 10.0;
+        cap_vertices(mesh)
+            .map(|v| sdf(mesh.positions[v]).abs())
+            .sum()
+    };
+    assert!(
+        cap_error(&clipped_sphere(BoundaryCapMode::FaceCrossing))
+            < cap_error(&clipped_sphere(BoundaryCapMode::FaceCenter))
+    );
+}
+
+// Rolling terrain along Y, solid below.
+fn terrain() -> Vec<f32> {
+    sample(|[x, y, z]| y - 8.3 - (0.3 * x).sin() - (0.2 * z).cos())
+}
+
+#[test]
+fn skirts_hang_down_within_boundary_planes() {
+    let sdf = terrain();
+    let surface = mesh(&sdf, SurfaceNetsConfig::default());
+    let config = SurfaceNetsConfig {
+        skirt_depth: Some(2.0),
+        ..Default::default()
+    };
+    let skirted = mesh(&sdf, config);
+
+    // The surface is untouched, and the skirt vertices are appended.
+    let num_surface = surface.positions.len();
+    assert_eq!(skirted.positions[..num_surface], surface.positions[..]);
+    assert!(skirted.positions.len() > num_surface);
+
+    for (skirt, &[x, y, z]) in skirted.surface_points.iter().enumerate().skip(num_surface) {
+        let cube = ConstShape3u32::<17, 17, 17>::linearize([x, y, z]) as usize;
+        let [p, q] = [skirted.cube_to_index[cube] as usize, skirt]
+            .map(|v| Vec3A::from(skirted.positions[v]));
+        let offset = q - p;
+        assert!((offset.length() - 2.0).abs() < 1e-5);
+        assert!(offset.y < 0.0);
+        assert!((x == 0 || x == 16) && offset.x == 0.0 || (z == 0 || z == 16) && offset.z == 0.0);
+    }
+}
+
+#[test]
+fn surface_mask_has_a_bit_per_surface_cube() {
+    let config = SurfaceNetsConfig {
+        generate_surface_mask: true,
+        ..Default::default()
+    };
+    let [min, max] = [[1, 2, 3], [17, 16, 15]];
+    let buffer = mesh_extent(&sphere([0.0; 3], 15.0), min, max, config);
+
+    let size = [0, 1, 2].map(|i| max[i] - min[i]);
+    let mut expected_mask = vec![0u64; (size[0] * size[1] * size[2]).div_ceil(64) as usize];
+    for &[x, y, z] in buffer.surface_points.iter() {
+        let i = ((z - min[2]) * size[1] + (y - min[1])) * size[0] + (x - min[0]);
+        expected_mask[i as usize / 64] |= 1 << (i % 64);
+    }
+    assert!(!buffer.surface_points.is_empty());
+    assert_eq!(buffer.surface_mask, expected_mask);
+}
+
+// A plane with some deterministic noise, at `z = height` on average.
+fn noisy_plane(height: f32) -> Vec<f32> {
+    (0u32..ChunkShape::SIZE)
+        .map(|i| {
+            let [_, _, z] = ChunkShape::delinearize(i);
+            let noise = (i.wrapping_mul(2654435761) >> 16) as f32 / 65536.0 - 0.5;
+            z as f32 - height + 0.4 * noise
+        })
+        .collect()
+}
+
+// The mean squared deviation of the unit normals from +Z.
+fn deviation_from_z(mesh: &SurfaceNetsBuffer) -> f32 {
+    let sum: f32 = mesh
+        .normals
+        .iter()
+        .map(|&n| (Vec3A::from(n).normalize() - Vec3A::Z).length_squared())
+        .sum();
+    sum / mesh.normals.len() as f32
+}
+
+fn with_normal_mode(normal_mode: NormalMode) -> SurfaceNetsConfig {
+    SurfaceNetsConfig {
+        normal_mode,
+        ..Default::default()
+    }
+}
+
+// The mean angle between the normals and the true ones of a sphere around `center`.
+fn mean_angular_error(mesh: &SurfaceNetsBuffer, center: [f32; 3]) -> f32 {
+    let sum: f32 = mesh
+        .positions
+        .iter()
+        .zip(mesh.normals.iter())
+        .map(|(&p, &n)| {
+            let true_normal = (Vec3A::from(p) - Vec3A::from(center)).normalize();
+            Vec3A::from(n).normalize().dot(true_normal).min(1.0).acos()
+        })
+        .sum();
+    sum / mesh.normals.len() as f32
+}
+
+#[test]
+fn smoothed_normals_reduce_noise() {
+    let plane = noisy_plane(8.5);
+    let raw = mesh(&plane, with_normal_mode(NormalMode::RawGradient));
+    let smoothed = mesh(&plane, with_normal_mode(NormalMode::Smoothed));
+    assert!(deviation_from_z(&smoothed) < 0.5 * deviation_from_z(&raw));
+
+    // On a smooth sphere, they agree closely.
+    let sphere = sphere([8.5; 3], 6.0);
+    let raw = mesh(&sphere, with_normal_mode(NormalMode::RawGradient));
+    let smoothed = mesh(&sphere, with_normal_mode(NormalMode::Smoothed));
+    assert_eq!(smoothed.positions, raw.positions);
+    for (&r, &s) in raw.normals.iter().zip(smoothed.normals.iter()) {
+        assert!(Vec3A::from(r).normalize().dot(Vec3A::from(s).normalize()) > 0.99);
+    }
+}
+
+// Counts the edges that belong to a single triangle, i.e. the open boundary of the mesh.
+fn open_edges(mesh: &SurfaceNetsBuffer) -> usize {
+    let mut edge_counts = HashMap::new();
+    for tri in mesh.indices.chunks(3) {
+        for i in 0..3 {
+            let [a, b] = [tri[i], tri[(i + 1) % 3]];
+            *edge_counts.entry([a.min(b), a.max(b)]).or_insert(0) += 1;
+        }
+    }
+    edge_counts.values().filter(|&&count| count == 1).count()
+}
+
+#[test]
+fn wrapping_closes_the_mesh_across_the_period() {
+    // A sphere centered on the periodic boundary at x = 0, with one period of 16 samples along X.
+    let sdf = sample(|[x, y, z]| {
+        let [x, y, z] = [x.min(16.0 - x), y - 4.5, z - 4.5];
+        (x * x + y * y + z * z).sqrt() - 3.5
+    });
+    let mesh = |wrap| {
+        let config = SurfaceNetsConfig {
+            wrap,
+            ..Default::default()
+        };
+        mesh_extent(&sdf, [0; 3], [15, 9, 9], config)
+    };
+
+    // Without wrapping, the sphere is cut into two open halves.
+    assert!(open_edges(&mesh([false; 3])) > 0);
+
+    // With wrapping, the halves are connected into a closed surface.
+    let wrapped = mesh([true, false, false]);
+    assert!(!wrapped.indices.is_empty());
+    assert_eq!(open_edges(&wrapped), 0);
+}
+
+#[test]
+fn safe_quads_match_when_every_cube_has_a_vertex() {
+    let sdf = sphere([8.5; 3], 6.0);
+    let safe = SurfaceNetsConfig {
+        safe_quads: true,
+        ..Default::default()
+    };
+    let mesh_default = mesh(&sdf, SurfaceNetsConfig::default());
+    assert_eq!(mesh(&sdf, safe).indices, mesh_default.indices);
+
+    // The same goes for a sparse set of active cubes.
+    let cubes: Vec<[u32; 3]> = mesh_default
+        .surface_points
+        .iter()
+        .copied()
+        .step_by(3)
+        .collect();
+    let from_cubes = |config| {
+        let mut buffer = SurfaceNetsBuffer::default();
+        surface_nets_from_cubes(
+            &sdf,
+            &ChunkShape {},
+            &cubes,
+            [0; 3],
+            [17; 3],
+            config,
+            &mut buffer,
+        );
+        buffer
+    };
+    let safe_mesh = from_cubes(safe);
+    assert!(!safe_mesh.indices.is_empty());
+    assert_eq!(
+        safe_mesh.indices,
+        from_cubes(SurfaceNetsConfig::default()).indices
+    );
+}
+
+#[test]
+fn central_difference_normals() {
+    let mesh = |sdf: &[f32], normal_stencil_radius| {
+        let config = SurfaceNetsConfig {
+            normal_mode: NormalMode::CentralDifference,
+            normal_stencil_radius,
+            ..Default::default()
+        };
+        // Leave enough padding for the widest stencil.
+        let padding = required_padding(SurfaceNetsConfig {
+            normal_stencil_radius: 2,
+            ..config
+        });
+        assert_eq!(padding, 2);
+        mesh_extent(sdf, [padding; 3], [17 - padding; 3], config)
+    };
+
+    // A wider stencil smooths out the noise.
+    let plane = noisy_plane(8.5);
+    assert!(deviation_from_z(&mesh(&plane, 2)) < deviation_from_z(&mesh(&plane, 1)));
+
+    // On a linear field, every radius gives the exact gradient.
+    let linear = sample(|[x, y, z]| 0.25 * x - 0.5 * y + z - 8.3);
+    for radius in [1, 2] {
+        let normals = mesh(&linear, radius).normals;
+        assert!(!normals.is_empty());
+        for n in normals {
+            assert!(Vec3A::from(n).abs_diff_eq(Vec3A::new(0.25, -0.5, 1.0), 1e-4));
+        }
+    }
+
+    // On a small sphere, the normals are closer to the true ones than the `RawGradient` normals.
+    let small_sphere = sphere([8.3; 3], 3.6);
+    let error = |normal_mode| {
+        let mesh = mesh_extent(
+            &small_sphere,
+            [1; 3],
+            [16; 3],
+            with_normal_mode(normal_mode),
+        );
+        mean_angular_error(&mesh, [8.3; 3])
+    };
+    assert!(error(NormalMode::CentralDifference) < 0.5 * error(NormalMode::RawGradient));
+}
+
+#[test]
+fn central_difference_stencils_stay_in_short_slices() {
+    // The samples end right after the extent, so the stencils of the cubes near its max corner run past the end.
+    let max = [10; 3];
+    let len = ChunkShape::linearize(max) as usize + 1;
+    let sdf = sphere([7.0; 3], 4.0);
+    let config = with_normal_mode(NormalMode::CentralDifference);
+
+    let short = mesh_extent(&sdf[..len], [0; 3], max, config);
+    let full = mesh_extent(&sdf, [0; 3], max, config);
+    assert!(!short.positions.is_empty());
+    assert_eq!(short.positions, full.positions);
+    assert!(short.normals.iter().flatten().all(|c| c.is_finite()));
+
+    let sdf: Vec<f64> = sdf[..len].iter().map(|&d| d as f64).collect();
+    let mut buffer = SurfaceNetsBuffer::default();
+    let mut world_positions = Vec::new();
+    surface_nets_f64(
+        &sdf,
+        &ChunkShape {},
+        [0; 3],
+        max,
+        [0.0; 3],
+        [1.0; 3],
+        config,
+        &mut buffer,
+        &mut world_positions,
+    );
+    assert!(!buffer.positions.is_empty());
+
+    let occupancy: Vec<bool> = sdf.iter().map(|&d| d < 0.0).collect();
+    surface_nets_occupancy(&occupancy, &ChunkShape {}, [0; 3], max, config, &mut buffer);
+    assert!(!buffer.positions.is_empty());
+}
+
+// The area of each triangle.
+fn triangle_areas(mesh: &SurfaceNetsBuffer) -> impl Iterator<Item = f32> + '_ {
+    mesh.indices.chunks(3).map(|tri| {
+        let [a, b, c] = [0, 1, 2].map(|i| Vec3A::from(mesh.positions[tri[i] as usize]));
+        0.5 * (b - a).cross(c - a).length()
+    })
+}
+
+#[test]
+fn vertex_merging_collapses_specks() {
+    // A sphere, and a speck that only covers a single sample.
+    let field = sample(|[x, y, z]| {
+        let sphere = sdf::sphere([x - 6.5, y - 6.5, z - 6.5], 4.0);
+        let speck = sdf::sphere([x - 13.0, y - 13.0, z - 13.0], 0.05);
+        sdf::union(sphere, speck)
+    });
+    let smallest_area =
+        |mesh: &SurfaceNetsBuffer| triangle_areas(mesh).fold(f32::INFINITY, f32::min);
+
+    // By default, the speck is a tiny closed blob.
+    let default = mesh(&field, SurfaceNetsConfig::default());
+    assert!(smallest_area(&default) < 1e-2);
+
+    // Merged, it collapses into a single vertex, and its triangles disappear.
+    let config = SurfaceNetsConfig {
+        vertex_merge_distance: Some(0.25),
+        ..Default::default()
+    };
+    let merged = mesh(&field, config);
+    assert!(merged.positions.len() < default.positions.len());
+    assert!(merged.indices.len() < default.indices.len());
+    assert!(smallest_area(&merged) > 0.1);
+    assert!(merged
+        .indices
+        .chunks(3)
+        .all(|t| t[0] != t[1] && t[1] != t[2] && t[2] != t[0]));
+}
+
+// How far each vertex is from the center of its cube.
+fn offsets_from_cube_centers(mesh: &SurfaceNetsBuffer) -> impl Iterator<Item = f32> + '_ {
+    mesh.positions
+        .iter()
+        .zip(mesh.surface_points.iter())
+        .map(|(&p, cube)| Vec3A::from(p).distance(Vec3A::from(cube.map(|c| c as f32 + 0.5))))
+}
+
+#[test]
+fn max_vertex_offset_clamps_to_the_cube_center() {
+    let sdf = sphere([8.5; 3], 6.0);
+    let default = mesh(&sdf, SurfaceNetsConfig::default());
+    assert!(offsets_from_cube_centers(&default).any(|offset| offset > 0.3));
+
+    let config = SurfaceNetsConfig {
+        max_vertex_offset: Some(0.2),
+        ..Default::default()
+    };
+    let clamped = mesh(&sdf, config);
+    assert!(offsets_from_cube_centers(&clamped).all(|offset| offset <= 0.2 + 1e-5));
+}
+
+#[test]
+fn iso_value_grows_the_surface() {
+    let sdf = sphere([8.5; 3], 5.0);
+    let mesh = |sdf: &[f32], iso_value| {
+        let config = SurfaceNetsConfig {
+            iso_value,
+            ..Default::default()
+        };
+        mesh(sdf, config)
+    };
+    let mean_radius = |mesh: &SurfaceNetsBuffer| {
+        let sum: f32 = mesh
+            .positions
+            .iter()
+            .map(|&p| Vec3A::from(p).distance(Vec3A::splat(8.5)))
+            .sum();
+        sum / mesh.positions.len() as f32
+    };
+
+    // The sphere grows by the iso value.
+    let sphere = mesh(&sdf, 0.0);
+    let grown = mesh(&sdf, 1.5);
+    assert!((mean_radius(&grown) - mean_radius(&sphere) - 1.5).abs() < 0.05);
+
+    // Which is the same as subtracting it from the SDF.
+    let shifted_sdf: Vec<f32> = sdf.iter().map(|d| d - 1.5).collect();
+    let shifted = mesh(&shifted_sdf, 0.0);
+    assert_eq!(grown.positions, shifted.positions);
+    assert_eq!(grown.normals, shifted.normals);
+    assert_eq!(grown.indices, shifted.indices);
+}
+
+#[test]
+fn voxel_size_scales_to_world_units() {
+    // 1 meter voxels in X and Z, and 0.25 meter voxels in Y.
+    type TallShape = ConstShape3u32<18, 66, 18>;
+    let voxel_size = Vec3A::new(1.0, 0.25, 1.0);
+
+    // A sphere with a radius of 6 meters.
+    let center = Vec3A::new(8.5, 8.25, 8.5);
+    let sdf: Vec<f32> = (0..TallShape::SIZE)
+        .map(|i| {
+            let p = Vec3A::from(TallShape::delinearize(i).map(|c| c as f32));
+            (p * voxel_size).distance(center) - 6.0
+        })
+        .collect();
+    let config = SurfaceNetsConfig {
+        voxel_size: Some(voxel_size.into()),
+        ..Default::default()
+    };
+    let mut mesh = SurfaceNetsBuffer::default();
+    surface_nets_with_config(&sdf, &TallShape {}, [0; 3], [17, 65, 17], config, &mut mesh);
+
+    // The positions are in meters, and the normals are perpendicular to the sphere.
+    assert!(!mesh.positions.is_empty());
+    for (&p, &n) in mesh.positions.iter().zip(mesh.normals.iter()) {
+        let radial = Vec3A::from(p) - center;
+        assert!((radial.length() - 6.0).abs() < 0.1);
+        assert!(Vec3A::from(n).normalize().dot(radial.normalize()) > 0.99);
+    }
+}
+
+#[test]
+fn quads_replace_triangle_pairs() {
+    let quads_config = |quads, generate_boundary_faces| SurfaceNetsConfig {
+        quads,
+        generate_boundary_faces,
+        ..Default::default()
+    };
+    let corners = |mesh: &SurfaceNetsBuffer, quad: &[u32]| {
+        [0, 1, 2, 3].map(|i| Vec3A::from(mesh.positions[quad[i] as usize]))
+    };
+
+    // A closed sphere takes 4 indices per face instead of 6.
+    let sdf = sphere([8.5; 3], 6.0);
+    let triangles = mesh(&sdf, quads_config(false, false));
+    let quads = mesh(&sdf, quads_config(true, false));
+    assert!(quads.indices.is_empty());
+    assert_eq!(quads.quad_indices.len() * 3, triangles.indices.len() * 2);
+
+    // Every quad faces out of the sphere, like its triangles.
+    for quad in quads.quad_indices.chunks(4) {
+        let [a, b, c, d] = corners(&quads, quad);
+        assert!((c - a).cross(d - b).dot(a - Vec3A::splat(8.5)) > 0.0);
+    }
+
+    // On a flat surface, every quad is planar.
+    let half_space = sample(|[x, y, z]| z - 0.3 * x - 0.2 * y - 4.1);
+    let quads = mesh(&half_space, quads_config(true, false));
+    assert!(!quads.quad_indices.is_empty());
+    for quad in quads.quad_indices.chunks(4) {
+        let [a, b, c, d] = corners(&quads, quad);
+        let normal = (b - a).cross(c - a);
+        assert!(normal.dot(d - a).abs() < 1e-4 * normal.length());
+    }
+
+    // The boundary faces that close the volume under it are quads too.
+    let triangles = mesh(&half_space, quads_config(false, true));
+    let quads = mesh(&half_space, quads_config(true, true));
+    assert_eq!(quads.quad_indices.len() * 3, triangles.indices.len() * 2);
+}
+
+#[test]
+fn degenerate_triangles_are_skipped() {
+    // A sphere with a void in it that is a single point, right on the sample at its center. The 8 cubes around it all place
+    // their vertex on that sample, so the 6 quads around it collapse into a point.
+    let sdf = sample(|p| {
+        let sphere = sdf::sphere(p.map(|c| c - 8.5), 6.0);
+        let void = sdf::sphere(p.map(|c| c - 8.0), 0.0);
+        sphere.max(-void)
+    });
+    let mesh = |skip_degenerate_triangles| {
+        let config = SurfaceNetsConfig {
+            skip_degenerate_triangles,
+            ..Default::default()
+        };
+        mesh(&sdf, config)
+    };
+
+    let (default, skipped) = (mesh(false), mesh(true));
+    let num_degenerate = triangle_areas(&default)
+        .filter(|&area| area < DEGENERATE_TRIANGLE_AREA)
+        .count();
+    assert_eq!(num_degenerate, 12);
+    assert_eq!(skipped.indices.len(), default.indices.len() - 12 * 3);
+    assert!(triangle_areas(&skipped).all(|area| area >= DEGENERATE_TRIANGLE_AREA));
+}
+
+#[test]
+fn vertices_are_clamped_per_coordinate() {
+    let sdf = sphere([8.5; 3], 6.0);
+    let mesh = |clamp_vertices_to_cube| {
+        let config = SurfaceNetsConfig {
+            clamp_vertices_to_cube,
+            ..Default::default()
+        };
+        mesh(&sdf, config)
+    };
+    // The position of each vertex in its cube.
+    let offsets = |mesh: &SurfaceNetsBuffer| -> Vec<Vec3A> {
+        let vertices = mesh.positions.iter().zip(mesh.surface_points.iter());
+        vertices
+            .map(|(&p, cube)| Vec3A::from(p) - Vec3A::from(cube.map(|c| c as f32)))
+            .collect()
+    };
+
+    let (default, clamped) = (mesh(None), mesh(Some(0.25)));
+    assert_eq!(mesh(Some(0.0)).positions, default.positions);
+    assert!(offsets(&default)
+        .iter()
+        .any(|d| d.cmplt(Vec3A::splat(0.25)).any() || d.cmpgt(Vec3A::splat(0.75)).any()));
+
+    // Each coordinate is clamped on its own. Only the diagonals of some quads can change.
+    for (c, d) in offsets(&clamped).iter().zip(offsets(&default).iter()) {
+        assert!(c.abs_diff_eq(d.clamp(Vec3A::splat(0.25), Vec3A::splat(0.75)), 1e-5));
+    }
+    assert_eq!(clamped.indices.len(), default.indices.len());
+}
+
+// An axis-aligned box, with its faces at 3.5 and 13.5.
+fn box_field() -> Vec<f32> {
+    sample(|p| sdf::cuboid(p.map(|c| c - 8.5), [5.0; 3]))
+}
+
+#[test]
+fn line_indices_cover_every_edge_once() {
+    let field = box_field();
+    let config = SurfaceNetsConfig {
+        generate_line_indices: true,
+        ..Default::default()
+    };
+    let mesh_triangles = mesh(&field, config);
+
+    // Every edge of a triangle is a line, and every line is a different edge.
+    let lines: HashSet<[u32; 2]> = mesh_triangles
+        .line_indices
+        .chunks(2)
+        .map(|l| [l[0].min(l[1]), l[0].max(l[1])])
+        .collect();
+    assert_eq!(lines.len(), mesh_triangles.line_indices.len() / 2);
+    let mut edges = HashSet::new();
+    for tri in mesh_triangles.indices.chunks(3) {
+        for e in 0..3 {
+            let [a, b] = [tri[e], tri[(e + 1) % 3]];
+            assert!(lines.contains(&[a.min(b), a.max(b)]));
+            edges.insert([a.min(b), a.max(b)]);
+        }
+    }
+    assert_eq!(lines.len(), edges.len());
+
+    // A closed mesh of quads has two edges per quad, one less per quad than its triangles.
+    let quads = mesh(
+        &field,
+        SurfaceNetsConfig {
+            quads: true,
+            ..config
+        },
+    );
+    assert_eq!(
+        quads.line_indices.len() / 2,
+        quads.quad_indices.len() / 4 * 2
+    );
+    assert_eq!(
+        quads.line_indices.len() / 2,
+        edges.len() - mesh_triangles.indices.len() / 6
+    );
+}
+
+#[test]
+fn face_normals_point_out_of_the_box_faces() {
+    let config = SurfaceNetsConfig {
+        generate_face_normals: true,
+        ..Default::default()
+    };
+    let mesh = mesh(&box_field(), config);
+    assert_eq!(mesh.face_normals.len(), mesh.indices.len() / 3);
+
+    // Every triangle on a face of the box faces straight out of it.
+    let mut num_faces = [0; 6];
+    for (tri, &n) in mesh.indices.chunks(3).zip(mesh.face_normals.iter()) {
+        let corners = [0, 1, 2].map(|i| mesh.positions[tri[i] as usize]);
+        for axis in 0..3 {
+            for (side, plane) in [(0, 3.5), (1, 13.5)] {
+                if corners.iter().all(|p| p[axis] == plane) {
+                    let mut expected = Vec3A::ZERO;
+                    expected[axis] = if side == 0 { -1.0 } else { 1.0 };
+                    assert!(Vec3A::from(n).abs_diff_eq(expected, 1e-6));
+                    num_faces[2 * axis + side] += 1;
+                }
+            }
+        }
+    }
+    assert!(num_faces.iter().all(|&n| n > 0));
+}
+
+#[test]
+fn uv_projections_on_box_faces() {
+    let field = box_field();
+    let mesh = |projection| {
+        let config = SurfaceNetsConfig {
+            generate_uvs: Some(projection),
+            ..Default::default()
+        };
+        mesh(&field, config)
+    };
+    let (triplanar, cube) = (mesh(UvProjection::Triplanar), mesh(UvProjection::Box));
+    assert_eq!(triplanar.uvs.len(), triplanar.positions.len());
+
+    // On the faces of the box, away from its edges, triplanar takes the other two coordinates, and box projection mirrors
+    // them so the faces on the min sides aren't seen from behind.
+    let inside = |c: f32| 3.5 < c && c < 13.5;
+    let mut num_checked = [0; 6];
+    for (v, &[x, y, z]) in triplanar.positions.iter().enumerate() {
+        let [ix, iy, iz] = [x, y, z].map(inside);
+        let (expected_triplanar, expected_box, face) = match [x, y, z] {
+            [13.5, _, _] if iy && iz => ([z, y], [-z, y], 1),
+            [3.5, _, _] if iy && iz => ([z, y], [z, y], 0),
+            [_, 13.5, _] if ix && iz => ([x, z], [x, -z], 3),
+            [_, 3.5, _] if ix && iz => ([x, z], [x, z], 2),
+            [_, _, 13.5] if ix && iy => ([x, y], [x, y], 5),
+            [_, _, 3.5] if ix && iy => ([x, y], [-x, y], 4),
+            _ => continue,
+        };
+        assert_eq!(triplanar.uvs[v], expected_triplanar);
+        assert_eq!(cube.uvs[v], expected_box);
+        num_checked[face] += 1;
+    }
+    assert!(num_checked.iter().all(|&n| n > 0));
+}
+
+#[test]
+fn relaxation_moves_vertices_onto_the_surface() {
+    let sdf = sphere([8.5; 3], 6.0);
+    let error = |mesh: &SurfaceNetsBuffer| {
+        let sum: f32 = mesh
+            .positions
+            .iter()
+            .map(|&p| (Vec3A::from(p).distance(Vec3A::splat(8.5)) - 6.0).abs())
+            .sum();
+        sum / mesh.positions.len() as f32
+    };
+
+    let default = mesh(&sdf, SurfaceNetsConfig::default());
+    let config = SurfaceNetsConfig {
+        relaxation: Some(VertexRelaxation {
+            iterations: 4,
+            step: 0.5,
+            clamp_to_cube: true,
+        }),
+        ..Default::default()
+    };
+    let relaxed = mesh(&sdf, config);
+    assert!(error(&relaxed) < error(&default));
+
+    // Only the positions moved, and every vertex stayed in its cube.
+    assert_eq!(relaxed.indices, default.indices);
+    for (&p, cube) in relaxed.positions.iter().zip(relaxed.surface_points.iter()) {
+        let cube = Vec3A::from(cube.map(|c| c as f32));
+        let p = Vec3A::from(p);
+        assert!(p.cmpge(cube).all() && p.cmple(cube + Vec3A::ONE).all());
+    }
+}
+
+#[test]
+fn normalized_normals_are_unit_gradients() {
+    let sdf: Vec<f32> = sphere([8.5; 3], 6.0).iter().map(|d| 0.5 * d).collect();
+    let mesh = |normal_mode| {
+        let config = SurfaceNetsConfig {
+            normal_mode,
+            voxel_size: Some([1.0, 2.0, 0.5]),
+            ..Default::default()
+        };
+        mesh(&sdf, config)
+    };
+    let (raw, normalized) = (mesh(NormalMode::RawGradient), mesh(NormalMode::Normalized));
+    assert_eq!(raw.positions, normalized.positions);
+    for (&r, &n) in raw.normals.iter().zip(normalized.normals.iter()) {
+        let n = Vec3A::from(n);
+        assert!((n.length() - 1.0).abs() < 1e-5);
+        assert!(Vec3A::from(r).normalize().abs_diff_eq(n, 1e-5));
+    }
+}
+
+#[test]
+fn area_weighted_normals_follow_the_box_faces() {
+    let config = SurfaceNetsConfig {
+        normal_mode: NormalMode::AreaWeightedFaceAverage,
+        ..Default::default()
+    };
+    let mesh = mesh(&box_field(), config);
+
+    // Away from the edges of the box, every vertex faces straight out of its side.
+    let mut num_checked = 0;
+    for (p, n) in mesh.positions.iter().zip(mesh.normals.iter()) {
+        if p[0] == 13.5 && (5.0..=12.0).contains(&p[1]) && (5.0..=12.0).contains(&p[2]) {
+            assert_eq!(*n, [1.0, 0.0, 0.0]);
+            num_checked += 1;
+        }
+    }
+    assert!(num_checked > 0);
+}
@@ -0,0 +1,79 @@
+//! Checks the `wgpu` mesher against the CPU one. These need a GPU adapter, so they're ignored by default; run them with
+//! `cargo test --features wgpu --test gpu -- --ignored`.
+
+#![cfg(feature = "wgpu")]
+
+use fast_surface_nets::glam::Vec3A;
+use fast_surface_nets::gpu::GpuSurfaceNets;
+use fast_surface_nets::ndshape::{ConstShape, ConstShape3u32};
+use fast_surface_nets::{surface_nets, wgpu, SurfaceNetsBuffer};
+
+type ChunkShape = ConstShape3u32<18, 18, 18>;
+
+// The triangles of `mesh` with their vertices renamed by `rename`, each rotated to start at its smallest vertex (which keeps
+// its winding), and sorted.
+fn triangle_set(mesh: &SurfaceNetsBuffer, rename: impl Fn(u32) -> u32) -> Vec<[u32; 3]> {
+    let mut triangles: Vec<[u32; 3]> = mesh
+        .indices
+        .chunks(3)
+        .map(|tri| {
+            let tri = [0, 1, 2].map(|i| rename(tri[i]));
+            let first = (0..3).min_by_key(|&i| tri[i]).unwrap();
+            [0, 1, 2].map(|i| tri[(first + i) % 3])
+        })
+        .collect();
+    triangles.sort_unstable();
+    triangles
+}
+
+#[test]
+#[ignore = "needs a GPU adapter"]
+fn gpu_mesh_matches_cpu_mesh() {
+    // A sphere off the grid's symmetries, so no quad has two diagonals of the same length.
+    let sdf: Vec<f32> = (0..ChunkShape::SIZE)
+        .map(|i| {
+            let p = Vec3A::from(ChunkShape::delinearize(i).map(|c| c as f32));
+            p.distance(Vec3A::new(8.3, 8.6, 8.45)) - 6.1
+        })
+        .collect();
+
+    let instance = wgpu::Instance::default();
+    let adapter = pollster::block_on(instance.request_adapter(&Default::default()))
+        .expect("no GPU adapter to test on");
+    let (device, queue) = pollster::block_on(adapter.request_device(&Default::default())).unwrap();
+    let gpu = GpuSurfaceNets::new(&device);
+    let gpu_mesh = gpu.mesh(&device, &queue, &sdf, [18; 3], 2000, 12000);
+    let mut mesh = SurfaceNetsBuffer::default();
+    gpu_mesh.read_back(&device, &queue, &mut mesh);
+
+    let mut expected = SurfaceNetsBuffer::default();
+    surface_nets(&sdf, &ChunkShape {}, [0; 3], [17; 3], &mut expected);
+    assert!(!expected.indices.is_empty());
+    assert_eq!(mesh.positions.len(), expected.positions.len());
+
+    // The GPU makes the vertices in no particular order, so match each one to the CPU vertex at the same position.
+    let gpu_to_cpu: Vec<u32> = mesh
+        .positions
+        .iter()
+        .map(|&p| {
+            let (v, distance) = expected
+                .positions
+                .iter()
+                .map(|&q| Vec3A::from(p).distance(Vec3A::from(q)))
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            assert!(distance < 1e-4, "no CPU vertex at {p:?}");
+            v as u32
+        })
+        .collect();
+    let mut matched = gpu_to_cpu.clone();
+    matched.sort_unstable();
+    matched.dedup();
+    assert_eq!(matched.len(), expected.positions.len());
+
+    assert_eq!(
+        triangle_set(&mesh, |v| gpu_to_cpu[v as usize]),
+        triangle_set(&expected, |v| v)
+    );
+}